@@ -12,15 +12,15 @@ use crate::{
     commands::logout::do_logout,
     models::{
         tags_map_from_list, tags_names_from_ids, AlbumListItem, AlbumMediaBody, AlbumMoveMediaBody,
-        AlbumNameBody,
+        AlbumNameBody, MediaListItem, UndoChange,
     },
     tools::{
-        ask_user, do_get_download_request_memory, ensure_login, format_date, identifier_to_string, parse_identifier, parse_vault_uri, print_table, render_media_duration, to_csv_string
+        ask_user, compare_media_list_items, do_get_download_request_memory, ensure_login, format_date, get_media_sort, get_playlist_options, identifier_to_string, is_archive_path, parse_identifier, parse_vault_uri, print_table, render_media_duration, to_csv_string
     },
 };
 
 use super::{
-    get_vault_url, print_request_error, run_cmd_download_album_thumbnail, run_cmd_export_album, run_cmd_import_album, run_cmd_upload_album_thumbnail, CommandGlobalOptions
+    get_vault_url, import_dry_run::run_cmd_import_album_dry_run, media_metadata_fetch::get_sort_upload_times, media_playlist::print_media_playlist, media_export::get_export_encryption, print_request_error, run_cmd_download_album_thumbnail, run_cmd_export_album, run_cmd_import_album, run_cmd_import_album_archive, run_cmd_upload_album_thumbnail, undo::record_undo_entry, CommandGlobalOptions
 };
 
 #[derive(Subcommand)]
@@ -57,6 +57,22 @@ pub enum AlbumCommand {
         /// CSV format
         #[arg(short, long)]
         csv: bool,
+
+        /// Sort the media list on the client side. Can be: title, duration, id, type or upload
+        #[arg(long)]
+        sort: Option<String>,
+
+        /// Sort in descending order. Requires --sort.
+        #[arg(long, requires = "sort")]
+        desc: bool,
+
+        /// Print a playlist of the media list instead, with links to the media assets. Can be: m3u or xspf
+        #[arg(long, conflicts_with = "csv")]
+        format: Option<String>,
+
+        /// Preferred resolutions for the playlist, in order, separated by commas. Example: 720p,480p. The original is used if none of them is available. Requires --format.
+        #[arg(long, requires = "format")]
+        resolution: Option<String>,
     },
 
     /// Downloads the thumbnail of an album
@@ -141,12 +157,44 @@ pub enum AlbumCommand {
         /// Path to the folder to download the files into
         #[arg(short, long)]
         output: Option<String>,
+
+        /// Write an archive instead of a folder. The format is chosen by the extension (.tar or .zip). Use - to write a tar archive to the standard output.
+        #[arg(long, conflicts_with = "output")]
+        archive: Option<String>,
+
+        /// Encrypt the archive with a passphrase, using the age format. The passphrase is asked for, unless the PMV_ARCHIVE_PASSPHRASE environment variable is set.
+        #[arg(long, requires = "archive")]
+        passphrase: bool,
+
+        /// Encrypt the archive for an age public key (age1...), instead of using a passphrase. Can be specified multiple times.
+        #[arg(long, requires = "archive", conflicts_with = "passphrase")]
+        recipient: Vec<String>,
+
+        /// Export format. Can be: folder (default) or html. The html format also writes a browsable offline gallery (index.html) into the export folder, and cannot be used with --archive.
+        #[arg(short, long)]
+        format: Option<String>,
+
+        /// Only export the media assets of the album matching a saved search
+        #[arg(short = 'S', long)]
+        search: Option<String>,
     },
 
     /// Imports an album, expecting a folder with the same format the export command uses.
     Import {
-        /// Path to the folder to import
+        /// Path to the folder or archive (tar or zip) to import. Use - to read a tar archive from the standard input.
         path: String,
+
+        /// Album to import the media into, instead of creating a new album
+        #[arg(long)]
+        into: Option<String>,
+
+        /// Age identity file to decrypt the archive, if it was encrypted for a public key. For passphrase encrypted archives, the passphrase is asked for, unless the PMV_ARCHIVE_PASSPHRASE environment variable is set.
+        #[arg(long)]
+        identity: Option<String>,
+
+        /// Validate the folder and print the planned operations, without changing anything in the vault
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// Optimizes thumbnails of albums, making the loading process faster
@@ -167,8 +215,22 @@ pub async fn run_album_cmd(global_opts: CommandGlobalOptions, cmd: AlbumCommand)
             album,
             extended,
             csv,
+            sort,
+            desc,
+            format,
+            resolution,
         } => {
-            run_cmd_get_album(global_opts, album, csv, extended).await;
+            run_cmd_get_album(
+                global_opts,
+                album,
+                csv,
+                extended,
+                sort,
+                desc,
+                format,
+                resolution,
+            )
+            .await;
         }
         AlbumCommand::Create { name } => {
             run_cmd_album_create(global_opts, name).await;
@@ -205,11 +267,48 @@ pub async fn run_album_cmd(global_opts: CommandGlobalOptions, cmd: AlbumCommand)
         AlbumCommand::OptimizeThumbnails => {
             run_cmd_optimize_albums_thumbnails(global_opts).await;
         }
-        AlbumCommand::Export { album, output } => {
-            run_cmd_export_album(global_opts, album, output).await;
+        AlbumCommand::Export {
+            album,
+            output,
+            archive,
+            passphrase,
+            recipient,
+            format,
+            search,
+        } => {
+            let html = match format.map(|f| f.to_lowercase()).as_deref() {
+                None | Some("folder") => false,
+                Some("html") => true,
+                Some(_) => {
+                    eprintln!("Invalid export format. Can be: folder or html");
+                    process::exit(1);
+                }
+            };
+            if html && archive.is_some() {
+                eprintln!("The html format cannot be used when exporting into an archive");
+                process::exit(1);
+            }
+            let encryption = get_export_encryption(passphrase, recipient).await;
+            run_cmd_export_album(global_opts, album, output, archive, encryption, html, search)
+                .await;
         },
-        AlbumCommand::Import { path } => {
-            run_cmd_import_album(global_opts, path).await;
+        AlbumCommand::Import {
+            path,
+            into,
+            identity,
+            dry_run,
+        } => {
+            if dry_run {
+                run_cmd_import_album_dry_run(path, into, identity).await;
+            } else if is_archive_path(&path) {
+                run_cmd_import_album_archive(global_opts, path, into, identity).await;
+            } else {
+                let import_ok = run_cmd_import_album(global_opts, path, into, None).await;
+
+                if !import_ok {
+                    process::exit(1);
+                }
+            }
         },
     }
 }
@@ -339,9 +438,9 @@ pub async fn run_cmd_list_albums(
             if alphabetically {
                 albums.sort_by(|a, b| a.name.cmp(&b.name));
             } else if id_sorted {
-                albums.sort_by(|a, b| a.id.cmp(&b.id));
+                albums.sort_by_key(|a| a.id);
             } else {
-                albums.sort_by(|a, b| b.lm.cmp(&a.lm));
+                albums.sort_by_key(|a| std::cmp::Reverse(a.lm));
             }
 
             let total = albums.len();
@@ -398,12 +497,33 @@ pub async fn run_cmd_list_albums(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn run_cmd_get_album(
     global_opts: CommandGlobalOptions,
     album: String,
     csv: bool,
     extended: bool,
+    sort: Option<String>,
+    desc: bool,
+    format: Option<String>,
+    resolution: Option<String>,
 ) {
+    let media_sort = match get_media_sort(sort, desc) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
+
+    let playlist_options = match get_playlist_options(format, resolution) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
+
     let url_parse_res = parse_vault_uri(get_vault_url(&global_opts.vault_url));
 
     if url_parse_res.is_err() {
@@ -479,6 +599,43 @@ pub async fn run_cmd_get_album(
 
     match api_res {
         Ok(album_data) => {
+            // Sort, keeping the original positions
+
+            let mut album_items: Vec<(usize, &MediaListItem)> =
+                album_data.list.iter().enumerate().collect();
+
+            if let Some(media_sort) = &media_sort {
+                let media_ids: Vec<u64> = album_data.list.iter().map(|m| m.id).collect();
+                let upload_times = get_sort_upload_times(
+                    &global_opts,
+                    &vault_url,
+                    logout_after_operation,
+                    media_sort,
+                    &media_ids,
+                )
+                .await;
+
+                album_items.sort_by(|a, b| {
+                    compare_media_list_items(a.1, b.1, media_sort, &upload_times)
+                });
+            }
+
+            if let Some(playlist) = &playlist_options {
+                let media_list: Vec<MediaListItem> =
+                    album_items.iter().map(|(_, m)| (*m).clone()).collect();
+
+                print_media_playlist(
+                    &global_opts,
+                    &vault_url,
+                    logout_after_operation,
+                    playlist,
+                    &media_list,
+                )
+                .await;
+
+                return;
+            }
+
             if logout_after_operation {
                 let logout_res = do_logout(&global_opts, &vault_url).await;
 
@@ -509,8 +666,8 @@ pub async fn run_cmd_get_album(
                 if !extended {
                     println!("\"Pos\",\"Id\",\"Type\",\"Title\"");
 
-                    for (i, item) in album_data.list.iter().enumerate() {
-                        let row_pos = i + 1;
+                    for (i, item) in album_items.iter() {
+                        let row_pos = *i + 1;
                         let row_id = item.id.to_string();
                         let row_type = to_csv_string(&item.media_type.to_type_string());
                         let row_title = to_csv_string(&item.title);
@@ -521,8 +678,8 @@ pub async fn run_cmd_get_album(
                         "\"Pos\",\"Id\",\"Type\",\"Title\",\"Description\",\"Tags\",\"Duration\""
                     );
 
-                    for (i, item) in album_data.list.iter().enumerate() {
-                        let row_pos = i + 1;
+                    for (i, item) in album_items.iter() {
+                        let row_pos = *i + 1;
                         let row_id = item.id.to_string();
                         let row_type = to_csv_string(&item.media_type.to_type_string());
                         let row_title = to_csv_string(&item.title);
@@ -544,9 +701,9 @@ pub async fn run_cmd_get_album(
                 ];
                 let mut table_body: Vec<Vec<String>> = Vec::with_capacity(album_size);
 
-                for (i, item) in album_data.list.iter().enumerate() {
+                for (i, item) in album_items.iter() {
                     table_body.push(vec![
-                        (i + 1).to_string(),
+                        (*i + 1).to_string(),
                         identifier_to_string(item.id).clone(),
                         item.media_type.to_type_string(),
                         to_csv_string(&item.title),
@@ -566,9 +723,9 @@ pub async fn run_cmd_get_album(
                 ];
                 let mut table_body: Vec<Vec<String>> = Vec::with_capacity(album_size);
 
-                for (i, item) in album_data.list.iter().enumerate() {
+                for (i, item) in album_items.iter() {
                     table_body.push(vec![
-                        (i + 1).to_string(),
+                        (*i + 1).to_string(),
                         identifier_to_string(item.id).clone(),
                         item.media_type.to_type_string(),
                         to_csv_string(&item.title),
@@ -1073,6 +1230,18 @@ pub async fn run_cmd_album_add_media(
 
     match api_res {
         Ok(_) => {
+            record_undo_entry(
+                &global_opts,
+                &vault_url,
+                &format!("album add #{album_id} #{media_id_param}"),
+                vec![UndoChange::AlbumMedia {
+                    album_id,
+                    media_id: media_id_param,
+                    position: None,
+                }],
+            )
+            .await;
+
             if logout_after_operation {
                 let logout_res = do_logout(&global_opts, &vault_url).await;
 
@@ -1182,6 +1351,7 @@ pub async fn run_cmd_album_remove_media(
 
     let api_get_res = api_call_get_album(&vault_url, album_id, global_opts.debug).await;
     let album_name: String;
+    let mut media_position: usize = 0;
 
     match api_get_res {
         Ok(album_data) => {
@@ -1190,9 +1360,10 @@ pub async fn run_cmd_album_remove_media(
             // Check if the media is in the album
             let mut media_is_in_album = false;
 
-            for m in album_data.list {
+            for (i, m) in album_data.list.iter().enumerate() {
                 if m.id == media_id_param {
                     media_is_in_album = true;
+                    media_position = i;
                     break;
                 }
             }
@@ -1242,6 +1413,18 @@ pub async fn run_cmd_album_remove_media(
 
     match api_res {
         Ok(_) => {
+            record_undo_entry(
+                &global_opts,
+                &vault_url,
+                &format!("album remove #{album_id} #{media_id_param}"),
+                vec![UndoChange::AlbumMedia {
+                    album_id,
+                    media_id: media_id_param,
+                    position: Some(media_position),
+                }],
+            )
+            .await;
+
             if logout_after_operation {
                 let logout_res = do_logout(&global_opts, &vault_url).await;
 
@@ -1397,8 +1580,12 @@ pub async fn run_cmd_album_media_change_position(
     // Get album
 
     let api_get_res = api_call_get_album(&vault_url, album_id, global_opts.debug).await;
+    let media_position: Option<usize>;
     let album_name = match api_get_res {
-        Ok(album_data) => album_data.name,
+        Ok(album_data) => {
+            media_position = album_data.list.iter().position(|m| m.id == media_id_param);
+            album_data.name
+        }
         Err(e) => {
             print_request_error(e);
             if logout_after_operation {
@@ -1430,6 +1617,18 @@ pub async fn run_cmd_album_media_change_position(
 
     match api_res {
         Ok(_) => {
+            record_undo_entry(
+                &global_opts,
+                &vault_url,
+                &format!("album set-position #{album_id} #{media_id_param}"),
+                vec![UndoChange::AlbumMedia {
+                    album_id,
+                    media_id: media_id_param,
+                    position: media_position,
+                }],
+            )
+            .await;
+
             if logout_after_operation {
                 let logout_res = do_logout(&global_opts, &vault_url).await;
 
@@ -1,15 +1,22 @@
 // Album export
 
-use std::process;
+use std::{collections::HashSet, process};
 
 use crate::{
     api::api_call_get_album,
     commands::{
+        batch_operation::{collect_batch_media, BatchMediaFilter},
         logout::do_logout,
-        media_export::{download_media_asset, run_cmd_export_media},
+        media_export::{
+            create_export_output, download_export_asset, export_media, finish_export_output,
+            write_export_html_gallery, MediaExportResult,
+        },
+        search_saved::load_saved_search,
     },
     models::{Album, AlbumMetadataExport},
-    tools::{ask_user, ensure_login, get_extension_from_url, parse_identifier, parse_vault_uri},
+    tools::{
+        ensure_login, get_extension_from_url, parse_identifier, parse_vault_uri, ArchiveEncryption,
+    },
 };
 
 use super::{get_vault_url, print_request_error, CommandGlobalOptions};
@@ -18,7 +25,13 @@ pub async fn run_cmd_export_album(
     global_opts: CommandGlobalOptions,
     album: String,
     output: Option<String>,
+    archive: Option<String>,
+    encryption: Option<ArchiveEncryption>,
+    html: bool,
+    search: Option<String>,
 ) {
+    let search = search.map(|name| load_saved_search(&name));
+
     let url_parse_res = parse_vault_uri(get_vault_url(&global_opts.vault_url));
 
     if url_parse_res.is_err() {
@@ -71,7 +84,7 @@ pub async fn run_cmd_export_album(
 
     let api_get_album_res = api_call_get_album(&vault_url, album_id, global_opts.debug).await;
 
-    let album_metadata: Album = match api_get_album_res {
+    let mut album_metadata: Album = match api_get_album_res {
         Ok(meta) => meta,
         Err(e) => {
             print_request_error(e);
@@ -89,64 +102,50 @@ pub async fn run_cmd_export_album(
         }
     };
 
-    // Output folder
+    // Only keep the media assets matching the saved search, in the album order
 
-    let out_folder: String = match output {
-        Some(o) => o,
-        None => {
-            format!("album_{album_id}")
+    if let Some(saved_search) = search {
+        let filter = BatchMediaFilter {
+            album: Some(album.clone()),
+            ..Default::default()
         }
-    };
-
-    let out_exists = std::path::Path::new(&out_folder).exists();
-
-    if out_exists && !global_opts.auto_confirm {
-        eprintln!("The folder {out_folder} already exists");
-        let confirmation = ask_user("Do you want to overwrite it? y/n: ")
-            .await
-            .unwrap_or("".to_string());
+        .with_saved_search(saved_search);
 
-        if confirmation.to_lowercase() != "y" {
-            if logout_after_operation {
-                let logout_res = do_logout(&global_opts, &vault_url).await;
+        let matching_media: HashSet<u64> =
+            collect_batch_media(&global_opts, &vault_url, logout_after_operation, filter)
+                .await
+                .iter()
+                .map(|m| m.id)
+                .collect();
 
-                match logout_res {
-                    Ok(_) => {}
-                    Err(_) => {
-                        process::exit(1);
-                    }
-                }
-            }
-            process::exit(1);
-        }
+        album_metadata
+            .list
+            .retain(|m| matching_media.contains(&m.id));
     }
 
-    if !out_exists {
-        let create_dir_res = tokio::fs::create_dir(std::path::Path::new(&out_folder)).await;
+    // Output
 
-        match create_dir_res {
-            Ok(_) => {}
-            Err(e) => {
-                let e_str = e.to_string();
-                eprintln!("Could not create the folder {out_folder}. Error: {e_str}");
-                if logout_after_operation {
-                    let logout_res = do_logout(&global_opts, &vault_url).await;
-
-                    match logout_res {
-                        Ok(_) => {}
-                        Err(_) => {
-                            process::exit(1);
-                        }
-                    }
-                }
-                process::exit(1);
-            }
+    let out_folder: String = match output {
+        Some(o) => o,
+        None => {
+            format!("album_{album_id}")
         }
-    }
+    };
+
+    let mut export_output = create_export_output(
+        &global_opts,
+        &vault_url,
+        logout_after_operation,
+        &out_folder,
+        &archive,
+        encryption.as_ref(),
+    )
+    .await;
 
     // Metadata
 
     let mut out_metadata = AlbumMetadataExport {
+        id: Some(album_id),
         name: album_metadata.name,
         media_list: album_metadata
             .list
@@ -163,43 +162,47 @@ pub async fn run_cmd_export_album(
             let ext = get_extension_from_url(&album_thumbnail, "jpg");
             let out_file_name = "thumbnail".to_owned() + "." + &ext;
 
-            let thumbnail_out_path = std::path::Path::new(&out_folder)
-                .join(&out_file_name)
-                .to_str()
-                .unwrap()
-                .to_string();
-
-            download_media_asset(
-                global_opts.clone(),
+            let download_ok = download_export_asset(
+                &global_opts,
                 &vault_url,
+                &mut export_output,
+                "",
+                &out_file_name,
                 "thumbnail",
                 album_thumbnail,
-                thumbnail_out_path,
-                logout_after_operation,
             )
             .await;
 
+            if !download_ok {
+                if logout_after_operation {
+                    let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                process::exit(1);
+            }
+
             out_metadata.thumbnail = Some(out_file_name);
         }
     }
 
     // Write metadata
 
-    let metadata_file = "metadata.json".to_string();
-    let metadata_out_path = std::path::Path::new(&out_folder)
-        .join(&metadata_file)
-        .to_str()
-        .unwrap()
-        .to_string();
-
     let metadata_str = serde_json::to_string(&out_metadata).unwrap();
 
-    let meta_write_res = tokio::fs::write(metadata_out_path.clone(), metadata_str).await;
+    let meta_write_res = export_output
+        .write_file("", "metadata.json", metadata_str.as_bytes())
+        .await;
 
     match meta_write_res {
         Ok(_) => {}
-        Err(e) => {
-            let e_str = e.to_string();
+        Err(e_str) => {
+            let metadata_out_path = export_output.get_file_path("", "metadata.json");
             eprintln!("Could not write metadata file: {metadata_out_path}. Error: {e_str}");
             if logout_after_operation {
                 let logout_res = do_logout(&global_opts, &vault_url).await;
@@ -220,21 +223,69 @@ pub async fn run_cmd_export_album(
     for media_item in album_metadata.list {
         let media_folder = ("media_".to_string() + &media_item.id.to_string()).to_string();
 
-        let media_out_path = std::path::Path::new(&out_folder)
-            .join(&media_folder)
-            .to_str()
-            .unwrap()
-            .to_string();
-
-        run_cmd_export_media(
-            global_opts.clone(),
-            media_item.id.to_string(),
-            Some(media_out_path),
-            true,
+        let export_res = export_media(
+            &global_opts,
+            &vault_url,
+            media_item.id,
+            &mut export_output,
+            &media_folder,
+        )
+        .await;
+
+        if export_res == MediaExportResult::Failed {
+            if logout_after_operation {
+                let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                match logout_res {
+                    Ok(_) => {}
+                    Err(_) => {
+                        process::exit(1);
+                    }
+                }
+            }
+            process::exit(1);
+        }
+    }
+
+    // HTML gallery
+
+    if html {
+        write_export_html_gallery(
+            &global_opts,
+            &vault_url,
+            logout_after_operation,
+            &out_folder,
+            &out_metadata.name,
+            &out_metadata.media_list,
         )
         .await;
     }
 
+    // Manifest
+
+    if let Err(e_str) = export_output.write_manifest("") {
+        eprintln!("Could not write the manifest file into {out_folder}. Error: {e_str}");
+        if logout_after_operation {
+            let logout_res = do_logout(&global_opts, &vault_url).await;
+
+            match logout_res {
+                Ok(_) => {}
+                Err(_) => {
+                    process::exit(1);
+                }
+            }
+        }
+        process::exit(1);
+    }
+
+    finish_export_output(
+        &global_opts,
+        &vault_url,
+        logout_after_operation,
+        export_output,
+    )
+    .await;
+
     // Done
 
     if logout_after_operation {
@@ -247,5 +298,12 @@ pub async fn run_cmd_export_album(
             }
         }
     }
-    eprintln!("Done. Successfully exported album into folder {out_folder}");
+    match archive {
+        Some(archive_path) => {
+            eprintln!("Done. Successfully exported album into archive {archive_path}");
+        }
+        None => {
+            eprintln!("Done. Successfully exported album into folder {out_folder}");
+        }
+    }
 }
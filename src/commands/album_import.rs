@@ -1,22 +1,69 @@
-// Media export command
+// Album import command
 
 use std::{
+    collections::{HashMap, HashSet},
     process,
     sync::{Arc, Mutex},
 };
 
 use crate::{
-    api::{api_call_album_change_thumbnail, api_call_create_album},
-    commands::{logout::do_logout, media_import::run_cmd_import_media},
-    models::{AlbumMetadataExport, AlbumNameBody},
-    tools::{ensure_login, parse_vault_uri},
+    api::{
+        api_call_album_add_media, api_call_album_change_thumbnail, api_call_create_album,
+        api_call_get_album,
+    },
+    commands::{
+        logout::do_logout,
+        media_import::{
+            extract_import_archive, finalize_media_import, read_media_import_metadata,
+            upload_media_import,
+        },
+    },
+    models::{AlbumImportJournal, AlbumMediaBody, AlbumMetadataExport, AlbumNameBody},
+    tools::{
+        ensure_login, identifier_to_string, parse_identifier, parse_vault_uri,
+        remove_temp_folder, to_csv_string, VaultURI, ARCHIVE_STDIO,
+    },
 };
 
 use super::{
     get_vault_url, media_upload::UploaderProgressPrinter, print_request_error, CommandGlobalOptions,
 };
 
-pub async fn run_cmd_import_album(global_opts: CommandGlobalOptions, path: String) {
+/// Imports an album from an archive (tar or zip) created by the export command
+/// The journal is stored next to the archive, since the extracted folder is temporary.
+pub async fn run_cmd_import_album_archive(
+    global_opts: CommandGlobalOptions,
+    path: String,
+    into: Option<String>,
+    identity: Option<String>,
+) {
+    let journal_file = if path == ARCHIVE_STDIO {
+        "import_journal.json".to_string()
+    } else {
+        path.clone() + ".import_journal.json"
+    };
+
+    let (temp_folder, import_folder) = extract_import_archive(&path, identity).await;
+
+    let import_ok =
+        run_cmd_import_album(global_opts, import_folder, into, Some(journal_file)).await;
+
+    remove_temp_folder(&temp_folder).await;
+
+    if !import_ok {
+        process::exit(1);
+    }
+}
+
+/// Imports an album from a folder created by the export command
+/// Returns false if the import failed. Errors are printed instead of exiting,
+/// so the callers can clean up before.
+pub async fn run_cmd_import_album(
+    global_opts: CommandGlobalOptions,
+    path: String,
+    into: Option<String>,
+    journal: Option<String>,
+) -> bool {
     let url_parse_res = parse_vault_uri(get_vault_url(&global_opts.vault_url));
 
     if url_parse_res.is_err() {
@@ -30,7 +77,7 @@ pub async fn run_cmd_import_album(global_opts: CommandGlobalOptions, path: Strin
             }
         }
 
-        process::exit(1);
+        return false;
     }
 
     let mut vault_url = url_parse_res.unwrap();
@@ -39,11 +86,27 @@ pub async fn run_cmd_import_album(global_opts: CommandGlobalOptions, path: Strin
     let login_result = ensure_login(&vault_url, &None, global_opts.debug).await;
 
     if login_result.is_err() {
-        process::exit(1);
+        return false;
     }
 
     vault_url = login_result.unwrap();
 
+    // Params
+
+    let into_album: Option<u64> = match into {
+        Some(into_str) => match parse_identifier(&into_str) {
+            Ok(id) => Some(id),
+            Err(_) => {
+                eprintln!("Invalid album identifier specified.");
+                if logout_after_operation {
+                    let _ = do_logout(&global_opts, &vault_url).await;
+                }
+                return false;
+            }
+        },
+        None => None,
+    };
+
     // Metadata file
 
     let metadata_file = std::path::Path::new(&path)
@@ -68,16 +131,9 @@ pub async fn run_cmd_import_album(global_opts: CommandGlobalOptions, path: Strin
                     let e_str = e.to_string();
                     eprintln!("Could not read metadata file. Error: {e_str}");
                     if logout_after_operation {
-                        let logout_res = do_logout(&global_opts, &vault_url).await;
-
-                        match logout_res {
-                            Ok(_) => {}
-                            Err(_) => {
-                                process::exit(1);
-                            }
-                        }
+                        let _ = do_logout(&global_opts, &vault_url).await;
                     }
-                    process::exit(1);
+                    return false;
                 }
             }
         }
@@ -85,107 +141,344 @@ pub async fn run_cmd_import_album(global_opts: CommandGlobalOptions, path: Strin
             let e_str = e.to_string();
             eprintln!("Could not read metadata file. Error: {e_str}");
             if logout_after_operation {
-                let logout_res = do_logout(&global_opts, &vault_url).await;
+                let _ = do_logout(&global_opts, &vault_url).await;
+            }
+            return false;
+        }
+    }
+
+    // Journal
+
+    let journal_file: String = match journal {
+        Some(j) => j,
+        None => std::path::Path::new(&path)
+            .join("import_journal.json")
+            .to_str()
+            .unwrap()
+            .to_string(),
+    };
+
+    let mut import_journal = AlbumImportJournal {
+        vault: vault_url.to_base_url(),
+        source_album: import_metadata.id,
+        album: None,
+        media: HashMap::new(),
+        pending_media: HashSet::new(),
+    };
+
+    if std::path::Path::new(&journal_file).exists() {
+        let journal_file_res = tokio::fs::read_to_string(&journal_file).await;
+
+        let parsed_journal: Result<AlbumImportJournal, String> = match journal_file_res {
+            Ok(journal_str) => serde_json::from_str(&journal_str).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+
+        match parsed_journal {
+            Ok(j) => {
+                if j.vault != import_journal.vault {
+                    let vault = &j.vault;
+                    eprintln!("The journal {journal_file} belongs to a different vault: {vault}");
+                    if logout_after_operation {
+                        let _ = do_logout(&global_opts, &vault_url).await;
+                    }
+                    return false;
+                }
 
-                match logout_res {
-                    Ok(_) => {}
-                    Err(_) => {
-                        process::exit(1);
+                if j.source_album != import_journal.source_album {
+                    let source_album = match j.source_album {
+                        Some(id) => identifier_to_string(id),
+                        None => "(unknown)".to_string(),
+                    };
+                    eprintln!(
+                        "The journal {journal_file} belongs to a different album: {source_album}"
+                    );
+                    if logout_after_operation {
+                        let _ = do_logout(&global_opts, &vault_url).await;
                     }
+                    return false;
                 }
+
+                import_journal = j;
+
+                let imported_count = import_journal
+                    .media
+                    .keys()
+                    .filter(|k| !import_journal.pending_media.contains(*k))
+                    .count();
+                eprintln!("Resuming import. Found {imported_count} media assets already imported in {journal_file}");
+            }
+            Err(e_str) => {
+                eprintln!("Could not read journal file: {journal_file}. Error: {e_str}");
+                if logout_after_operation {
+                    let _ = do_logout(&global_opts, &vault_url).await;
+                }
+                return false;
             }
-            process::exit(1);
         }
     }
 
-    // Create album
+    // Find the album to import into (the one specified, or the one created by a previous run)
 
-    let album_id: u64;
     let album_name = import_metadata.name;
+    let mut album_list: Option<Vec<u64>> = None;
 
-    let api_res = api_call_create_album(
-        &vault_url,
-        AlbumNameBody {
-            name: album_name.clone(),
-        },
-        global_opts.debug,
-    )
-    .await;
+    if let Some(album_id) = into_album.or(import_journal.album) {
+        let api_res = api_call_get_album(&vault_url, album_id, global_opts.debug).await;
 
-    match api_res {
-        Ok(added_album) => {
-            album_id = added_album.album_id;
+        match api_res {
+            Ok(album_data) => {
+                album_list = Some(album_data.list.iter().map(|m| m.id).collect());
+                import_journal.album = Some(album_id);
+
+                let journal_ok = write_album_import_journal(
+                    &global_opts,
+                    &vault_url,
+                    logout_after_operation,
+                    &journal_file,
+                    &import_journal,
+                )
+                .await;
+
+                if !journal_ok {
+                    return false;
+                }
+            }
+            Err(e) => {
+                if into_album.is_some() {
+                    print_request_error(e);
+                    if logout_after_operation {
+                        let _ = do_logout(&global_opts, &vault_url).await;
+                    }
+                    return false;
+                }
 
-            eprintln!("Successfully created album #{album_id}: {album_name}");
+                eprintln!("Album #{album_id} could not be found. It will be created again.");
+            }
         }
-        Err(e) => {
-            print_request_error(e);
-            if logout_after_operation {
-                let logout_res = do_logout(&global_opts, &vault_url).await;
+    }
+
+    // Create album
 
-                match logout_res {
-                    Ok(_) => {}
-                    Err(_) => {
-                        process::exit(1);
+    let album_id: u64 = match album_list {
+        Some(_) => import_journal.album.unwrap(),
+        None => {
+            let api_res = api_call_create_album(
+                &vault_url,
+                AlbumNameBody {
+                    name: album_name.clone(),
+                },
+                global_opts.debug,
+            )
+            .await;
+
+            let new_album_id: u64 = match api_res {
+                Ok(added_album) => {
+                    let new_album_id = added_album.album_id;
+
+                    eprintln!("Successfully created album #{new_album_id}: {album_name}");
+
+                    new_album_id
+                }
+                Err(e) => {
+                    print_request_error(e);
+                    if logout_after_operation {
+                        let _ = do_logout(&global_opts, &vault_url).await;
                     }
+                    return false;
                 }
+            };
+
+            import_journal.album = Some(new_album_id);
+            let journal_ok = write_album_import_journal(
+                &global_opts,
+                &vault_url,
+                logout_after_operation,
+                &journal_file,
+                &import_journal,
+            )
+            .await;
+
+            if !journal_ok {
+                return false;
             }
-            process::exit(1);
-        }
-    }
 
-    // Set thumbnail
+            // Set thumbnail
 
-    if let Some(thumbnail_file) = import_metadata.thumbnail {
-        let thumbnail_file_path = std::path::Path::new(&path)
-            .join(&thumbnail_file)
-            .to_str()
-            .unwrap()
-            .to_string();
+            if let Some(thumbnail_file) = &import_metadata.thumbnail {
+                let thumbnail_file_path = std::path::Path::new(&path)
+                    .join(thumbnail_file)
+                    .to_str()
+                    .unwrap()
+                    .to_string();
 
-        let progress_printer = Arc::new(Mutex::new(UploaderProgressPrinter::new()));
+                let progress_printer = Arc::new(Mutex::new(UploaderProgressPrinter::new()));
 
-        let api_res = api_call_album_change_thumbnail(
-            &vault_url,
-            album_id,
-            thumbnail_file_path.clone(),
-            global_opts.debug,
-            progress_printer,
-        )
-        .await;
+                let api_res = api_call_album_change_thumbnail(
+                    &vault_url,
+                    new_album_id,
+                    thumbnail_file_path.clone(),
+                    global_opts.debug,
+                    progress_printer,
+                )
+                .await;
 
-        match api_res {
-            Ok(upload_res) => {
-                eprintln!("Upload completed: {thumbnail_file_path}");
+                match api_res {
+                    Ok(upload_res) => {
+                        eprintln!("Upload completed: {thumbnail_file_path}");
 
-                let thumb_new_url = upload_res.url;
+                        let thumb_new_url = upload_res.url;
 
-                eprintln!(
-                    "Successfully updated the thumbnail of album #{album_id}: {thumb_new_url}"
-                );
-            }
-            Err(e) => {
-                print_request_error(e);
+                        eprintln!(
+                            "Successfully updated the thumbnail of album #{new_album_id}: {thumb_new_url}"
+                        );
+                    }
+                    Err(e) => {
+                        print_request_error(e);
+                    }
+                }
             }
+
+            new_album_id
         }
-    }
+    };
+
+    let mut album_list = album_list.unwrap_or_default();
 
     // Import media
 
-    for media_folder in import_metadata.media_list {
+    let n_total = import_metadata.media_list.len();
+    let mut n_failed: usize = 0;
+
+    for (i, media_folder) in import_metadata.media_list.iter().enumerate() {
+        let n_done = i + 1;
+
+        let uploaded_id = import_journal.media.get(media_folder).copied();
+
+        if let Some(media_id) = uploaded_id {
+            if !import_journal.pending_media.contains(media_folder) {
+                let media_id_str = identifier_to_string(media_id);
+
+                if album_list.contains(&media_id) {
+                    eprintln!("[{n_done}/{n_total}] Skipped {media_folder}: Already imported as {media_id_str}");
+                    continue;
+                }
+
+                if add_album_import_media(&global_opts, &vault_url, album_id, media_id).await {
+                    album_list.push(media_id);
+                    eprintln!("[{n_done}/{n_total}] {media_folder} was already imported as {media_id_str}. Added it into album #{album_id}");
+                } else {
+                    n_failed += 1;
+                }
+
+                continue;
+            }
+        }
+
+        eprintln!("[{n_done}/{n_total}] Importing {media_folder}...");
+
         let media_path = std::path::Path::new(&path)
-            .join(&media_folder)
+            .join(media_folder)
             .to_str()
             .unwrap()
             .to_string();
 
-        run_cmd_import_media(
-            global_opts.clone(),
-            media_path,
-            Some(album_id.to_string()),
-            true,
+        let media_metadata = match read_media_import_metadata(&media_path).await {
+            Ok(m) => m,
+            Err(e_str) => {
+                n_failed += 1;
+                eprintln!("Error: Could not import {media_folder}: {e_str}");
+                continue;
+            }
+        };
+
+        // The media asset is recorded as soon as it is uploaded,
+        // so resuming finishes its metadata instead of uploading it again
+
+        let media_id: u64 = match uploaded_id {
+            Some(id) => {
+                let id_str = identifier_to_string(id);
+                eprintln!("Resuming {media_folder}: Already uploaded as {id_str}");
+
+                if !album_list.contains(&id) {
+                    if !add_album_import_media(&global_opts, &vault_url, album_id, id).await {
+                        n_failed += 1;
+                        continue;
+                    }
+
+                    album_list.push(id);
+                }
+
+                id
+            }
+            None => {
+                let upload_res = upload_media_import(
+                    &global_opts,
+                    &vault_url,
+                    &media_path,
+                    &media_metadata,
+                    Some(album_id),
+                )
+                .await;
+
+                match upload_res {
+                    Some(id) => {
+                        album_list.push(id);
+                        import_journal.media.insert(media_folder.clone(), id);
+                        import_journal.pending_media.insert(media_folder.clone());
+
+                        let journal_ok = write_album_import_journal(
+                            &global_opts,
+                            &vault_url,
+                            logout_after_operation,
+                            &journal_file,
+                            &import_journal,
+                        )
+                        .await;
+
+                        if !journal_ok {
+                            return false;
+                        }
+
+                        id
+                    }
+                    None => {
+                        n_failed += 1;
+                        eprintln!("Error: Could not import {media_folder}");
+                        continue;
+                    }
+                }
+            }
+        };
+
+        let finalize_res = finalize_media_import(
+            &global_opts,
+            &vault_url,
+            &media_path,
+            media_id,
+            &media_metadata,
         )
         .await;
+
+        if finalize_res {
+            import_journal.pending_media.remove(media_folder);
+
+            let journal_ok = write_album_import_journal(
+                &global_opts,
+                &vault_url,
+                logout_after_operation,
+                &journal_file,
+                &import_journal,
+            )
+            .await;
+
+            if !journal_ok {
+                return false;
+            }
+        } else {
+            n_failed += 1;
+            eprintln!("Error: Could not import {media_folder}");
+        }
     }
 
     // Done
@@ -193,13 +486,65 @@ pub async fn run_cmd_import_album(global_opts: CommandGlobalOptions, path: Strin
     if logout_after_operation {
         let logout_res = do_logout(&global_opts, &vault_url).await;
 
-        match logout_res {
-            Ok(_) => {}
-            Err(_) => {
-                process::exit(1);
-            }
+        if logout_res.is_err() {
+            return false;
+        }
+    }
+
+    if n_failed > 0 {
+        eprintln!("Import finished with errors. {n_failed} media assets could not be imported. Run the command again to retry them.");
+        return false;
+    }
+
+    let album_name_csv = to_csv_string(&album_name);
+    eprintln!("Done. Successfully imported album #{album_id} - {album_name_csv}");
+
+    true
+}
+
+/// Adds an already imported media asset into the album, printing the error on failure
+async fn add_album_import_media(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    album_id: u64,
+    media_id: u64,
+) -> bool {
+    let api_res = api_call_album_add_media(
+        vault_url,
+        album_id,
+        AlbumMediaBody { media_id },
+        global_opts.debug,
+    )
+    .await;
+
+    match api_res {
+        Ok(_) => true,
+        Err(e) => {
+            print_request_error(e);
+            false
+        }
+    }
+}
+
+async fn write_album_import_journal(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    logout_after_operation: bool,
+    journal_file: &str,
+    import_journal: &AlbumImportJournal,
+) -> bool {
+    let journal_str = serde_json::to_string(import_journal).unwrap();
+
+    let write_res = tokio::fs::write(journal_file, journal_str).await;
+
+    if let Err(e) = write_res {
+        let e_str = e.to_string();
+        eprintln!("Could not write journal file: {journal_file}. Error: {e_str}");
+        if logout_after_operation {
+            let _ = do_logout(global_opts, vault_url).await;
         }
+        return false;
     }
 
-    eprintln!("Done. Successfully imported album #{album_id} - {album_name}");
+    true
 }
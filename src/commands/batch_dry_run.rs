@@ -0,0 +1,230 @@
+// Dry run of batch operations
+
+use std::{collections::HashSet, process};
+
+use crate::{
+    api::{api_call_get_album, api_call_get_tags},
+    models::{
+        parse_tag_name, tags_reverse_map_from_list, BatchPlan, BatchPlanEntry, MediaListItem,
+    },
+    tools::{identifier_to_string, parse_identifier, print_table, to_csv_string, VaultURI},
+};
+
+use super::{
+    batch_operation::BatchCommand, logout::do_logout, print_request_error, CommandGlobalOptions,
+};
+
+/// Prints the changes a batch operation would apply, without applying them
+pub async fn run_batch_dry_run(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    logout_after_operation: bool,
+    media_list: &[MediaListItem],
+    batch_command: &BatchCommand,
+    json: bool,
+) {
+    let plan = get_batch_plan(
+        global_opts,
+        vault_url,
+        logout_after_operation,
+        media_list,
+        batch_command,
+    )
+    .await;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&plan).unwrap());
+    } else {
+        let table_head: Vec<String> = vec![
+            "Id".to_string(),
+            "Type".to_string(),
+            "Title".to_string(),
+            "Changes".to_string(),
+        ];
+
+        let table_body: Vec<Vec<String>> = plan
+            .media
+            .iter()
+            .map(|entry| {
+                vec![
+                    identifier_to_string(entry.id),
+                    entry.media_type.clone(),
+                    to_csv_string(&entry.title),
+                    entry.to_changes_string(),
+                ]
+            })
+            .collect();
+
+        print_table(&table_head, &table_body, false);
+    }
+
+    let changed = plan.changed;
+    let matched = plan.matched;
+    eprintln!(
+        "Dry run: {changed} of {matched} media assets would change. No changes were applied."
+    );
+}
+
+/// Computes the changes a batch operation would apply to each media asset
+pub async fn get_batch_plan(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    logout_after_operation: bool,
+    media_list: &[MediaListItem],
+    batch_command: &BatchCommand,
+) -> BatchPlan {
+    let mut entries: Vec<BatchPlanEntry> = media_list
+        .iter()
+        .map(|media| BatchPlanEntry {
+            id: media.id,
+            media_type: media.media_type.to_type_string(),
+            title: media.title.clone(),
+            ..Default::default()
+        })
+        .collect();
+
+    match batch_command {
+        BatchCommand::AddTags { tags } | BatchCommand::RemoveTags { tags } => {
+            let adding = matches!(batch_command, BatchCommand::AddTags { .. });
+
+            let tags_res = api_call_get_tags(vault_url, global_opts.debug).await;
+
+            let tags_reverse_map = match tags_res {
+                Ok(tags_vec) => tags_reverse_map_from_list(&tags_vec),
+                Err(e) => {
+                    print_request_error(e);
+                    if logout_after_operation {
+                        let logout_res = do_logout(global_opts, vault_url).await;
+
+                        match logout_res {
+                            Ok(_) => {}
+                            Err(_) => {
+                                process::exit(1);
+                            }
+                        }
+                    }
+                    process::exit(1);
+                }
+            };
+
+            let mut tag_names: Vec<String> = Vec::new();
+
+            for tag_name in tags.split(' ') {
+                let parsed_tag_name = parse_tag_name(tag_name);
+                if parsed_tag_name.is_empty() {
+                    continue;
+                }
+
+                if !adding && !tags_reverse_map.contains_key(&parsed_tag_name) {
+                    if logout_after_operation {
+                        let logout_res = do_logout(global_opts, vault_url).await;
+
+                        match logout_res {
+                            Ok(_) => {}
+                            Err(_) => {
+                                process::exit(1);
+                            }
+                        }
+                    }
+                    eprintln!("Could not find tag with name: {tag_name}");
+                    process::exit(1);
+                }
+
+                tag_names.push(parsed_tag_name);
+            }
+
+            if tag_names.is_empty() {
+                if logout_after_operation {
+                    let logout_res = do_logout(global_opts, vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                eprintln!("Error: Tag list is empty.");
+                process::exit(1);
+            }
+
+            for (entry, media) in entries.iter_mut().zip(media_list.iter()) {
+                for tag_name in tag_names.iter() {
+                    let has_tag = match tags_reverse_map.get(tag_name) {
+                        Some(tag_id) => media.tags.contains(tag_id),
+                        None => false,
+                    };
+
+                    if adding && !has_tag {
+                        entry.add_tags.push(tag_name.clone());
+                    } else if !adding && has_tag {
+                        entry.remove_tags.push(tag_name.clone());
+                    }
+                }
+            }
+        }
+        BatchCommand::AddToAlbum { album } | BatchCommand::RemoveFromAlbum { album } => {
+            let adding = matches!(batch_command, BatchCommand::AddToAlbum { .. });
+
+            let album_id = match parse_identifier(album) {
+                Ok(id) => id,
+                Err(_) => {
+                    if logout_after_operation {
+                        let logout_res = do_logout(global_opts, vault_url).await;
+
+                        match logout_res {
+                            Ok(_) => {}
+                            Err(_) => {
+                                process::exit(1);
+                            }
+                        }
+                    }
+                    eprintln!("Invalid album identifier specified.");
+                    process::exit(1);
+                }
+            };
+
+            let api_res = api_call_get_album(vault_url, album_id, global_opts.debug).await;
+
+            let album_media: HashSet<u64> = match api_res {
+                Ok(album_data) => album_data.list.iter().map(|m| m.id).collect(),
+                Err(e) => {
+                    print_request_error(e);
+                    if logout_after_operation {
+                        let logout_res = do_logout(global_opts, vault_url).await;
+
+                        match logout_res {
+                            Ok(_) => {}
+                            Err(_) => {
+                                process::exit(1);
+                            }
+                        }
+                    }
+                    process::exit(1);
+                }
+            };
+
+            for entry in entries.iter_mut() {
+                let in_album = album_media.contains(&entry.id);
+
+                if adding && !in_album {
+                    entry.add_to_album = Some(album_id);
+                } else if !adding && in_album {
+                    entry.remove_from_album = Some(album_id);
+                }
+            }
+        }
+        BatchCommand::Delete => {
+            for entry in entries.iter_mut() {
+                entry.delete = true;
+            }
+        }
+    }
+
+    BatchPlan {
+        operation: batch_command.name(),
+        matched: entries.len(),
+        changed: entries.iter().filter(|e| e.has_changes()).count(),
+        media: entries,
+    }
+}
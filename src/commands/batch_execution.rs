@@ -0,0 +1,184 @@
+// Execution of batch operations
+
+use std::{collections::HashSet, future::Future, process};
+
+use tokio::task::JoinSet;
+
+use crate::{
+    models::{MediaListItem, UndoChange},
+    tools::{identifier_to_string, to_csv_string, BatchJournal, RequestError, VaultURI},
+};
+
+use super::{
+    logout::do_logout, print_request_error, undo::record_undo_entry, CommandGlobalOptions,
+};
+
+/// Options to execute a batch operation
+pub struct BatchExecutionOptions {
+    /// Max number of media assets to process at the same time
+    pub concurrency: usize,
+
+    /// True to continue with the rest of the media assets after an error
+    pub continue_on_error: bool,
+
+    /// Path to the journal file
+    pub journal: Option<String>,
+}
+
+/// Entry to record in the undo journal for a batch operation
+pub struct BatchUndoEntry {
+    /// Description of the operation
+    pub operation: String,
+
+    /// Previous state of every media asset the operation changes
+    pub changes: Vec<UndoChange>,
+}
+
+/// Runs a batch operation task for each media asset, with bounded concurrency
+/// The task returns the lines to print when the media asset is completed
+/// Completed media assets are recorded in the journal, if any
+/// Once the tasks finish, the undo entry is recorded, only with the changes of the completed media assets
+/// If any media asset fails, a report is printed and the process exits
+#[allow(clippy::too_many_arguments)]
+pub async fn run_batch_tasks<F, Fut>(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    logout_after_operation: bool,
+    media_list: Vec<MediaListItem>,
+    options: &BatchExecutionOptions,
+    mut journal: Option<BatchJournal>,
+    undo_entry: Option<BatchUndoEntry>,
+    task: F,
+) where
+    F: Fn(VaultURI, MediaListItem) -> Fut,
+    Fut: Future<Output = Result<Vec<String>, RequestError>> + Send + 'static,
+{
+    let n_total = media_list.len();
+    let mut n_done: usize = 0;
+
+    let mut pending = media_list.into_iter();
+    let mut tasks: JoinSet<(MediaListItem, Result<Vec<String>, RequestError>)> = JoinSet::new();
+
+    let mut completed: HashSet<u64> = HashSet::new();
+    let mut failed: Vec<MediaListItem> = Vec::new();
+    let mut stopped = false;
+
+    loop {
+        while !stopped && tasks.len() < options.concurrency {
+            match pending.next() {
+                Some(media) => {
+                    let media_task = task(vault_url.clone(), media.clone());
+
+                    tasks.spawn(async move { (media, media_task.await) });
+                }
+                None => {
+                    break;
+                }
+            }
+        }
+
+        let (media, task_res) = match tasks.join_next().await {
+            Some(Ok(r)) => r,
+            Some(Err(e)) => {
+                if logout_after_operation {
+                    let logout_res = do_logout(global_opts, vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                eprintln!("Error: {e}");
+                process::exit(1);
+            }
+            None => {
+                break;
+            }
+        };
+
+        n_done += 1;
+
+        let media_id_str = identifier_to_string(media.id);
+        let media_title = to_csv_string(&media.title);
+
+        match task_res {
+            Ok(lines) => {
+                for line in lines {
+                    eprintln!("[{n_done}/{n_total}] {line}");
+                }
+
+                if let Some(j) = journal.as_mut() {
+                    if let Err(e) = j.record_completed(media.id) {
+                        eprintln!("Warning: {e}");
+                    }
+                }
+
+                completed.insert(media.id);
+            }
+            Err(e) => {
+                eprintln!("[{n_done}/{n_total}] Failed for media {media_id_str}: {media_title}");
+                print_request_error(e);
+
+                failed.push(media);
+
+                // Stop starting new tasks, but let the running ones finish
+                if !options.continue_on_error {
+                    stopped = true;
+                }
+            }
+        }
+    }
+
+    // Undo journal
+
+    if let Some(undo_entry) = undo_entry {
+        let changes: Vec<UndoChange> = undo_entry
+            .changes
+            .into_iter()
+            .filter(|c| completed.contains(&c.media_id()))
+            .collect();
+
+        record_undo_entry(global_opts, vault_url, &undo_entry.operation, changes).await;
+    }
+
+    if failed.is_empty() {
+        return;
+    }
+
+    // Failures report
+
+    let n_failed = failed.len();
+    let n_skipped = n_total - n_done;
+
+    eprintln!("The operation failed for {n_failed} of {n_total} media assets:");
+
+    for media in failed.iter() {
+        let media_id_str = identifier_to_string(media.id);
+        let media_title = to_csv_string(&media.title);
+        eprintln!("    {media_id_str}: {media_title}");
+    }
+
+    if n_skipped > 0 {
+        eprintln!("The operation was stopped. {n_skipped} media assets were not processed.");
+    }
+
+    if let Some(j) = &journal {
+        let journal_path = j.path();
+        eprintln!("Run the same command with --journal {journal_path} to resume the operation.");
+    }
+
+    if logout_after_operation {
+        let logout_res = do_logout(global_opts, vault_url).await;
+
+        match logout_res {
+            Ok(_) => {}
+            Err(_) => {
+                process::exit(1);
+            }
+        }
+    }
+
+    process::exit(1);
+}
@@ -1,26 +1,36 @@
 // Batch operation command
 
-use std::process;
+use std::{collections::HashSet, process};
 
 use clap::Subcommand;
 
 use crate::{
     api::{
-        api_call_album_add_media, api_call_album_remove_media, api_call_get_album, api_call_get_tags, api_call_media_delete, api_call_search_advanced, api_call_tag_add, api_call_tag_remove, MAX_API_TAGS_FILTER, MAX_SEARCH_PAGE_LIMIT
+        api_call_album_add_media, api_call_album_remove_media, api_call_get_album,
+        api_call_get_tags, api_call_media_delete, api_call_search_advanced, api_call_tag_add,
+        api_call_tag_remove, MAX_API_TAGS_FILTER, MAX_SEARCH_PAGE_LIMIT,
     },
     models::{
         parse_media_type, parse_tag_name, parse_tag_search_mode, tags_map_from_list,
-        tags_reverse_map_from_list, AddTagBody, AlbumMediaBody, MediaListItem, MediaType,
-        RemoveTagBody, TagSearchMode,
+        tags_reverse_map_from_list, AddTagBody, AlbumMediaBody, BatchJournalHeader, MediaListItem,
+        MediaType, RemoveTagBody, SavedSearch, TagSearchMode, UndoChange,
     },
     tools::{
-        ask_user, ensure_login, identifier_to_string, parse_identifier, parse_vault_uri,
-        to_csv_string, VaultURI,
+        ask_user, ensure_login, get_text_filter, get_text_match_mode, identifier_to_string,
+        parse_identifier, parse_search_query, parse_vault_uri, read_identifier_list, to_csv_string,
+        BatchJournal, SearchQuery, VaultURI,
     },
 };
 
 use super::{
-    get_vault_url, logout::do_logout, print_request_error, search_advanced::media_matches_filter,
+    batch_dry_run::run_batch_dry_run,
+    batch_execution::{run_batch_tasks, BatchExecutionOptions, BatchUndoEntry},
+    get_vault_url,
+    logout::do_logout,
+    media_metadata_fetch::fetch_media_metadata_list,
+    print_request_error,
+    search_advanced::{filter_media_by_metadata, media_matches_filter},
+    search_saved::load_saved_search,
     CommandGlobalOptions,
 };
 
@@ -54,6 +64,38 @@ pub enum BatchCommand {
     Delete,
 }
 
+impl BatchCommand {
+    /// Gets the name of the operation
+    pub fn name(&self) -> String {
+        match self {
+            BatchCommand::AddTags { .. } => "add-tags".to_string(),
+            BatchCommand::RemoveTags { .. } => "remove-tags".to_string(),
+            BatchCommand::AddToAlbum { .. } => "add-to-album".to_string(),
+            BatchCommand::RemoveFromAlbum { .. } => "remove-from-album".to_string(),
+            BatchCommand::Delete => "delete".to_string(),
+        }
+    }
+
+    /// Gets the argument of the operation, normalized
+    pub fn argument(&self) -> String {
+        match self {
+            BatchCommand::AddTags { tags } | BatchCommand::RemoveTags { tags } => tags
+                .split(' ')
+                .map(parse_tag_name)
+                .filter(|t| !t.is_empty())
+                .collect::<Vec<String>>()
+                .join(" "),
+            BatchCommand::AddToAlbum { album } | BatchCommand::RemoveFromAlbum { album } => {
+                match parse_identifier(album) {
+                    Ok(id) => id.to_string(),
+                    Err(_) => album.clone(),
+                }
+            }
+            BatchCommand::Delete => "".to_string(),
+        }
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn run_cmd_batch_operation(
     global_opts: CommandGlobalOptions,
@@ -63,9 +105,73 @@ pub async fn run_cmd_batch_operation(
     tags: Option<String>,
     tags_mode: Option<String>,
     album: Option<String>,
+    query: Option<String>,
+    regex: bool,
+    fuzzy: bool,
+    search: Option<String>,
+    ids_from: Option<String>,
     everything: bool,
+    dry_run: bool,
+    json: bool,
+    concurrency: Option<u32>,
+    continue_on_error: bool,
+    journal: Option<String>,
     batch_command: BatchCommand,
 ) {
+    let concurrency = concurrency.unwrap_or(1);
+
+    if concurrency == 0 {
+        eprintln!("Error: The concurrency must be at least 1.");
+        process::exit(1);
+    }
+
+    let execution_options = BatchExecutionOptions {
+        concurrency: concurrency as usize,
+        continue_on_error,
+        journal,
+    };
+
+    if ids_from.as_deref() == Some("-") && !global_opts.auto_confirm && !dry_run {
+        eprintln!("Error: Reading the media IDs from the standard input requires the -y option, since the confirmation cannot be asked.");
+        process::exit(1);
+    }
+
+    let ids = match ids_from {
+        Some(path) => match read_identifier_list(&path) {
+            Ok(ids) => {
+                if ids.is_empty() {
+                    eprintln!("Error: The list of media IDs is empty.");
+                    process::exit(1);
+                }
+
+                Some(ids)
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let mut filter = BatchMediaFilter {
+        title,
+        description,
+        media_type,
+        tags,
+        tags_mode,
+        album,
+        query,
+        regex,
+        fuzzy,
+        ids,
+        everything,
+    };
+
+    if let Some(search_name) = search {
+        filter = filter.with_saved_search(load_saved_search(&search_name));
+    }
+
     let url_parse_res = parse_vault_uri(get_vault_url(&global_opts.vault_url));
 
     if url_parse_res.is_err() {
@@ -93,13 +199,115 @@ pub async fn run_cmd_batch_operation(
 
     vault_url = login_result.unwrap();
 
+    // Identifies the operation in the journal, to only resume the same one
+
+    let journal_header = BatchJournalHeader {
+        operation: batch_command.name(),
+        argument: batch_command.argument(),
+        vault: vault_url.to_base_url(),
+        filter: filter.to_saved_search(),
+        ids: filter.ids.clone(),
+        everything: filter.everything,
+    };
+
+    let media_list =
+        collect_batch_media(&global_opts, &vault_url, logout_after_operation, filter).await;
+
+    apply_batch_operation(
+        global_opts,
+        vault_url,
+        logout_after_operation,
+        media_list,
+        batch_command,
+        dry_run,
+        json,
+        execution_options,
+        journal_header,
+    )
+    .await;
+}
+
+#[derive(Default)]
+pub struct BatchMediaFilter {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub media_type: Option<String>,
+    pub tags: Option<String>,
+    pub tags_mode: Option<String>,
+    pub album: Option<String>,
+    pub query: Option<String>,
+    pub regex: bool,
+    pub fuzzy: bool,
+    pub ids: Option<Vec<u64>>,
+    pub everything: bool,
+}
+
+impl BatchMediaFilter {
+    /// Fills the filter with the criteria of a saved search
+    /// The options already set take precedence over the saved ones
+    pub fn with_saved_search(self, saved_search: SavedSearch) -> BatchMediaFilter {
+        let (regex, fuzzy) = if self.regex || self.fuzzy {
+            (self.regex, self.fuzzy)
+        } else {
+            (saved_search.regex, saved_search.fuzzy)
+        };
+
+        BatchMediaFilter {
+            title: self.title.or(saved_search.title),
+            description: self.description.or(saved_search.description),
+            media_type: self.media_type.or(saved_search.media_type),
+            tags: self.tags.or(saved_search.tags),
+            tags_mode: self.tags_mode.or(saved_search.tags_mode),
+            album: self.album.or(saved_search.album),
+            query: self.query.or(saved_search.query),
+            regex,
+            fuzzy,
+            ids: self.ids,
+            everything: self.everything,
+        }
+    }
+
+    /// Gets the criteria of the filter, in the same format of the saved searches
+    pub fn to_saved_search(&self) -> SavedSearch {
+        SavedSearch {
+            title: self.title.clone(),
+            description: self.description.clone(),
+            media_type: self.media_type.clone(),
+            tags: self.tags.clone(),
+            tags_mode: self.tags_mode.clone(),
+            album: self.album.clone(),
+            query: self.query.clone(),
+            regex: self.regex,
+            fuzzy: self.fuzzy,
+        }
+    }
+}
+
+pub async fn collect_batch_media(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    logout_after_operation: bool,
+    filter: BatchMediaFilter,
+) -> Vec<MediaListItem> {
+    let title = filter.title;
+    let description = filter.description;
+    let media_type = filter.media_type;
+    let tags = filter.tags;
+    let tags_mode = filter.tags_mode;
+    let album = filter.album;
+    let query = filter.query;
+    let regex = filter.regex;
+    let fuzzy = filter.fuzzy;
+    let ids = filter.ids;
+    let everything = filter.everything;
+
     // Get tags
 
-    let tags_res = api_call_get_tags(&vault_url, global_opts.debug).await;
+    let tags_res = api_call_get_tags(vault_url, global_opts.debug).await;
 
     if tags_res.is_err() {
         if logout_after_operation {
-            let logout_res = do_logout(&global_opts, &vault_url).await;
+            let logout_res = do_logout(global_opts, vault_url).await;
 
             match logout_res {
                 Ok(_) => {}
@@ -126,8 +334,7 @@ pub async fn run_cmd_batch_operation(
         match album_id_res {
             Ok(_) => {
                 let album_get_api_res =
-                    api_call_get_album(&vault_url, album_id_res.unwrap(), global_opts.debug)
-                        .await;
+                    api_call_get_album(vault_url, album_id_res.unwrap(), global_opts.debug).await;
 
                 match album_get_api_res {
                     Ok(album_data) => {
@@ -141,7 +348,7 @@ pub async fn run_cmd_batch_operation(
             }
             Err(_) => {
                 if logout_after_operation {
-                    let logout_res = do_logout(&global_opts, &vault_url).await;
+                    let logout_res = do_logout(global_opts, vault_url).await;
 
                     match logout_res {
                         Ok(_) => {}
@@ -156,10 +363,82 @@ pub async fn run_cmd_batch_operation(
         }
     }
 
+    // Get the listed media assets (if necessary)
+    // They are filtered in the same way as the album ones
+
+    if let Some(media_ids) = ids {
+        let metadata_map =
+            fetch_media_metadata_list(global_opts, vault_url, logout_after_operation, &media_ids)
+                .await;
+
+        let album_ids: Option<HashSet<u64>> = album_filter
+            .as_ref()
+            .map(|album_list| album_list.iter().map(|m| m.id).collect());
+
+        let mut listed_media: Vec<MediaListItem> = Vec::with_capacity(media_ids.len());
+
+        for media_id in media_ids {
+            match metadata_map.get(&media_id) {
+                Some(metadata) => {
+                    if let Some(album_ids) = &album_ids {
+                        if !album_ids.contains(&media_id) {
+                            continue;
+                        }
+                    }
+
+                    listed_media.push(metadata.to_list_item());
+                }
+                None => {
+                    let media_id_str = identifier_to_string(media_id);
+                    eprintln!(
+                        "Warning: Could not find media asset {media_id_str}. It will be skipped."
+                    );
+                }
+            }
+        }
+
+        album_filter = Some(listed_media);
+    }
+
     // Params
 
-    let title_filter = title.unwrap_or("".to_string());
-    let description_filter = description.unwrap_or("".to_string());
+    let text_match_mode = get_text_match_mode(regex, fuzzy);
+
+    let title_filter = match get_text_filter(title, text_match_mode) {
+        Ok(f) => f,
+        Err(e) => {
+            if logout_after_operation {
+                let logout_res = do_logout(global_opts, vault_url).await;
+
+                match logout_res {
+                    Ok(_) => {}
+                    Err(_) => {
+                        process::exit(1);
+                    }
+                }
+            }
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
+
+    let description_filter = match get_text_filter(description, text_match_mode) {
+        Ok(f) => f,
+        Err(e) => {
+            if logout_after_operation {
+                let logout_res = do_logout(global_opts, vault_url).await;
+
+                match logout_res {
+                    Ok(_) => {}
+                    Err(_) => {
+                        process::exit(1);
+                    }
+                }
+            }
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
 
     let mut media_type_filter: Option<MediaType> = None;
 
@@ -172,7 +451,7 @@ pub async fn run_cmd_batch_operation(
             }
             Err(_) => {
                 if logout_after_operation {
-                    let logout_res = do_logout(&global_opts, &vault_url).await;
+                    let logout_res = do_logout(global_opts, vault_url).await;
 
                     match logout_res {
                         Ok(_) => {}
@@ -205,7 +484,7 @@ pub async fn run_cmd_batch_operation(
 
             if !tags_reverse_map.contains_key(&parsed_tag_name) {
                 if logout_after_operation {
-                    let logout_res = do_logout(&global_opts, &vault_url).await;
+                    let logout_res = do_logout(global_opts, vault_url).await;
 
                     match logout_res {
                         Ok(_) => {}
@@ -242,7 +521,7 @@ pub async fn run_cmd_batch_operation(
             }
             Err(_) => {
                 if logout_after_operation {
-                    let logout_res = do_logout(&global_opts, &vault_url).await;
+                    let logout_res = do_logout(global_opts, vault_url).await;
 
                     match logout_res {
                         Ok(_) => {}
@@ -257,7 +536,7 @@ pub async fn run_cmd_batch_operation(
         }
     }
 
-    let tag_mode_api_param: String = match tags_filter_mode {
+    let mut tag_mode_api_param: String = match tags_filter_mode {
         TagSearchMode::All => "allof".to_string(),
         TagSearchMode::Any => {
             if tags_filter_count > MAX_API_TAGS_FILTER {
@@ -285,16 +564,50 @@ pub async fn run_cmd_batch_operation(
         },
     }
 
+    let mut search_query: Option<SearchQuery> = None;
+
+    if let Some(query_str) = query {
+        match parse_search_query(&query_str, &tags_reverse_map, text_match_mode) {
+            Ok(q) => {
+                if tag_param.is_none() {
+                    if let Some((query_tags, query_tags_mode)) =
+                        q.get_server_tags_filter(MAX_API_TAGS_FILTER)
+                    {
+                        tag_param = Some(query_tags);
+                        tag_mode_api_param = query_tags_mode;
+                    }
+                }
+
+                search_query = Some(q);
+            }
+            Err(e) => {
+                if logout_after_operation {
+                    let logout_res = do_logout(global_opts, vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                eprintln!("Invalid search query: {e}");
+                process::exit(1);
+            }
+        }
+    }
+
     if everything {
         if tags_filter_mode != TagSearchMode::All
             || tag_param.is_some()
             || media_type_filter.is_some()
-            || !description_filter.is_empty()
-            || !title_filter.is_empty()
+            || description_filter.is_some()
+            || title_filter.is_some()
             || album_filter.is_some()
+            || search_query.is_some()
         {
             if logout_after_operation {
-                let logout_res = do_logout(&global_opts, &vault_url).await;
+                let logout_res = do_logout(global_opts, vault_url).await;
 
                 match logout_res {
                     Ok(_) => {}
@@ -309,12 +622,13 @@ pub async fn run_cmd_batch_operation(
     } else if tags_filter_mode == TagSearchMode::All
         && tag_param.is_none()
         && media_type_filter.is_none()
-        && description_filter.is_empty()
-        && title_filter.is_empty()
+        && description_filter.is_none()
+        && title_filter.is_none()
         && album_filter.is_none()
+        && search_query.is_none()
     {
         if logout_after_operation {
-            let logout_res = do_logout(&global_opts, &vault_url).await;
+            let logout_res = do_logout(global_opts, vault_url).await;
 
             match logout_res {
                 Ok(_) => {}
@@ -341,6 +655,7 @@ pub async fn run_cmd_batch_operation(
                     &media_type_filter,
                     &tags_filter,
                     &tags_filter_mode,
+                    &search_query,
                 ) {
                     advanced_search_results.push(item);
                 }
@@ -352,7 +667,7 @@ pub async fn run_cmd_batch_operation(
             while !advanced_search_finished {
                 // Call API
                 let api_res = api_call_search_advanced(
-                    &vault_url,
+                    vault_url,
                     tag_param.as_deref(),
                     &tag_mode_api_param,
                     false,
@@ -372,6 +687,7 @@ pub async fn run_cmd_batch_operation(
                                 &media_type_filter,
                                 &tags_filter,
                                 &tags_filter_mode,
+                                &search_query,
                             ) {
                                 advanced_search_results.push(item);
                             }
@@ -386,7 +702,7 @@ pub async fn run_cmd_batch_operation(
                     Err(e) => {
                         print_request_error(e);
                         if logout_after_operation {
-                            let logout_res = do_logout(&global_opts, &vault_url).await;
+                            let logout_res = do_logout(global_opts, vault_url).await;
 
                             match logout_res {
                                 Ok(_) => {}
@@ -402,22 +718,27 @@ pub async fn run_cmd_batch_operation(
         }
     }
 
-    apply_batch_operation(
+    filter_media_by_metadata(
         global_opts,
         vault_url,
         logout_after_operation,
+        &search_query,
         advanced_search_results,
-        batch_command,
     )
-    .await;
+    .await
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn apply_batch_operation(
     global_opts: CommandGlobalOptions,
     vault_url: VaultURI,
     logout_after_operation: bool,
-    media_list: Vec<MediaListItem>,
+    mut media_list: Vec<MediaListItem>,
     batch_command: BatchCommand,
+    dry_run: bool,
+    json: bool,
+    execution_options: BatchExecutionOptions,
+    journal_header: BatchJournalHeader,
 ) {
     if media_list.is_empty() {
         if logout_after_operation {
@@ -434,6 +755,81 @@ async fn apply_batch_operation(
         process::exit(1);
     }
 
+    if dry_run {
+        run_batch_dry_run(
+            &global_opts,
+            &vault_url,
+            logout_after_operation,
+            &media_list,
+            &batch_command,
+            json,
+        )
+        .await;
+
+        if logout_after_operation {
+            let logout_res = do_logout(&global_opts, &vault_url).await;
+
+            match logout_res {
+                Ok(_) => {}
+                Err(_) => {
+                    process::exit(1);
+                }
+            }
+        }
+
+        return;
+    }
+
+    // Open the journal, skipping the media assets already completed
+
+    let journal = match &execution_options.journal {
+        Some(journal_path) => match BatchJournal::open(journal_path, &journal_header) {
+            Ok(j) => {
+                let n_matched = media_list.len();
+
+                media_list.retain(|m| !j.is_completed(m.id));
+
+                let n_completed = n_matched - media_list.len();
+
+                if n_completed > 0 {
+                    eprintln!("Resuming from the journal. Skipping {n_completed} media assets already completed.");
+                }
+
+                Some(j)
+            }
+            Err(e) => {
+                if logout_after_operation {
+                    let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                eprintln!("Error: {e}");
+                process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    if media_list.is_empty() {
+        if logout_after_operation {
+            let logout_res = do_logout(&global_opts, &vault_url).await;
+
+            match logout_res {
+                Ok(_) => {}
+                Err(_) => {
+                    process::exit(1);
+                }
+            }
+        }
+        eprintln!("All the media assets were already completed.");
+        return;
+    }
+
     match batch_command {
         BatchCommand::AddTags { tags } => {
             batch_add_tags(
@@ -442,6 +838,8 @@ async fn apply_batch_operation(
                 logout_after_operation,
                 media_list,
                 tags,
+                &execution_options,
+                journal,
             )
             .await;
         }
@@ -452,6 +850,8 @@ async fn apply_batch_operation(
                 logout_after_operation,
                 media_list,
                 tags,
+                &execution_options,
+                journal,
             )
             .await;
         }
@@ -462,6 +862,8 @@ async fn apply_batch_operation(
                 logout_after_operation,
                 media_list,
                 album,
+                &execution_options,
+                journal,
             )
             .await;
         }
@@ -472,6 +874,8 @@ async fn apply_batch_operation(
                 logout_after_operation,
                 media_list,
                 album,
+                &execution_options,
+                journal,
             )
             .await;
         }
@@ -481,6 +885,8 @@ async fn apply_batch_operation(
                 &vault_url,
                 logout_after_operation,
                 media_list,
+                &execution_options,
+                journal,
             )
             .await;
         }
@@ -506,6 +912,8 @@ async fn batch_add_tags(
     logout_after_operation: bool,
     media_list: Vec<MediaListItem>,
     tags: String,
+    execution_options: &BatchExecutionOptions,
+    journal: Option<BatchJournal>,
 ) {
     let n_total = media_list.len();
 
@@ -558,47 +966,100 @@ async fn batch_add_tags(
         }
     }
 
+    // Undo journal
+
+    let undo_entry = if global_opts.undo_log {
+        let tags_res = api_call_get_tags(vault_url, global_opts.debug).await;
+
+        let tags_reverse_map = match tags_res {
+            Ok(tags_vec) => tags_reverse_map_from_list(&tags_vec),
+            Err(e) => {
+                print_request_error(e);
+                if logout_after_operation {
+                    let logout_res = do_logout(&global_opts, vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                process::exit(1);
+            }
+        };
+
+        let mut undo_changes: Vec<UndoChange> = Vec::new();
+
+        for media in media_list.iter() {
+            for tag in tags_to_add.iter() {
+                let has_tag = match tags_reverse_map.get(tag) {
+                    Some(tag_id) => media.tags.contains(tag_id),
+                    None => false,
+                };
+
+                if !has_tag {
+                    undo_changes.push(UndoChange::MediaTag {
+                        media_id: media.id,
+                        tag: tag.clone(),
+                        present: false,
+                    });
+                }
+            }
+        }
+
+        let tags_str = tags_to_add.join(" ");
+
+        Some(BatchUndoEntry {
+            operation: format!("batch add-tags {tags_str}"),
+            changes: undo_changes,
+        })
+    } else {
+        None
+    };
+
     // Operation loop
 
-    let mut n_done: usize = 0;
-
-    for media in media_list {
-        n_done += 1;
-        for tag in tags_to_add.iter() {
-            let api_res = api_call_tag_add(
-                vault_url,
-                AddTagBody {
-                    media_id: media.id,
-                    tag_name: tag.to_string(),
-                },
-                global_opts.debug,
-            )
-            .await;
+    let debug = global_opts.debug;
+
+    run_batch_tasks(
+        &global_opts,
+        vault_url,
+        logout_after_operation,
+        media_list,
+        execution_options,
+        journal,
+        undo_entry,
+        |task_url, media| {
+            let tags_to_add = tags_to_add.clone();
+
+            async move {
+                let mut lines: Vec<String> = Vec::new();
+
+                for tag in tags_to_add.iter() {
+                    api_call_tag_add(
+                        &task_url,
+                        AddTagBody {
+                            media_id: media.id,
+                            tag_name: tag.to_string(),
+                        },
+                        debug,
+                    )
+                    .await?;
 
-            match api_res {
-                Ok(_) => {
                     let media_id_str = identifier_to_string(media.id);
                     let tag_str = to_csv_string(tag);
                     let media_title = to_csv_string(&media.title);
-                    eprintln!("[{n_done}/{n_total}] Added tag {tag_str} to media {media_id_str}: {media_title}");
+                    lines.push(format!(
+                        "Added tag {tag_str} to media {media_id_str}: {media_title}"
+                    ));
                 }
-                Err(e) => {
-                    print_request_error(e);
-                    if logout_after_operation {
-                        let logout_res = do_logout(&global_opts, vault_url).await;
 
-                        match logout_res {
-                            Ok(_) => {}
-                            Err(_) => {
-                                process::exit(1);
-                            }
-                        }
-                    }
-                    process::exit(1);
-                }
+                Ok(lines)
             }
-        }
-    }
+        },
+    )
+    .await;
 }
 
 async fn batch_remove_tags(
@@ -607,6 +1068,8 @@ async fn batch_remove_tags(
     logout_after_operation: bool,
     media_list: Vec<MediaListItem>,
     tags: String,
+    execution_options: &BatchExecutionOptions,
+    journal: Option<BatchJournal>,
 ) {
     let n_total = media_list.len();
 
@@ -700,51 +1163,89 @@ async fn batch_remove_tags(
         }
     }
 
-    // Operation loop
-
-    let mut n_done: usize = 0;
+    // Undo journal
 
-    let default_tag_name = "???".to_string();
-    for media in media_list {
-        n_done += 1;
+    let undo_entry = if global_opts.undo_log {
+        let mut undo_changes: Vec<UndoChange> = Vec::new();
+        let mut tag_names: Vec<String> = Vec::new();
 
         for tag in tags_to_remove.iter() {
-            let tag_name = tags_map.get(tag).unwrap_or(&default_tag_name);
-
-            let api_res = api_call_tag_remove(
-                vault_url,
-                RemoveTagBody {
-                    media_id: media.id,
-                    tag_id: *tag,
-                },
-                global_opts.debug,
-            )
-            .await;
+            let tag_name = match tags_map.get(tag) {
+                Some(n) => n.clone(),
+                None => {
+                    continue;
+                }
+            };
+
+            for media in media_list.iter() {
+                if media.tags.contains(tag) {
+                    undo_changes.push(UndoChange::MediaTag {
+                        media_id: media.id,
+                        tag: tag_name.clone(),
+                        present: true,
+                    });
+                }
+            }
+
+            tag_names.push(tag_name);
+        }
+
+        let tags_str = tag_names.join(" ");
+
+        Some(BatchUndoEntry {
+            operation: format!("batch remove-tags {tags_str}"),
+            changes: undo_changes,
+        })
+    } else {
+        None
+    };
+
+    // Operation loop
+
+    let debug = global_opts.debug;
+
+    run_batch_tasks(
+        &global_opts,
+        vault_url,
+        logout_after_operation,
+        media_list,
+        execution_options,
+        journal,
+        undo_entry,
+        |task_url, media| {
+            let tags_to_remove = tags_to_remove.clone();
+            let tags_map = tags_map.clone();
+
+            async move {
+                let mut lines: Vec<String> = Vec::new();
+
+                let default_tag_name = "???".to_string();
+                for tag in tags_to_remove.iter() {
+                    let tag_name = tags_map.get(tag).unwrap_or(&default_tag_name);
+
+                    api_call_tag_remove(
+                        &task_url,
+                        RemoveTagBody {
+                            media_id: media.id,
+                            tag_id: *tag,
+                        },
+                        debug,
+                    )
+                    .await?;
 
-            match api_res {
-                Ok(_) => {
                     let media_id_str = identifier_to_string(media.id);
                     let tag_str = to_csv_string(tag_name);
                     let media_title = to_csv_string(&media.title);
-                    eprintln!("[{n_done}/{n_total}] Removed tag {tag_str} from media {media_id_str}: {media_title}");
+                    lines.push(format!(
+                        "Removed tag {tag_str} from media {media_id_str}: {media_title}"
+                    ));
                 }
-                Err(e) => {
-                    print_request_error(e);
-                    if logout_after_operation {
-                        let logout_res = do_logout(&global_opts, vault_url).await;
 
-                        match logout_res {
-                            Ok(_) => {}
-                            Err(_) => {
-                                process::exit(1);
-                            }
-                        }
-                    }
-                    process::exit(1);
-                }
+                Ok(lines)
             }
-        }
-    }
+        },
+    )
+    .await;
 }
 
 async fn batch_add_to_album(
@@ -753,6 +1254,8 @@ async fn batch_add_to_album(
     logout_after_operation: bool,
     media_list: Vec<MediaListItem>,
     album: String,
+    execution_options: &BatchExecutionOptions,
+    journal: Option<BatchJournal>,
 ) {
     let n_total = media_list.len();
 
@@ -802,27 +1305,13 @@ async fn batch_add_to_album(
         }
     }
 
-    // Operation loop
-
-    let mut n_done: usize = 0;
+    // Undo journal
 
-    for media in media_list {
-        n_done += 1;
-
-        let api_res = api_call_album_add_media(
-            vault_url,
-            album_id,
-            AlbumMediaBody { media_id: media.id },
-            global_opts.debug,
-        )
-        .await;
+    let undo_entry = if global_opts.undo_log {
+        let api_res = api_call_get_album(vault_url, album_id, global_opts.debug).await;
 
-        match api_res {
-            Ok(_) => {
-                let media_id_str = identifier_to_string(media.id);
-                let media_title = to_csv_string(&media.title);
-                eprintln!("[{n_done}/{n_total}] Added media {media_id_str}: {media_title} into album {album_id_str}");
-            }
+        let album_media: HashSet<u64> = match api_res {
+            Ok(album_data) => album_data.list.iter().map(|m| m.id).collect(),
             Err(e) => {
                 print_request_error(e);
                 if logout_after_operation {
@@ -837,8 +1326,59 @@ async fn batch_add_to_album(
                 }
                 process::exit(1);
             }
-        }
-    }
+        };
+
+        let undo_changes: Vec<UndoChange> = media_list
+            .iter()
+            .filter(|m| !album_media.contains(&m.id))
+            .map(|m| UndoChange::AlbumMedia {
+                album_id,
+                media_id: m.id,
+                position: None,
+            })
+            .collect();
+
+        Some(BatchUndoEntry {
+            operation: format!("batch add-to-album {album_id_str}"),
+            changes: undo_changes,
+        })
+    } else {
+        None
+    };
+
+    // Operation loop
+
+    let debug = global_opts.debug;
+
+    run_batch_tasks(
+        &global_opts,
+        vault_url,
+        logout_after_operation,
+        media_list,
+        execution_options,
+        journal,
+        undo_entry,
+        |task_url, media| {
+            let album_id_str = album_id_str.clone();
+
+            async move {
+                api_call_album_add_media(
+                    &task_url,
+                    album_id,
+                    AlbumMediaBody { media_id: media.id },
+                    debug,
+                )
+                .await?;
+
+                let media_id_str = identifier_to_string(media.id);
+                let media_title = to_csv_string(&media.title);
+                Ok(vec![format!(
+                    "Added media {media_id_str}: {media_title} into album {album_id_str}"
+                )])
+            }
+        },
+    )
+    .await;
 }
 
 async fn batch_remove_from_album(
@@ -847,6 +1387,8 @@ async fn batch_remove_from_album(
     logout_after_operation: bool,
     media_list: Vec<MediaListItem>,
     album: String,
+    execution_options: &BatchExecutionOptions,
+    journal: Option<BatchJournal>,
 ) {
     let n_total = media_list.len();
 
@@ -898,27 +1440,13 @@ async fn batch_remove_from_album(
         }
     }
 
-    // Operation loop
-
-    let mut n_done: usize = 0;
+    // Undo journal
 
-    for media in media_list {
-        n_done += 1;
-
-        let api_res = api_call_album_remove_media(
-            vault_url,
-            album_id,
-            AlbumMediaBody { media_id: media.id },
-            global_opts.debug,
-        )
-        .await;
+    let undo_entry = if global_opts.undo_log {
+        let api_res = api_call_get_album(vault_url, album_id, global_opts.debug).await;
 
-        match api_res {
-            Ok(_) => {
-                let media_id_str = identifier_to_string(media.id);
-                let media_title = to_csv_string(&media.title);
-                eprintln!("[{n_done}/{n_total}] Removed media {media_id_str}: {media_title} from album {album_id_str}");
-            }
+        let album_media: Vec<u64> = match api_res {
+            Ok(album_data) => album_data.list.iter().map(|m| m.id).collect(),
             Err(e) => {
                 print_request_error(e);
                 if logout_after_operation {
@@ -933,8 +1461,65 @@ async fn batch_remove_from_album(
                 }
                 process::exit(1);
             }
-        }
-    }
+        };
+
+        let media_to_remove: HashSet<u64> = media_list.iter().map(|m| m.id).collect();
+
+        // From the last position to the first one, so the undo
+        // (which goes in reverse order) restores the original positions
+        let undo_changes: Vec<UndoChange> = album_media
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, media_id)| media_to_remove.contains(media_id))
+            .map(|(position, media_id)| UndoChange::AlbumMedia {
+                album_id,
+                media_id: *media_id,
+                position: Some(position),
+            })
+            .collect();
+
+        Some(BatchUndoEntry {
+            operation: format!("batch remove-from-album {album_id_str}"),
+            changes: undo_changes,
+        })
+    } else {
+        None
+    };
+
+    // Operation loop
+
+    let debug = global_opts.debug;
+
+    run_batch_tasks(
+        &global_opts,
+        vault_url,
+        logout_after_operation,
+        media_list,
+        execution_options,
+        journal,
+        undo_entry,
+        |task_url, media| {
+            let album_id_str = album_id_str.clone();
+
+            async move {
+                api_call_album_remove_media(
+                    &task_url,
+                    album_id,
+                    AlbumMediaBody { media_id: media.id },
+                    debug,
+                )
+                .await?;
+
+                let media_id_str = identifier_to_string(media.id);
+                let media_title = to_csv_string(&media.title);
+                Ok(vec![format!(
+                    "Removed media {media_id_str}: {media_title} from album {album_id_str}"
+                )])
+            }
+        },
+    )
+    .await;
 }
 
 async fn batch_delete(
@@ -942,9 +1527,15 @@ async fn batch_delete(
     vault_url: &VaultURI,
     logout_after_operation: bool,
     media_list: Vec<MediaListItem>,
+    execution_options: &BatchExecutionOptions,
+    journal: Option<BatchJournal>,
 ) {
     let n_total = media_list.len();
 
+    if global_opts.undo_log {
+        eprintln!("Warning: Deleting media assets cannot be undone. Nothing will be recorded in the undo journal.");
+    }
+
     // Ask confirmation
 
     if !global_opts.auto_confirm {
@@ -968,33 +1559,23 @@ async fn batch_delete(
 
     // Operation loop
 
-    let mut n_done: usize = 0;
-
-    for media in media_list {
-        n_done += 1;
-
-        let api_res = api_call_media_delete(vault_url, media.id, global_opts.debug).await;
+    let debug = global_opts.debug;
 
-        match api_res {
-            Ok(_) => {
-                let media_id_str = identifier_to_string(media.id);
-                let media_title = to_csv_string(&media.title);
-                eprintln!("[{n_done}/{n_total}] Deleted media {media_id_str}: {media_title}");
-            }
-            Err(e) => {
-                print_request_error(e);
-                if logout_after_operation {
-                    let logout_res = do_logout(&global_opts, vault_url).await;
-
-                    match logout_res {
-                        Ok(_) => {}
-                        Err(_) => {
-                            process::exit(1);
-                        }
-                    }
-                }
-                process::exit(1);
-            }
-        }
-    }
+    run_batch_tasks(
+        &global_opts,
+        vault_url,
+        logout_after_operation,
+        media_list,
+        execution_options,
+        journal,
+        None,
+        |task_url, media| async move {
+            api_call_media_delete(&task_url, media.id, debug).await?;
+
+            let media_id_str = identifier_to_string(media.id);
+            let media_title = to_csv_string(&media.title);
+            Ok(vec![format!("Deleted media {media_id_str}: {media_title}")])
+        },
+    )
+    .await;
 }
@@ -0,0 +1,46 @@
+// Catalog command
+
+use clap::Subcommand;
+
+use super::{
+    catalog_apply::run_cmd_catalog_apply, catalog_export::run_cmd_catalog_export,
+    CommandGlobalOptions,
+};
+
+#[derive(Subcommand)]
+pub enum CatalogCommand {
+    /// Exports a catalog of the vault metadata (without downloading any media), with an entry for each media asset.
+    Export {
+        /// Path to the catalog file
+        output: String,
+
+        /// Catalog format. Can be: csv, jsonl or sqlite. By default, it is detected from the file extension.
+        #[arg(short, long)]
+        format: Option<String>,
+
+        /// Only include the media assets matching a saved search
+        #[arg(short, long)]
+        search: Option<String>,
+    },
+
+    /// Applies the changes of a CSV file (with the same columns the catalog uses) to the media assets, matching rows by the id column. Only the columns present in the file are compared: title, description, tags, force_start_beginning and is_anim.
+    Apply {
+        /// Path to the CSV file with the edits
+        path: String,
+    },
+}
+
+pub async fn run_catalog_cmd(global_opts: CommandGlobalOptions, cmd: CatalogCommand) {
+    match cmd {
+        CatalogCommand::Export {
+            output,
+            format,
+            search,
+        } => {
+            run_cmd_catalog_export(global_opts, output, format, search).await;
+        }
+        CatalogCommand::Apply { path } => {
+            run_cmd_catalog_apply(global_opts, path).await;
+        }
+    }
+}
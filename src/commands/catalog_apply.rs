@@ -0,0 +1,510 @@
+// Catalog apply command
+
+use std::{collections::HashSet, process};
+
+use crate::{
+    api::{
+        api_call_get_media, api_call_get_tags, api_call_media_change_description,
+        api_call_media_change_extra, api_call_media_change_title, api_call_tag_add,
+        api_call_tag_remove,
+    },
+    commands::logout::do_logout,
+    models::{
+        parse_tag_name, tags_map_from_list, tags_names_from_ids, AddTagBody,
+        MediaUpdateDescriptionBody, MediaUpdateExtraBody, MediaUpdateTitleBody, RemoveTagBody,
+    },
+    tools::{
+        ask_user, ensure_login, identifier_to_string, parse_identifier, parse_vault_uri,
+        to_csv_string, RequestError, VaultURI,
+    },
+};
+
+use super::{get_vault_url, print_request_error, CommandGlobalOptions};
+
+/// A row of the edits file. The columns not present in the file are None.
+struct CatalogEditRow {
+    media_id: u64,
+    title: Option<String>,
+    description: Option<String>,
+    tags: Option<Vec<String>>,
+    force_start_beginning: Option<bool>,
+    is_anim: Option<bool>,
+}
+
+/// Changes to apply to a media asset
+struct CatalogEdit {
+    media_id: u64,
+    title: Option<String>,
+    description: Option<String>,
+    tags_add: Vec<String>,
+    tags_remove: Vec<u64>,
+    extra: Option<MediaUpdateExtraBody>,
+}
+
+pub async fn run_cmd_catalog_apply(global_opts: CommandGlobalOptions, path: String) {
+    // Read the edits before logging in, so nothing is changed if the file is invalid
+
+    let rows = match read_catalog_edits(&path) {
+        Ok(r) => r,
+        Err(errors) => {
+            for e_str in errors {
+                eprintln!("Error: {e_str}");
+            }
+            eprintln!("Could not read the edits file: {path}. Nothing was changed.");
+            process::exit(1);
+        }
+    };
+
+    if rows.is_empty() {
+        eprintln!("The edits file {path} has no rows.");
+        return;
+    }
+
+    let url_parse_res = parse_vault_uri(get_vault_url(&global_opts.vault_url));
+
+    if url_parse_res.is_err() {
+        match url_parse_res.err().unwrap() {
+            crate::tools::VaultURIParseError::InvalidProtocol => {
+                eprintln!("Invalid vault URL provided. Must be an HTTP or HTTPS URL.");
+            }
+            crate::tools::VaultURIParseError::URLError(e) => {
+                let err_msg = e.to_string();
+                eprintln!("Invalid vault URL provided: {err_msg}");
+            }
+        }
+
+        process::exit(1);
+    }
+
+    let mut vault_url = url_parse_res.unwrap();
+
+    let logout_after_operation = vault_url.is_login();
+    let login_result = ensure_login(&vault_url, &None, global_opts.debug).await;
+
+    if login_result.is_err() {
+        process::exit(1);
+    }
+
+    vault_url = login_result.unwrap();
+
+    // Get tags
+
+    let tags_res = api_call_get_tags(&vault_url, global_opts.debug).await;
+
+    let tags_map = match tags_res {
+        Ok(t) => tags_map_from_list(&t),
+        Err(e) => {
+            print_request_error(e);
+            if logout_after_operation {
+                let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                match logout_res {
+                    Ok(_) => {}
+                    Err(_) => {
+                        process::exit(1);
+                    }
+                }
+            }
+            process::exit(1);
+        }
+    };
+
+    // Compare with the current metadata
+
+    let mut edits: Vec<CatalogEdit> = Vec::new();
+    let mut n_not_found: usize = 0;
+
+    for row in rows {
+        let media_id_str = identifier_to_string(row.media_id);
+
+        let api_res = api_call_get_media(&vault_url, row.media_id, global_opts.debug).await;
+
+        let metadata = match api_res {
+            Ok(m) => m,
+            Err(RequestError::StatusCode(status)) | Err(RequestError::Api { status, .. })
+                if status == 404 =>
+            {
+                eprintln!("Skipped {media_id_str}: The media asset does not exist");
+                n_not_found += 1;
+                continue;
+            }
+            Err(e) => {
+                print_request_error(e);
+                if logout_after_operation {
+                    let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                process::exit(1);
+            }
+        };
+
+        let mut edit = CatalogEdit {
+            media_id: row.media_id,
+            title: None,
+            description: None,
+            tags_add: Vec::new(),
+            tags_remove: Vec::new(),
+            extra: None,
+        };
+
+        let mut diff: Vec<String> = Vec::new();
+
+        if let Some(title) = row.title {
+            if title != metadata.title {
+                let old_title = to_csv_string(&metadata.title);
+                let new_title = to_csv_string(&title);
+                diff.push(format!("title: {old_title} -> {new_title}"));
+                edit.title = Some(title);
+            }
+        }
+
+        if let Some(description) = row.description {
+            if description != metadata.description {
+                let old_description = to_csv_string(&metadata.description);
+                let new_description = to_csv_string(&description);
+                diff.push(format!(
+                    "description: {old_description} -> {new_description}"
+                ));
+                edit.description = Some(description);
+            }
+        }
+
+        if let Some(row_tags) = row.tags {
+            let current_tags = tags_names_from_ids(&metadata.tags, &tags_map);
+
+            for tag in row_tags.iter() {
+                if !current_tags.contains(tag) {
+                    edit.tags_add.push(tag.clone());
+                }
+            }
+
+            let mut removed_names: Vec<String> = Vec::new();
+
+            for (tag_id, tag) in metadata.tags.iter().zip(current_tags.iter()) {
+                if !row_tags.contains(tag) {
+                    edit.tags_remove.push(*tag_id);
+                    removed_names.push(tag.clone());
+                }
+            }
+
+            if !edit.tags_add.is_empty() || !removed_names.is_empty() {
+                let mut tags_diff: Vec<String> =
+                    edit.tags_add.iter().map(|t| "+".to_string() + t).collect();
+                tags_diff.extend(removed_names.iter().map(|t| "-".to_string() + t));
+
+                let tags_diff_str = tags_diff.join(" ");
+                diff.push(format!("tags: {tags_diff_str}"));
+            }
+        }
+
+        let current_force_start_beginning = metadata.force_start_beginning.unwrap_or(false);
+        let current_is_anim = metadata.is_anim.unwrap_or(false);
+
+        let new_force_start_beginning = row
+            .force_start_beginning
+            .unwrap_or(current_force_start_beginning);
+        let new_is_anim = row.is_anim.unwrap_or(current_is_anim);
+
+        if new_force_start_beginning != current_force_start_beginning {
+            diff.push(format!(
+                "force_start_beginning: {current_force_start_beginning} -> {new_force_start_beginning}"
+            ));
+        }
+
+        if new_is_anim != current_is_anim {
+            diff.push(format!("is_anim: {current_is_anim} -> {new_is_anim}"));
+        }
+
+        if new_force_start_beginning != current_force_start_beginning
+            || new_is_anim != current_is_anim
+        {
+            edit.extra = Some(MediaUpdateExtraBody {
+                force_start_beginning: Some(new_force_start_beginning),
+                is_anim: Some(new_is_anim),
+            });
+        }
+
+        if diff.is_empty() {
+            continue;
+        }
+
+        println!("{media_id_str}");
+
+        for line in diff {
+            println!("    {line}");
+        }
+
+        edits.push(edit);
+    }
+
+    let n_edits = edits.len();
+
+    if n_edits == 0 {
+        if logout_after_operation {
+            let logout_res = do_logout(&global_opts, &vault_url).await;
+
+            match logout_res {
+                Ok(_) => {}
+                Err(_) => {
+                    process::exit(1);
+                }
+            }
+        }
+        eprintln!("No changes found in {path}");
+        return;
+    }
+
+    // Ask confirmation
+
+    if !global_opts.auto_confirm {
+        eprintln!("Applying changes to {n_edits} media assets");
+        let confirmation = ask_user("Continue? y/n: ").await.unwrap_or("".to_string());
+
+        if confirmation.to_lowercase() != "y" {
+            if logout_after_operation {
+                let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                match logout_res {
+                    Ok(_) => {}
+                    Err(_) => {
+                        process::exit(1);
+                    }
+                }
+            }
+            process::exit(1);
+        }
+    }
+
+    // Apply
+
+    let mut n_failed: usize = 0;
+
+    for (i, edit) in edits.into_iter().enumerate() {
+        let n_done = i + 1;
+        let media_id_str = identifier_to_string(edit.media_id);
+
+        let edit_res = apply_catalog_edit(&global_opts, &vault_url, edit).await;
+
+        match edit_res {
+            Ok(_) => {
+                eprintln!("[{n_done}/{n_edits}] Updated {media_id_str}");
+            }
+            Err(e) => {
+                print_request_error(e);
+                eprintln!("[{n_done}/{n_edits}] Could not update {media_id_str}");
+                n_failed += 1;
+            }
+        }
+    }
+
+    // Done
+
+    if logout_after_operation {
+        let logout_res = do_logout(&global_opts, &vault_url).await;
+
+        match logout_res {
+            Ok(_) => {}
+            Err(_) => {
+                process::exit(1);
+            }
+        }
+    }
+
+    let n_updated = n_edits - n_failed;
+
+    eprintln!("Done. Updated: {n_updated}, Failed: {n_failed}, Not found: {n_not_found}");
+
+    if n_failed > 0 {
+        process::exit(1);
+    }
+}
+
+async fn apply_catalog_edit(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    edit: CatalogEdit,
+) -> Result<(), RequestError> {
+    if let Some(title) = edit.title {
+        api_call_media_change_title(
+            vault_url,
+            edit.media_id,
+            MediaUpdateTitleBody { title },
+            global_opts.debug,
+        )
+        .await?;
+    }
+
+    if let Some(description) = edit.description {
+        api_call_media_change_description(
+            vault_url,
+            edit.media_id,
+            MediaUpdateDescriptionBody { description },
+            global_opts.debug,
+        )
+        .await?;
+    }
+
+    for tag_name in edit.tags_add {
+        api_call_tag_add(
+            vault_url,
+            AddTagBody {
+                media_id: edit.media_id,
+                tag_name,
+            },
+            global_opts.debug,
+        )
+        .await?;
+    }
+
+    for tag_id in edit.tags_remove {
+        api_call_tag_remove(
+            vault_url,
+            RemoveTagBody {
+                media_id: edit.media_id,
+                tag_id,
+            },
+            global_opts.debug,
+        )
+        .await?;
+    }
+
+    if let Some(extra) = edit.extra {
+        api_call_media_change_extra(vault_url, edit.media_id, extra, global_opts.debug).await?;
+    }
+
+    Ok(())
+}
+
+/// Reads the rows of an edits file (CSV, with headers)
+/// Returns the list of errors found, if any
+fn read_catalog_edits(path: &str) -> Result<Vec<CatalogEditRow>, Vec<String>> {
+    let mut reader = csv::Reader::from_path(path).map_err(|e| vec![e.to_string()])?;
+
+    let headers = reader.headers().map_err(|e| vec![e.to_string()])?.clone();
+
+    let find_column = |name: &str| headers.iter().position(|h| h.trim() == name);
+
+    let id_column = match find_column("id") {
+        Some(c) => c,
+        None => {
+            return Err(vec!["The file has no id column".to_string()]);
+        }
+    };
+
+    let title_column = find_column("title");
+    let description_column = find_column("description");
+    let tags_column = find_column("tags");
+    let force_start_beginning_column = find_column("force_start_beginning");
+    let is_anim_column = find_column("is_anim");
+
+    let mut rows: Vec<CatalogEditRow> = Vec::new();
+    let mut errors: Vec<String> = Vec::new();
+    let mut seen_ids: HashSet<u64> = HashSet::new();
+
+    for (i, record_res) in reader.records().enumerate() {
+        // The header is the first line
+        let line = i + 2;
+
+        let record = match record_res {
+            Ok(r) => r,
+            Err(e) => {
+                errors.push(format!("Line {line}: {e}"));
+                continue;
+            }
+        };
+
+        let get_field = |column: Option<usize>| -> Option<String> {
+            column.and_then(|c| record.get(c)).map(|v| v.to_string())
+        };
+
+        let id_str = record.get(id_column).unwrap_or("").trim();
+
+        let media_id = match parse_identifier(id_str) {
+            Ok(id) => id,
+            Err(_) => {
+                errors.push(format!("Line {line}: Invalid media ID: {id_str}"));
+                continue;
+            }
+        };
+
+        if !seen_ids.insert(media_id) {
+            errors.push(format!(
+                "Line {line}: Media ID listed more than once: {id_str}"
+            ));
+            continue;
+        }
+
+        let tags = get_field(tags_column).map(|t| {
+            let mut tags_list: Vec<String> = Vec::new();
+
+            for tag in t
+                .split(' ')
+                .map(|t| parse_tag_name(t).to_lowercase())
+                .filter(|t| !t.is_empty())
+            {
+                if !tags_list.contains(&tag) {
+                    tags_list.push(tag);
+                }
+            }
+
+            tags_list
+        });
+
+        let force_start_beginning =
+            match parse_catalog_bool(get_field(force_start_beginning_column)) {
+                Ok(b) => b,
+                Err(v) => {
+                    errors.push(format!(
+                        "Line {line}: Invalid value for force_start_beginning: {v}"
+                    ));
+                    continue;
+                }
+            };
+
+        let is_anim = match parse_catalog_bool(get_field(is_anim_column)) {
+            Ok(b) => b,
+            Err(v) => {
+                errors.push(format!("Line {line}: Invalid value for is_anim: {v}"));
+                continue;
+            }
+        };
+
+        rows.push(CatalogEditRow {
+            media_id,
+            title: get_field(title_column),
+            description: get_field(description_column),
+            tags,
+            force_start_beginning,
+            is_anim,
+        });
+    }
+
+    if errors.is_empty() {
+        Ok(rows)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Parses a boolean column. Empty values are left unchanged (None).
+fn parse_catalog_bool(value: Option<String>) -> Result<Option<bool>, String> {
+    let v = match value {
+        Some(v) => v.trim().to_lowercase(),
+        None => {
+            return Ok(None);
+        }
+    };
+
+    match v.as_str() {
+        "" => Ok(None),
+        "true" | "yes" | "y" | "1" => Ok(Some(true)),
+        "false" | "no" | "n" | "0" => Ok(Some(false)),
+        _ => Err(v),
+    }
+}
@@ -0,0 +1,488 @@
+// Catalog export command
+
+use std::{collections::HashMap, path::Path, process};
+
+use crate::{
+    api::{
+        api_call_get_albums, api_call_get_media, api_call_get_media_albums,
+        api_call_get_media_stats, api_call_get_tags, api_call_search, MAX_SEARCH_PAGE_LIMIT,
+    },
+    commands::logout::do_logout,
+    models::{tags_map_from_list, tags_names_from_ids, CatalogEntry},
+    tools::{
+        ask_user, ensure_login, format_date, identifier_to_string, parse_vault_uri, RequestError,
+        VaultURI,
+    },
+};
+
+use super::{
+    batch_operation::{collect_batch_media, BatchMediaFilter},
+    get_vault_url, print_request_error,
+    search_saved::load_saved_search,
+    CommandGlobalOptions,
+};
+
+/// Format of a catalog file
+#[derive(Clone, Copy, PartialEq)]
+pub enum CatalogFormat {
+    Csv,
+    JsonLines,
+    Sqlite,
+}
+
+/// Gets the catalog format, from the option or the extension of the file
+pub fn get_catalog_format(path: &str, format: Option<String>) -> Result<CatalogFormat, String> {
+    let format_str = match format {
+        Some(f) => f.to_lowercase(),
+        None => Path::new(path)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default(),
+    };
+
+    match format_str.as_str() {
+        "csv" => Ok(CatalogFormat::Csv),
+        "jsonl" | "json" | "ndjson" => Ok(CatalogFormat::JsonLines),
+        "sqlite" | "sqlite3" | "db" => Ok(CatalogFormat::Sqlite),
+        _ => Err(
+            "Unknown catalog format. Can be: csv, jsonl or sqlite. Use the --format option to specify it."
+                .to_string(),
+        ),
+    }
+}
+
+pub async fn run_cmd_catalog_export(
+    global_opts: CommandGlobalOptions,
+    output: String,
+    format: Option<String>,
+    search: Option<String>,
+) {
+    let catalog_format = match get_catalog_format(&output, format) {
+        Ok(f) => f,
+        Err(e_str) => {
+            eprintln!("{e_str}");
+            process::exit(1);
+        }
+    };
+
+    let search = search.map(|name| load_saved_search(&name));
+
+    let url_parse_res = parse_vault_uri(get_vault_url(&global_opts.vault_url));
+
+    if url_parse_res.is_err() {
+        match url_parse_res.err().unwrap() {
+            crate::tools::VaultURIParseError::InvalidProtocol => {
+                eprintln!("Invalid vault URL provided. Must be an HTTP or HTTPS URL.");
+            }
+            crate::tools::VaultURIParseError::URLError(e) => {
+                let err_msg = e.to_string();
+                eprintln!("Invalid vault URL provided: {err_msg}");
+            }
+        }
+
+        process::exit(1);
+    }
+
+    let mut vault_url = url_parse_res.unwrap();
+
+    let logout_after_operation = vault_url.is_login();
+    let login_result = ensure_login(&vault_url, &None, global_opts.debug).await;
+
+    if login_result.is_err() {
+        process::exit(1);
+    }
+
+    vault_url = login_result.unwrap();
+
+    // Ask before overwriting
+
+    if Path::new(&output).exists() && !global_opts.auto_confirm {
+        eprintln!("The file {output} already exists");
+        let confirmation = ask_user("Do you want to overwrite it? y/n: ")
+            .await
+            .unwrap_or("".to_string());
+
+        if confirmation.to_lowercase() != "y" {
+            if logout_after_operation {
+                let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                match logout_res {
+                    Ok(_) => {}
+                    Err(_) => {
+                        process::exit(1);
+                    }
+                }
+            }
+            process::exit(1);
+        }
+    }
+
+    // Get tags and albums
+
+    let tags_res = api_call_get_tags(&vault_url, global_opts.debug).await;
+
+    let tags_map = match tags_res {
+        Ok(tags) => tags_map_from_list(&tags),
+        Err(e) => {
+            print_request_error(e);
+            if logout_after_operation {
+                let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                match logout_res {
+                    Ok(_) => {}
+                    Err(_) => {
+                        process::exit(1);
+                    }
+                }
+            }
+            process::exit(1);
+        }
+    };
+
+    let albums_res = api_call_get_albums(&vault_url, global_opts.debug).await;
+
+    let albums_names: HashMap<u64, String> = match albums_res {
+        Ok(albums) => albums.into_iter().map(|a| (a.id, a.name)).collect(),
+        Err(e) => {
+            print_request_error(e);
+            if logout_after_operation {
+                let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                match logout_res {
+                    Ok(_) => {}
+                    Err(_) => {
+                        process::exit(1);
+                    }
+                }
+            }
+            process::exit(1);
+        }
+    };
+
+    // Find the media assets to include, oldest first
+
+    let media_ids: Vec<u64> = match search {
+        Some(saved_search) => {
+            let filter = BatchMediaFilter::default().with_saved_search(saved_search);
+
+            let media_list =
+                collect_batch_media(&global_opts, &vault_url, logout_after_operation, filter).await;
+
+            media_list.iter().rev().map(|m| m.id).collect()
+        }
+        None => {
+            let mut ids: Vec<u64> = Vec::new();
+            let mut page: u32 = 0;
+            let mut finished = false;
+
+            while !finished {
+                let search_res = api_call_search(
+                    &vault_url,
+                    None,
+                    true,
+                    page,
+                    MAX_SEARCH_PAGE_LIMIT as u32,
+                    global_opts.debug,
+                )
+                .await;
+
+                let search_result = match search_res {
+                    Ok(r) => r,
+                    Err(e) => {
+                        print_request_error(e);
+                        if logout_after_operation {
+                            let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                            match logout_res {
+                                Ok(_) => {}
+                                Err(_) => {
+                                    process::exit(1);
+                                }
+                            }
+                        }
+                        process::exit(1);
+                    }
+                };
+
+                ids.extend(search_result.page_items.iter().map(|m| m.id));
+
+                page += 1;
+                finished = search_result.page_items.is_empty() || page >= search_result.page_count;
+            }
+
+            ids
+        }
+    };
+
+    // Fetch the metadata of each media asset
+
+    let mut catalog: Vec<CatalogEntry> = Vec::new();
+    let total_count = media_ids.len();
+
+    for (i, media_id) in media_ids.iter().enumerate() {
+        let n_done = i + 1;
+        let media_id_str = identifier_to_string(*media_id);
+
+        eprintln!("[{n_done}/{total_count}] Fetching metadata of {media_id_str}...");
+
+        let entry_res =
+            get_catalog_entry(&vault_url, *media_id, &tags_map, global_opts.debug).await;
+
+        match entry_res {
+            Ok(entry) => {
+                catalog.push(entry);
+            }
+            Err(RequestError::StatusCode(status)) | Err(RequestError::Api { status, .. })
+                if status == 404 =>
+            {
+                eprintln!("Skipped {media_id_str}: The media asset no longer exists");
+            }
+            Err(e) => {
+                print_request_error(e);
+                if logout_after_operation {
+                    let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                process::exit(1);
+            }
+        }
+    }
+
+    if logout_after_operation {
+        let logout_res = do_logout(&global_opts, &vault_url).await;
+
+        match logout_res {
+            Ok(_) => {}
+            Err(_) => {
+                process::exit(1);
+            }
+        }
+    }
+
+    // Write catalog
+
+    let write_res = match catalog_format {
+        CatalogFormat::Csv => write_catalog_csv(&output, &catalog),
+        CatalogFormat::JsonLines => write_catalog_json_lines(&output, &catalog),
+        CatalogFormat::Sqlite => write_catalog_sqlite(&output, &catalog, &albums_names),
+    };
+
+    if let Err(e_str) = write_res {
+        eprintln!("Could not write the catalog file: {output}. Error: {e_str}");
+        process::exit(1);
+    }
+
+    let entries_count = catalog.len();
+
+    eprintln!("Done. Successfully exported {entries_count} media assets into {output}");
+}
+
+async fn get_catalog_entry(
+    vault_url: &VaultURI,
+    media_id: u64,
+    tags_map: &HashMap<u64, String>,
+    debug: bool,
+) -> Result<CatalogEntry, RequestError> {
+    let metadata = api_call_get_media(vault_url, media_id, debug).await?;
+    let albums = api_call_get_media_albums(vault_url, media_id, debug).await?;
+    let stats = api_call_get_media_stats(vault_url, media_id, debug).await?;
+
+    Ok(CatalogEntry {
+        id: metadata.id,
+        media_type: metadata.media_type.to_type_string(),
+        title: metadata.title,
+        description: metadata.description,
+        tags: tags_names_from_ids(&metadata.tags, tags_map),
+        duration: metadata.duration,
+        width: metadata.width,
+        height: metadata.height,
+        fps: metadata.fps,
+        force_start_beginning: metadata.force_start_beginning.unwrap_or(false),
+        is_anim: metadata.is_anim.unwrap_or(false),
+        upload_time: metadata.upload_time,
+        albums,
+        meta_size: stats.meta_size,
+        assets_size: stats.assets.iter().map(|a| a.size).sum(),
+        assets: stats.assets,
+    })
+}
+
+pub const CATALOG_CSV_HEADER: &[&str] = &[
+    "id",
+    "type",
+    "title",
+    "description",
+    "tags",
+    "duration",
+    "width",
+    "height",
+    "fps",
+    "force_start_beginning",
+    "is_anim",
+    "upload_time",
+    "upload_date",
+    "albums",
+    "meta_size",
+    "assets_size",
+];
+
+fn write_catalog_csv(path: &str, catalog: &[CatalogEntry]) -> Result<(), String> {
+    let mut writer = csv::Writer::from_path(path).map_err(|e| e.to_string())?;
+
+    writer
+        .write_record(CATALOG_CSV_HEADER)
+        .map_err(|e| e.to_string())?;
+
+    for entry in catalog {
+        let albums: Vec<String> = entry.albums.iter().map(|a| a.to_string()).collect();
+
+        writer
+            .write_record([
+                entry.id.to_string(),
+                entry.media_type.clone(),
+                entry.title.clone(),
+                entry.description.clone(),
+                entry.tags.join(" "),
+                entry.duration.map(|d| d.to_string()).unwrap_or_default(),
+                entry.width.map(|w| w.to_string()).unwrap_or_default(),
+                entry.height.map(|h| h.to_string()).unwrap_or_default(),
+                entry.fps.map(|f| f.to_string()).unwrap_or_default(),
+                entry.force_start_beginning.to_string(),
+                entry.is_anim.to_string(),
+                entry.upload_time.to_string(),
+                format_date(entry.upload_time),
+                albums.join(" "),
+                entry.meta_size.to_string(),
+                entry.assets_size.to_string(),
+            ])
+            .map_err(|e| e.to_string())?;
+    }
+
+    writer.flush().map_err(|e| e.to_string())
+}
+
+fn write_catalog_json_lines(path: &str, catalog: &[CatalogEntry]) -> Result<(), String> {
+    let mut catalog_str = String::new();
+
+    for entry in catalog {
+        catalog_str.push_str(&serde_json::to_string(entry).unwrap());
+        catalog_str.push('\n');
+    }
+
+    std::fs::write(path, catalog_str).map_err(|e| e.to_string())
+}
+
+fn write_catalog_sqlite(
+    path: &str,
+    catalog: &[CatalogEntry],
+    albums_names: &HashMap<u64, String>,
+) -> Result<(), String> {
+    if Path::new(path).exists() {
+        std::fs::remove_file(path).map_err(|e| e.to_string())?;
+    }
+
+    let mut conn = rusqlite::Connection::open(path).map_err(|e| e.to_string())?;
+
+    conn.execute_batch(
+        "CREATE TABLE media (
+            id INTEGER PRIMARY KEY,
+            type TEXT NOT NULL,
+            title TEXT NOT NULL,
+            description TEXT NOT NULL,
+            duration REAL,
+            width INTEGER,
+            height INTEGER,
+            fps INTEGER,
+            force_start_beginning INTEGER NOT NULL,
+            is_anim INTEGER NOT NULL,
+            upload_time INTEGER NOT NULL,
+            meta_size INTEGER NOT NULL,
+            assets_size INTEGER NOT NULL
+        );
+        CREATE TABLE media_tags (media_id INTEGER NOT NULL, tag TEXT NOT NULL);
+        CREATE TABLE albums (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+        CREATE TABLE media_albums (media_id INTEGER NOT NULL, album_id INTEGER NOT NULL);
+        CREATE TABLE media_assets (
+            media_id INTEGER NOT NULL,
+            asset_id INTEGER NOT NULL,
+            type TEXT NOT NULL,
+            name TEXT NOT NULL,
+            size INTEGER NOT NULL
+        );
+        CREATE INDEX media_tags_tag ON media_tags (tag);
+        CREATE INDEX media_albums_album ON media_albums (album_id);",
+    )
+    .map_err(|e| e.to_string())?;
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    for (album_id, album_name) in albums_names.iter() {
+        tx.execute(
+            "INSERT INTO albums (id, name) VALUES (?1, ?2)",
+            rusqlite::params![*album_id as i64, album_name],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    for entry in catalog {
+        let media_id = entry.id as i64;
+
+        tx.execute(
+            "INSERT INTO media (id, type, title, description, duration, width, height, fps, force_start_beginning, is_anim, upload_time, meta_size, assets_size) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            rusqlite::params![
+                media_id,
+                entry.media_type,
+                entry.title,
+                entry.description,
+                entry.duration,
+                entry.width,
+                entry.height,
+                entry.fps,
+                entry.force_start_beginning,
+                entry.is_anim,
+                entry.upload_time,
+                entry.meta_size as i64,
+                entry.assets_size as i64,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+
+        for tag in entry.tags.iter() {
+            tx.execute(
+                "INSERT INTO media_tags (media_id, tag) VALUES (?1, ?2)",
+                rusqlite::params![media_id, tag],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+
+        for album_id in entry.albums.iter() {
+            tx.execute(
+                "INSERT INTO media_albums (media_id, album_id) VALUES (?1, ?2)",
+                rusqlite::params![media_id, *album_id as i64],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+
+        for asset in entry.assets.iter() {
+            tx.execute(
+                "INSERT INTO media_assets (media_id, asset_id, type, name, size) VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![
+                    media_id,
+                    asset.id as i64,
+                    asset.asset_type,
+                    asset.name,
+                    asset.size as i64,
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())
+}
@@ -0,0 +1,34 @@
+// Export command
+
+use clap::Subcommand;
+
+use super::{export_verify::run_cmd_export_verify, CommandGlobalOptions};
+
+#[derive(Subcommand)]
+pub enum ExportCommand {
+    /// Verifies an export (media, album or vault), checking every file exists and matches the sizes and checksums of the manifest.
+    Verify {
+        /// Path to the folder or archive (tar or zip) to verify. Use - to read a tar archive from the standard input.
+        path: String,
+
+        /// Also compare the exported metadata with the media assets and albums in the vault
+        #[arg(short, long)]
+        remote: bool,
+
+        /// Age identity file to decrypt the archive, if it was encrypted for a public key. For passphrase encrypted archives, the passphrase is asked for, unless the PMV_ARCHIVE_PASSPHRASE environment variable is set.
+        #[arg(long)]
+        identity: Option<String>,
+    },
+}
+
+pub async fn run_export_cmd(global_opts: CommandGlobalOptions, cmd: ExportCommand) {
+    match cmd {
+        ExportCommand::Verify {
+            path,
+            remote,
+            identity,
+        } => {
+            run_cmd_export_verify(global_opts, path, remote, identity).await;
+        }
+    }
+}
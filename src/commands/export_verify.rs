@@ -0,0 +1,605 @@
+// Export verification command
+
+use std::{collections::HashMap, path::Path, process};
+
+use crate::{
+    api::{api_call_get_album, api_call_get_media, api_call_get_tags},
+    commands::{logout::do_logout, media_import::extract_import_archive},
+    models::{
+        tags_map_from_list, AlbumMetadataExport, ExportManifest, MediaMetadataExport,
+        VaultMetadataExport,
+    },
+    tools::{
+        compute_file_checksum, ensure_login, identifier_to_string, is_archive_path,
+        parse_vault_uri, remove_temp_folder, RequestError, VaultURI, EXPORT_MANIFEST_FILE,
+    },
+};
+
+use super::{get_vault_url, print_request_error, CommandGlobalOptions};
+
+pub async fn run_cmd_export_verify(
+    global_opts: CommandGlobalOptions,
+    path: String,
+    remote: bool,
+    identity: Option<String>,
+) {
+    // Connect to the vault, only if the export is compared with it
+
+    let mut remote_vault: Option<RemoteVault> = None;
+    let mut logout_after_operation = false;
+
+    if remote {
+        let url_parse_res = parse_vault_uri(get_vault_url(&global_opts.vault_url));
+
+        if url_parse_res.is_err() {
+            match url_parse_res.err().unwrap() {
+                crate::tools::VaultURIParseError::InvalidProtocol => {
+                    eprintln!("Invalid vault URL provided. Must be an HTTP or HTTPS URL.");
+                }
+                crate::tools::VaultURIParseError::URLError(e) => {
+                    let err_msg = e.to_string();
+                    eprintln!("Invalid vault URL provided: {err_msg}");
+                }
+            }
+
+            process::exit(1);
+        }
+
+        let mut vault_url = url_parse_res.unwrap();
+
+        logout_after_operation = vault_url.is_login();
+        let login_result = ensure_login(&vault_url, &None, global_opts.debug).await;
+
+        if login_result.is_err() {
+            process::exit(1);
+        }
+
+        vault_url = login_result.unwrap();
+
+        let tags_res = api_call_get_tags(&vault_url, global_opts.debug).await;
+
+        let tags = match tags_res {
+            Ok(t) => tags_map_from_list(&t),
+            Err(e) => {
+                print_request_error(e);
+                if logout_after_operation {
+                    let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                process::exit(1);
+            }
+        };
+
+        remote_vault = Some(RemoteVault {
+            vault_url,
+            debug: global_opts.debug,
+            tags,
+        });
+    }
+
+    // Archives are extracted into a temporary folder
+
+    let mut folder = path.clone();
+    let mut archive_temp_folder: Option<String> = None;
+
+    if is_archive_path(&path) {
+        let (temp_folder, import_folder) = extract_import_archive(&path, identity).await;
+
+        archive_temp_folder = Some(temp_folder);
+        folder = import_folder;
+    }
+
+    // Verify
+
+    let mut verifier = ExportVerifier {
+        problems: 0,
+        media_count: 0,
+        remote: remote_vault,
+    };
+
+    verifier.verify_export_folder(&folder).await;
+
+    // Done
+
+    if let Some(temp_folder) = &archive_temp_folder {
+        remove_temp_folder(temp_folder).await;
+    }
+
+    if logout_after_operation {
+        if let Some(r) = &verifier.remote {
+            let logout_res = do_logout(&global_opts, &r.vault_url).await;
+
+            match logout_res {
+                Ok(_) => {}
+                Err(_) => {
+                    process::exit(1);
+                }
+            }
+        }
+    }
+
+    let problems = verifier.problems;
+    let media_count = verifier.media_count;
+
+    if problems > 0 {
+        eprintln!("Verification failed. Found {problems} problems in {path} ({media_count} media assets checked)");
+        process::exit(1);
+    }
+
+    eprintln!(
+        "Verification passed. The export {path} is complete ({media_count} media assets checked)"
+    );
+}
+
+struct RemoteVault {
+    vault_url: VaultURI,
+    debug: bool,
+    tags: HashMap<u64, String>,
+}
+
+struct ExportVerifier {
+    problems: usize,
+    media_count: usize,
+    remote: Option<RemoteVault>,
+}
+
+impl ExportVerifier {
+    fn report(&mut self, folder: &str, problem: &str) {
+        eprintln!("Error: {folder}: {problem}");
+        self.problems += 1;
+    }
+
+    async fn verify_export_folder(&mut self, folder: &str) {
+        let metadata_path = Path::new(folder).join("metadata.json");
+
+        let metadata_str = match std::fs::read_to_string(&metadata_path) {
+            Ok(s) => s,
+            Err(e) => {
+                let e_str = e.to_string();
+                self.report(
+                    folder,
+                    &format!("Could not read metadata.json. Error: {e_str}"),
+                );
+                return;
+            }
+        };
+
+        if let Ok(vault_metadata) = serde_json::from_str::<VaultMetadataExport>(&metadata_str) {
+            eprintln!("Verifying vault export: {folder}");
+            self.verify_vault_folder(folder, vault_metadata).await;
+        } else if let Ok(album_metadata) =
+            serde_json::from_str::<AlbumMetadataExport>(&metadata_str)
+        {
+            eprintln!("Verifying album export: {folder}");
+            self.verify_album_folder(folder, album_metadata).await;
+        } else {
+            eprintln!("Verifying media export: {folder}");
+            self.verify_media_folder(folder, None).await;
+        }
+    }
+
+    async fn verify_vault_folder(&mut self, folder: &str, metadata: VaultMetadataExport) {
+        let mut files: Vec<String> = vec!["metadata.json".to_string()];
+
+        for album in metadata.albums.iter() {
+            if let Some(thumbnail) = &album.thumbnail {
+                files.push(thumbnail.clone());
+            }
+        }
+
+        self.verify_files(folder, &files);
+
+        for media_folder in metadata.media_list.iter() {
+            self.verify_media_sub_folder(folder, media_folder).await;
+        }
+
+        for album in metadata.albums.iter() {
+            self.compare_remote_album(folder, album.id, &album.name, &album.media_list)
+                .await;
+        }
+    }
+
+    async fn verify_album_folder(&mut self, folder: &str, metadata: AlbumMetadataExport) {
+        let mut files: Vec<String> = vec!["metadata.json".to_string()];
+
+        if let Some(thumbnail) = &metadata.thumbnail {
+            files.push(thumbnail.clone());
+        }
+
+        self.verify_files(folder, &files);
+
+        for media_folder in metadata.media_list.iter() {
+            self.verify_media_sub_folder(folder, media_folder).await;
+        }
+
+        match metadata.id {
+            Some(album_id) => {
+                self.compare_remote_album(folder, album_id, &metadata.name, &metadata.media_list)
+                    .await;
+            }
+            None => {
+                if self.remote.is_some() {
+                    self.report(
+                        folder,
+                        "The album ID is unknown, so it cannot be compared with the vault.",
+                    );
+                }
+            }
+        }
+    }
+
+    async fn verify_media_sub_folder(&mut self, folder: &str, media_folder: &str) {
+        let media_folder_path = Path::new(folder)
+            .join(media_folder)
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        if !Path::new(&media_folder_path).is_dir() {
+            self.report(&media_folder_path, "Missing media folder.");
+            return;
+        }
+
+        let media_id: Option<u64> = media_folder
+            .strip_prefix("media_")
+            .and_then(|id| id.parse().ok());
+
+        self.verify_media_folder(&media_folder_path, media_id).await;
+    }
+
+    async fn verify_media_folder(&mut self, folder: &str, expected_id: Option<u64>) {
+        self.media_count += 1;
+
+        let metadata_path = Path::new(folder).join("metadata.json");
+
+        let parsed_metadata: Result<MediaMetadataExport, String> =
+            match std::fs::read_to_string(&metadata_path) {
+                Ok(s) => serde_json::from_str(&s).map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+
+        let metadata = match parsed_metadata {
+            Ok(m) => m,
+            Err(e_str) => {
+                self.report(
+                    folder,
+                    &format!("Could not read metadata.json. Error: {e_str}"),
+                );
+                return;
+            }
+        };
+
+        let mut files: Vec<String> = vec!["metadata.json".to_string()];
+
+        match &metadata.original {
+            Some(original) => {
+                files.push(original.clone());
+            }
+            None => {
+                self.report(folder, "No original file specified in metadata.json.");
+            }
+        }
+
+        if let Some(thumbnail) = &metadata.thumbnail {
+            files.push(thumbnail.clone());
+        }
+
+        if let Some(notes) = &metadata.notes {
+            files.push(notes.clone());
+        }
+
+        if let Some(ext_desc) = &metadata.ext_desc {
+            files.push(ext_desc.clone());
+        }
+
+        if let Some(subtitles) = &metadata.subtitles {
+            for subtitle in subtitles {
+                files.push(subtitle.file.clone());
+            }
+        }
+
+        if let Some(audios) = &metadata.audios {
+            for audio in audios {
+                files.push(audio.file.clone());
+            }
+        }
+
+        if let Some(attachments) = &metadata.attachments {
+            for attachment in attachments {
+                files.push(attachment.file.clone());
+            }
+        }
+
+        self.verify_files(folder, &files);
+
+        match metadata.id.or(expected_id) {
+            Some(media_id) => {
+                self.compare_remote_media(folder, media_id, &metadata).await;
+            }
+            None => {
+                if self.remote.is_some() {
+                    self.report(
+                        folder,
+                        "The media ID is unknown, so it cannot be compared with the vault.",
+                    );
+                }
+            }
+        }
+    }
+
+    /// Checks the referenced files exist, and the manifest matches the files in the folder
+    fn verify_files(&mut self, folder: &str, files: &[String]) {
+        for file in files {
+            if !Path::new(folder).join(file).is_file() {
+                self.report(folder, &format!("Missing file: {file}"));
+            }
+        }
+
+        let manifest_path = Path::new(folder).join(EXPORT_MANIFEST_FILE);
+
+        if !manifest_path.exists() {
+            self.report(
+                folder,
+                "Missing manifest.json. The sizes and checksums cannot be verified.",
+            );
+            return;
+        }
+
+        let parsed_manifest: Result<ExportManifest, String> =
+            match std::fs::read_to_string(&manifest_path) {
+                Ok(s) => serde_json::from_str(&s).map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+
+        let manifest = match parsed_manifest {
+            Ok(m) => m,
+            Err(e_str) => {
+                self.report(
+                    folder,
+                    &format!("Could not read manifest.json. Error: {e_str}"),
+                );
+                return;
+            }
+        };
+
+        for file in files {
+            if !manifest.files.iter().any(|f| f.name == *file) {
+                self.report(folder, &format!("File not listed in the manifest: {file}"));
+            }
+        }
+
+        for manifest_file in manifest.files.iter() {
+            let file_name = &manifest_file.name;
+            let file_path = Path::new(folder).join(file_name);
+
+            if !file_path.is_file() {
+                if !files.contains(file_name) {
+                    self.report(folder, &format!("Missing file: {file_name}"));
+                }
+                continue;
+            }
+
+            match compute_file_checksum(&file_path) {
+                Ok((size, sha256)) => {
+                    let expected_size = manifest_file.size;
+
+                    if size != expected_size {
+                        self.report(folder, &format!("Size mismatch for {file_name}. Expected {expected_size} bytes, found {size} bytes."));
+                    } else if sha256 != manifest_file.sha256 {
+                        self.report(folder, &format!("Checksum mismatch for {file_name}"));
+                    }
+                }
+                Err(e_str) => {
+                    self.report(
+                        folder,
+                        &format!("Could not read file {file_name}. Error: {e_str}"),
+                    );
+                }
+            }
+        }
+    }
+
+    async fn compare_remote_media(
+        &mut self,
+        folder: &str,
+        media_id: u64,
+        metadata: &MediaMetadataExport,
+    ) {
+        let remote = match &self.remote {
+            Some(r) => r,
+            None => {
+                return;
+            }
+        };
+
+        let media_id_str = identifier_to_string(media_id);
+
+        let api_res = api_call_get_media(&remote.vault_url, media_id, remote.debug).await;
+
+        let live = match api_res {
+            Ok(m) => m,
+            Err(RequestError::StatusCode(status)) | Err(RequestError::Api { status, .. })
+                if status == 404 =>
+            {
+                self.report(
+                    folder,
+                    &format!("Media {media_id_str} does not exist in the vault."),
+                );
+                return;
+            }
+            Err(e) => {
+                print_request_error(e);
+                self.report(
+                    folder,
+                    &format!("Could not fetch media {media_id_str} from the vault."),
+                );
+                return;
+            }
+        };
+
+        let mut differences: Vec<String> = Vec::new();
+
+        if metadata.title.as_ref().is_some_and(|t| *t != live.title) {
+            differences.push("title".to_string());
+        }
+
+        if metadata
+            .description
+            .as_ref()
+            .is_some_and(|d| *d != live.description)
+        {
+            differences.push("description".to_string());
+        }
+
+        let mut live_tags: Vec<String> = live
+            .tags
+            .iter()
+            .map(|t| remote.tags.get(t).cloned().unwrap_or(t.to_string()))
+            .collect();
+        live_tags.sort();
+
+        let mut export_tags = metadata.tags.clone().unwrap_or_default();
+        export_tags.sort();
+
+        if live_tags != export_tags {
+            differences.push("tags".to_string());
+        }
+
+        let mut live_subtitles: Vec<String> = live
+            .subtitles
+            .iter()
+            .flatten()
+            .map(|s| s.id.clone())
+            .collect();
+        live_subtitles.sort();
+
+        let mut export_subtitles: Vec<String> = metadata
+            .subtitles
+            .iter()
+            .flatten()
+            .map(|s| s.id.clone())
+            .collect();
+        export_subtitles.sort();
+
+        if live_subtitles != export_subtitles {
+            differences.push("subtitles".to_string());
+        }
+
+        let mut live_audios: Vec<String> =
+            live.audios.iter().flatten().map(|a| a.id.clone()).collect();
+        live_audios.sort();
+
+        let mut export_audios: Vec<String> = metadata
+            .audios
+            .iter()
+            .flatten()
+            .map(|a| a.id.clone())
+            .collect();
+        export_audios.sort();
+
+        if live_audios != export_audios {
+            differences.push("audio tracks".to_string());
+        }
+
+        let mut live_attachments: Vec<String> = live
+            .attachments
+            .iter()
+            .flatten()
+            .map(|a| a.name.clone())
+            .collect();
+        live_attachments.sort();
+
+        let mut export_attachments: Vec<String> = metadata
+            .attachments
+            .iter()
+            .flatten()
+            .map(|a| a.name.clone())
+            .collect();
+        export_attachments.sort();
+
+        if live_attachments != export_attachments {
+            differences.push("attachments".to_string());
+        }
+
+        let live_time_slices = live.time_slices.as_ref().map(|t| t.len()).unwrap_or(0);
+        let export_time_slices = metadata.time_slices.as_ref().map(|t| t.len()).unwrap_or(0);
+
+        if live_time_slices != export_time_slices {
+            differences.push("time slices".to_string());
+        }
+
+        if !differences.is_empty() {
+            let differences_str = differences.join(", ");
+            self.report(
+                folder,
+                &format!("Media {media_id_str} differs from the vault in: {differences_str}"),
+            );
+        }
+    }
+
+    async fn compare_remote_album(
+        &mut self,
+        folder: &str,
+        album_id: u64,
+        name: &str,
+        media_list: &[String],
+    ) {
+        let remote = match &self.remote {
+            Some(r) => r,
+            None => {
+                return;
+            }
+        };
+
+        let album_id_str = identifier_to_string(album_id);
+
+        let api_res = api_call_get_album(&remote.vault_url, album_id, remote.debug).await;
+
+        let live = match api_res {
+            Ok(a) => a,
+            Err(RequestError::StatusCode(status)) | Err(RequestError::Api { status, .. })
+                if status == 404 =>
+            {
+                self.report(
+                    folder,
+                    &format!("Album {album_id_str} does not exist in the vault."),
+                );
+                return;
+            }
+            Err(e) => {
+                print_request_error(e);
+                self.report(
+                    folder,
+                    &format!("Could not fetch album {album_id_str} from the vault."),
+                );
+                return;
+            }
+        };
+
+        if live.name != name {
+            self.report(
+                folder,
+                &format!("Album {album_id_str} has a different name in the vault."),
+            );
+        }
+
+        let live_media_list: Vec<String> = live
+            .list
+            .iter()
+            .map(|m| "media_".to_string() + &m.id.to_string())
+            .collect();
+
+        if live_media_list != media_list {
+            self.report(
+                folder,
+                &format!("Album {album_id_str} has a different list of media assets in the vault."),
+            );
+        }
+    }
+}
@@ -0,0 +1,331 @@
+// Import validation (dry run)
+
+use std::{collections::HashSet, path::Path, process};
+
+use crate::{
+    commands::media_import::{extract_import_archive, validate_media_import},
+    models::{parse_tag_name, AlbumMetadataExport, MediaMetadataExport},
+    tools::{
+        identifier_to_string, is_archive_path, parse_identifier, remove_temp_folder, to_csv_string,
+    },
+};
+
+/// Problems and planned operations found when validating an import
+struct ImportPlan {
+    problems: Vec<String>,
+    operations: Vec<String>,
+}
+
+impl ImportPlan {
+    fn problem(&mut self, folder: &str, problem: String) {
+        self.problems.push(format!("{folder}: {problem}"));
+    }
+
+    fn operation(&mut self, operation: String) {
+        self.operations.push(operation);
+    }
+
+    fn print_and_exit(self, path: &str) {
+        if !self.operations.is_empty() {
+            println!("Planned operations:");
+
+            for (i, operation) in self.operations.iter().enumerate() {
+                let n = i + 1;
+                println!("  {n}. {operation}");
+            }
+        }
+
+        for problem in self.problems.iter() {
+            eprintln!("Error: {problem}");
+        }
+
+        let problems_count = self.problems.len();
+
+        if problems_count > 0 {
+            eprintln!("Dry run finished. Found {problems_count} problems in {path}. Nothing was imported.");
+            process::exit(1);
+        }
+
+        eprintln!("Dry run finished. No problems found in {path}. Nothing was imported.");
+    }
+}
+
+pub async fn run_cmd_import_media_dry_run(
+    path: String,
+    album: Option<String>,
+    identity: Option<String>,
+) {
+    let album_param: Option<u64> = match album {
+        Some(album_id) => match parse_identifier(&album_id) {
+            Ok(id) => Some(id),
+            Err(_) => {
+                eprintln!("Invalid album identifier specified.");
+                process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let (folder, archive_temp_folder) = get_dry_run_folder(&path, identity).await;
+
+    let mut plan = ImportPlan {
+        problems: Vec::new(),
+        operations: Vec::new(),
+    };
+
+    let album_str = album_param.map(|id| "album ".to_string() + &identifier_to_string(id));
+
+    plan_media_import(&mut plan, &folder, "", album_str.as_deref());
+
+    if let Some(temp_folder) = &archive_temp_folder {
+        remove_temp_folder(temp_folder).await;
+    }
+
+    plan.print_and_exit(&path);
+}
+
+pub async fn run_cmd_import_album_dry_run(
+    path: String,
+    into: Option<String>,
+    identity: Option<String>,
+) {
+    let into_album: Option<u64> = match into {
+        Some(album_id) => match parse_identifier(&album_id) {
+            Ok(id) => Some(id),
+            Err(_) => {
+                eprintln!("Invalid album identifier specified.");
+                process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let (folder, archive_temp_folder) = get_dry_run_folder(&path, identity).await;
+
+    let mut plan = ImportPlan {
+        problems: Vec::new(),
+        operations: Vec::new(),
+    };
+
+    validate_album_import(&mut plan, &folder, into_album);
+
+    if let Some(temp_folder) = &archive_temp_folder {
+        remove_temp_folder(temp_folder).await;
+    }
+
+    plan.print_and_exit(&path);
+}
+
+/// Gets the folder to validate, extracting the archive if necessary
+async fn get_dry_run_folder(path: &str, identity: Option<String>) -> (String, Option<String>) {
+    if is_archive_path(path) {
+        let (temp_folder, import_folder) = extract_import_archive(path, identity).await;
+        (import_folder, Some(temp_folder))
+    } else {
+        (path.to_string(), None)
+    }
+}
+
+fn validate_album_import(plan: &mut ImportPlan, folder: &str, into_album: Option<u64>) {
+    let metadata_file = Path::new(folder).join("metadata.json");
+
+    let parsed_metadata: Result<AlbumMetadataExport, String> =
+        match std::fs::read_to_string(&metadata_file) {
+            Ok(s) => serde_json::from_str(&s).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+
+    let metadata = match parsed_metadata {
+        Ok(m) => m,
+        Err(e_str) => {
+            plan.problem(
+                folder,
+                format!("Could not read metadata file. Error: {e_str}"),
+            );
+            return;
+        }
+    };
+
+    if metadata.name.is_empty() {
+        plan.problem(folder, "The album name cannot be empty.".to_string());
+    }
+
+    let target_album = match into_album {
+        Some(album_id) => "album ".to_string() + &identifier_to_string(album_id),
+        None => {
+            let album_name_csv = to_csv_string(&metadata.name);
+            plan.operation(format!("Create album {album_name_csv}"));
+
+            "the new album".to_string()
+        }
+    };
+
+    if let Some(thumbnail) = &metadata.thumbnail {
+        validate_file_exists(plan, folder, thumbnail);
+
+        if into_album.is_none() {
+            plan.operation(format!("Upload album thumbnail: {thumbnail}"));
+        }
+    }
+
+    let mut seen_folders: HashSet<&String> = HashSet::new();
+
+    for media_folder in metadata.media_list.iter() {
+        if !seen_folders.insert(media_folder) {
+            plan.problem(
+                folder,
+                format!("Media folder listed more than once: {media_folder}"),
+            );
+            continue;
+        }
+
+        let media_path = Path::new(folder)
+            .join(media_folder)
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        if !Path::new(&media_path).is_dir() {
+            plan.problem(folder, format!("Missing media folder: {media_folder}"));
+            continue;
+        }
+
+        plan_media_import(plan, &media_path, media_folder, Some(&target_album));
+    }
+}
+
+fn plan_media_import(plan: &mut ImportPlan, folder: &str, label: &str, album: Option<&str>) {
+    let prefix = if label.is_empty() {
+        "".to_string()
+    } else {
+        format!("[{label}] ")
+    };
+
+    let metadata_file = Path::new(folder).join("metadata.json");
+
+    let parsed_metadata: Result<MediaMetadataExport, String> =
+        match std::fs::read_to_string(&metadata_file) {
+            Ok(s) => serde_json::from_str(&s).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+
+    let metadata = match parsed_metadata {
+        Ok(m) => m,
+        Err(e_str) => {
+            plan.problem(
+                folder,
+                format!("Could not read metadata file. Error: {e_str}"),
+            );
+            return;
+        }
+    };
+
+    for problem in validate_media_import(folder, &metadata) {
+        plan.problem(folder, problem);
+    }
+
+    // Original
+
+    if let Some(original) = &metadata.original {
+        let title = metadata.title.clone().unwrap_or_default();
+        let title_csv = to_csv_string(&title);
+        let album_str = match album {
+            Some(a) => format!(" into {a}"),
+            None => "".to_string(),
+        };
+
+        plan.operation(format!(
+            "{prefix}Upload media asset: {original} (title: {title_csv}){album_str}"
+        ));
+    }
+
+    // Tags
+
+    if let Some(tags) = &metadata.tags {
+        for tag in tags {
+            let parsed_tag = parse_tag_name(tag);
+
+            if !parsed_tag.is_empty() {
+                plan.operation(format!("{prefix}Add tag: {parsed_tag}"));
+            }
+        }
+    }
+
+    // Description
+
+    if let Some(description) = &metadata.description {
+        if !description.is_empty() {
+            plan.operation(format!("{prefix}Set description"));
+        }
+    }
+
+    plan.operation(format!("{prefix}Set extra parameters"));
+
+    // Thumbnail
+
+    if let Some(thumbnail) = &metadata.thumbnail {
+        plan.operation(format!("{prefix}Upload thumbnail: {thumbnail}"));
+    }
+
+    // Extended description
+
+    if let Some(ext_desc) = &metadata.ext_desc {
+        plan.operation(format!("{prefix}Set extended description: {ext_desc}"));
+    }
+
+    // Time slices
+
+    if let Some(time_slices) = &metadata.time_slices {
+        if !time_slices.is_empty() {
+            let time_slices_count = time_slices.len();
+            plan.operation(format!("{prefix}Set time slices ({time_slices_count})"));
+        }
+    }
+
+    // Image notes
+
+    if let Some(notes) = &metadata.notes {
+        plan.operation(format!("{prefix}Set image notes: {notes}"));
+    }
+
+    // Subtitles
+
+    if let Some(subtitles) = &metadata.subtitles {
+        for subtitle in subtitles {
+            let sub_id = &subtitle.id;
+            let sub_file = &subtitle.file;
+            plan.operation(format!("{prefix}Upload subtitles {sub_id}: {sub_file}"));
+        }
+    }
+
+    // Audios
+
+    if let Some(audios) = &metadata.audios {
+        for audio in audios {
+            let track_id = &audio.id;
+            let audio_file = &audio.file;
+            plan.operation(format!(
+                "{prefix}Upload audio track {track_id}: {audio_file}"
+            ));
+        }
+    }
+
+    // Attachments
+
+    if let Some(attachments) = &metadata.attachments {
+        for att in attachments {
+            let att_name = &att.name;
+            let att_file = &att.file;
+            plan.operation(format!("{prefix}Upload attachment {att_name}: {att_file}"));
+        }
+    }
+}
+
+fn validate_file_exists(plan: &mut ImportPlan, folder: &str, file: &str) -> bool {
+    if Path::new(folder).join(file).is_file() {
+        true
+    } else {
+        plan.problem(folder, format!("Missing file: {file}"));
+        false
+    }
+}
@@ -0,0 +1,495 @@
+// Directory tree import command
+
+use std::{
+    cmp::Ordering,
+    path::{Path, PathBuf},
+    process,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    api::{
+        api_call_album_change_thumbnail, api_call_create_album, api_call_tag_add,
+        api_call_upload_media,
+    },
+    commands::logout::do_logout,
+    models::{parse_tag_name, AddTagBody, AlbumNameBody},
+    tools::{ask_user, ensure_login, identifier_to_string, parse_vault_uri, to_csv_string},
+};
+
+use super::{
+    get_vault_url, media_upload::UploaderProgressPrinter, print_request_error, CommandGlobalOptions,
+};
+
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "webp", "bmp", "tif", "tiff", "avif", "heic",
+];
+
+const VIDEO_EXTENSIONS: &[&str] = &[
+    "mp4", "mkv", "webm", "avi", "mov", "wmv", "flv", "m4v", "mpg", "mpeg", "3gp",
+];
+
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "wav", "ogg", "flac", "aac", "m4a", "opus", "wma"];
+
+/// Media files found in a directory of the tree
+struct ImportTreeFolder {
+    /// Name of the album, None for the files in the root directory
+    album_name: Option<String>,
+
+    /// Tags extracted from the path segments
+    path_tags: Vec<String>,
+
+    /// Media files, sorted by file name
+    files: Vec<PathBuf>,
+}
+
+pub async fn run_cmd_import_tree(
+    global_opts: CommandGlobalOptions,
+    root: String,
+    path_tags: bool,
+    tags: Option<String>,
+) {
+    if !Path::new(&root).is_dir() {
+        eprintln!("The path {root} is not a directory.");
+        process::exit(1);
+    }
+
+    // Scan the tree before logging in, so nothing is created if it fails
+
+    let mut folders: Vec<ImportTreeFolder> = Vec::new();
+
+    let scan_res = scan_import_tree(Path::new(&root), &[], &mut folders);
+
+    if let Err(e_str) = scan_res {
+        eprintln!("Could not read the directory tree: {root}. Error: {e_str}");
+        process::exit(1);
+    }
+
+    folders.retain(|f| !f.files.is_empty());
+
+    let n_total: usize = folders.iter().map(|f| f.files.len()).sum();
+    let n_albums = folders.iter().filter(|f| f.album_name.is_some()).count();
+
+    if n_total == 0 {
+        eprintln!("No media files found in {root}");
+        return;
+    }
+
+    let extra_tags: Vec<String> = tags
+        .unwrap_or("".to_string())
+        .split(' ')
+        .map(parse_tag_name)
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    let url_parse_res = parse_vault_uri(get_vault_url(&global_opts.vault_url));
+
+    if url_parse_res.is_err() {
+        match url_parse_res.err().unwrap() {
+            crate::tools::VaultURIParseError::InvalidProtocol => {
+                eprintln!("Invalid vault URL provided. Must be an HTTP or HTTPS URL.");
+            }
+            crate::tools::VaultURIParseError::URLError(e) => {
+                let err_msg = e.to_string();
+                eprintln!("Invalid vault URL provided: {err_msg}");
+            }
+        }
+
+        process::exit(1);
+    }
+
+    let mut vault_url = url_parse_res.unwrap();
+
+    let logout_after_operation = vault_url.is_login();
+    let login_result = ensure_login(&vault_url, &None, global_opts.debug).await;
+
+    if login_result.is_err() {
+        process::exit(1);
+    }
+
+    vault_url = login_result.unwrap();
+
+    // Ask confirmation
+
+    if !global_opts.auto_confirm {
+        eprintln!("Importing {n_total} media files from {root}, creating {n_albums} albums");
+        let confirmation = ask_user("Continue? y/n: ").await.unwrap_or("".to_string());
+
+        if confirmation.to_lowercase() != "y" {
+            if logout_after_operation {
+                let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                match logout_res {
+                    Ok(_) => {}
+                    Err(_) => {
+                        process::exit(1);
+                    }
+                }
+            }
+            process::exit(1);
+        }
+    }
+
+    // Import
+
+    let mut n_done: usize = 0;
+    let mut n_failed: usize = 0;
+
+    for folder in folders.iter() {
+        let album_id: Option<u64> = match &folder.album_name {
+            Some(album_name) => {
+                let api_res = api_call_create_album(
+                    &vault_url,
+                    AlbumNameBody {
+                        name: album_name.clone(),
+                    },
+                    global_opts.debug,
+                )
+                .await;
+
+                match api_res {
+                    Ok(added_album) => {
+                        let album_id = added_album.album_id;
+                        let album_name_csv = to_csv_string(album_name);
+
+                        eprintln!("Successfully created album #{album_id}: {album_name_csv}");
+
+                        Some(album_id)
+                    }
+                    Err(e) => {
+                        print_request_error(e);
+                        if logout_after_operation {
+                            let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                            match logout_res {
+                                Ok(_) => {}
+                                Err(_) => {
+                                    process::exit(1);
+                                }
+                            }
+                        }
+                        process::exit(1);
+                    }
+                }
+            }
+            None => None,
+        };
+
+        // The first image of the directory is used as the album thumbnail
+
+        if let Some(album_id) = album_id {
+            let thumbnail_file = folder
+                .files
+                .iter()
+                .find(|f| has_extension(f, IMAGE_EXTENSIONS));
+
+            if let Some(thumbnail_file) = thumbnail_file {
+                let thumbnail_file_path = thumbnail_file.to_str().unwrap().to_string();
+
+                let progress_printer = Arc::new(Mutex::new(UploaderProgressPrinter::new()));
+
+                let api_res = api_call_album_change_thumbnail(
+                    &vault_url,
+                    album_id,
+                    thumbnail_file_path.clone(),
+                    global_opts.debug,
+                    progress_printer,
+                )
+                .await;
+
+                match api_res {
+                    Ok(upload_res) => {
+                        eprintln!("Upload completed: {thumbnail_file_path}");
+
+                        let thumb_new_url = upload_res.url;
+
+                        eprintln!(
+                            "Successfully updated the thumbnail of album #{album_id}: {thumb_new_url}"
+                        );
+                    }
+                    Err(e) => {
+                        print_request_error(e);
+                    }
+                }
+            }
+        }
+
+        // Upload the files in order, so the album keeps the file name order
+
+        for file in folder.files.iter() {
+            n_done += 1;
+
+            let file_path = file.to_str().unwrap().to_string();
+
+            eprintln!("[{n_done}/{n_total}] Uploading {file_path}...");
+
+            let progress_printer = Arc::new(Mutex::new(UploaderProgressPrinter::new()));
+
+            let api_res = api_call_upload_media(
+                &vault_url,
+                file_path.clone(),
+                None,
+                album_id,
+                global_opts.debug,
+                progress_printer,
+            )
+            .await;
+
+            let media_id = match api_res {
+                Ok(upload_res) => upload_res.media_id,
+                Err(e) => {
+                    print_request_error(e);
+                    eprintln!("Error: Could not upload {file_path}");
+                    n_failed += 1;
+                    continue;
+                }
+            };
+
+            let media_id_str = identifier_to_string(media_id);
+
+            eprintln!("Upload completed: {file_path}");
+            eprintln!("Media asset created: {media_id_str}");
+
+            // Tags
+
+            let mut media_tags: Vec<&String> = Vec::new();
+
+            if path_tags {
+                media_tags.extend(folder.path_tags.iter());
+            }
+
+            media_tags.extend(extra_tags.iter());
+
+            for tag in media_tags {
+                let api_tag_res = api_call_tag_add(
+                    &vault_url,
+                    AddTagBody {
+                        media_id,
+                        tag_name: tag.clone(),
+                    },
+                    global_opts.debug,
+                )
+                .await;
+
+                match api_tag_res {
+                    Ok(_) => {
+                        eprintln!("Added tag {tag} to {media_id_str}");
+                    }
+                    Err(e) => {
+                        print_request_error(e);
+                    }
+                }
+            }
+        }
+    }
+
+    // Done
+
+    if logout_after_operation {
+        let logout_res = do_logout(&global_opts, &vault_url).await;
+
+        match logout_res {
+            Ok(_) => {}
+            Err(_) => {
+                process::exit(1);
+            }
+        }
+    }
+
+    let n_imported = n_total - n_failed;
+
+    eprintln!("Done. Imported {n_imported} media files into {n_albums} albums. Failed: {n_failed}");
+
+    if n_failed > 0 {
+        process::exit(1);
+    }
+}
+
+/// Scans a directory recursively, adding a folder for each directory
+/// Each directory becomes an album, named after its path relative to the root
+fn scan_import_tree(
+    dir: &Path,
+    segments: &[String],
+    folders: &mut Vec<ImportTreeFolder>,
+) -> Result<(), String> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    let mut sub_dirs: Vec<PathBuf> = Vec::new();
+
+    let entries = std::fs::read_dir(dir).map_err(|e| e.to_string())?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+
+        if file_name.starts_with('.') {
+            continue;
+        }
+
+        // Symbolic links to directories are not followed, to prevent loops
+        let file_type = entry.file_type().map_err(|e| e.to_string())?;
+        let entry_path = entry.path();
+
+        // The paths are sent to the uploader as strings, so they must be valid UTF-8
+        if entry_path.to_str().is_none() {
+            let entry_path_str = entry_path.to_string_lossy();
+            eprintln!("Warning: Skipped {entry_path_str} because its path is not valid UTF-8.");
+            continue;
+        }
+
+        if file_type.is_dir() {
+            sub_dirs.push(entry_path);
+        } else if entry_path.is_file() && is_media_file(&entry_path) {
+            files.push(entry_path);
+        }
+    }
+
+    files.sort_by(|a, b| compare_file_names(a, b));
+    sub_dirs.sort_by(|a, b| compare_file_names(a, b));
+
+    let album_name = if segments.is_empty() {
+        None
+    } else {
+        Some(segments.join("/"))
+    };
+
+    folders.push(ImportTreeFolder {
+        album_name,
+        path_tags: segments
+            .iter()
+            .map(|s| parse_tag_name(s))
+            .filter(|t| !t.is_empty())
+            .collect(),
+        files,
+    });
+
+    for sub_dir in sub_dirs {
+        let mut sub_segments = segments.to_vec();
+        sub_segments.push(sub_dir.file_name().unwrap().to_string_lossy().to_string());
+
+        scan_import_tree(&sub_dir, &sub_segments, folders)?;
+    }
+
+    Ok(())
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    match path.extension() {
+        Some(ext) => extensions.contains(&ext.to_string_lossy().to_lowercase().as_str()),
+        None => false,
+    }
+}
+
+fn is_media_file(path: &Path) -> bool {
+    has_extension(path, IMAGE_EXTENSIONS)
+        || has_extension(path, VIDEO_EXTENSIONS)
+        || has_extension(path, AUDIO_EXTENSIONS)
+}
+
+/// Compares file names, ignoring case and comparing digit runs as numbers,
+/// so "img2.jpg" goes before "img10.jpg"
+fn compare_file_names(a: &Path, b: &Path) -> Ordering {
+    let a_name = a.file_name().unwrap().to_string_lossy().to_lowercase();
+    let b_name = b.file_name().unwrap().to_string_lossy().to_lowercase();
+
+    let mut a_chars = a_name.chars().peekable();
+    let mut b_chars = b_name.chars().peekable();
+
+    loop {
+        match (a_chars.peek(), b_chars.peek()) {
+            (None, None) => return a_name.cmp(&b_name),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(ac), Some(bc)) if ac.is_ascii_digit() && bc.is_ascii_digit() => {
+                let mut a_num = String::new();
+                while let Some(c) = a_chars.next_if(|c| c.is_ascii_digit()) {
+                    a_num.push(c);
+                }
+
+                let mut b_num = String::new();
+                while let Some(c) = b_chars.next_if(|c| c.is_ascii_digit()) {
+                    b_num.push(c);
+                }
+
+                let a_trimmed = a_num.trim_start_matches('0');
+                let b_trimmed = b_num.trim_start_matches('0');
+
+                let ord = a_trimmed
+                    .len()
+                    .cmp(&b_trimmed.len())
+                    .then_with(|| a_trimmed.cmp(b_trimmed));
+
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            (Some(ac), Some(bc)) => {
+                let ord = ac.cmp(bc);
+
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compare(a: &str, b: &str) -> Ordering {
+        compare_file_names(Path::new(a), Path::new(b))
+    }
+
+    #[test]
+    fn test_compare_file_names() {
+        // Digit runs are compared as numbers
+
+        assert_eq!(compare("img2.jpg", "img10.jpg"), Ordering::Less);
+        assert_eq!(compare("img10.jpg", "img2.jpg"), Ordering::Greater);
+        assert_eq!(compare("img10.jpg", "img10.jpg"), Ordering::Equal);
+        assert_eq!(compare("2 b.jpg", "10 a.jpg"), Ordering::Less);
+        assert_eq!(compare("img.jpg", "img1.jpg"), Ordering::Less);
+
+        // Leading zeros do not change the number
+
+        assert_eq!(compare("img002.jpg", "img10.jpg"), Ordering::Less);
+        assert_eq!(compare("img010.jpg", "img9.jpg"), Ordering::Greater);
+
+        // Equal numbers with different digits are ordered by their text, to keep the order stable
+
+        assert_eq!(compare("img01.jpg", "img1.jpg"), Ordering::Less);
+        assert_eq!(compare("img1.jpg", "img01.jpg"), Ordering::Greater);
+        assert_eq!(compare("img1a.jpg", "img01b.jpg"), Ordering::Less);
+
+        // Case is ignored
+
+        assert_eq!(compare("IMG2.jpg", "img10.jpg"), Ordering::Less);
+        assert_eq!(compare("b.jpg", "A.jpg"), Ordering::Greater);
+        assert_eq!(compare("Photo.jpg", "photo.JPG"), Ordering::Equal);
+
+        // Only the file name is compared
+
+        assert_eq!(compare("z/img2.jpg", "a/img10.jpg"), Ordering::Less);
+
+        let mut files: Vec<PathBuf> = ["img10.png", "IMG1.png", "img2.png", "img002.png"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+
+        files.sort_by(|a, b| compare_file_names(a, b));
+
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("IMG1.png"),
+                PathBuf::from("img002.png"),
+                PathBuf::from("img2.png"),
+                PathBuf::from("img10.png"),
+            ]
+        );
+    }
+}
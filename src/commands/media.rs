@@ -13,16 +13,17 @@ use crate::{
     commands::logout::do_logout,
     models::{
         tags_map_from_list, tags_names_from_ids, MediaUpdateDescriptionBody, MediaUpdateExtraBody,
-        MediaUpdateTitleBody,
+        MediaUpdateTitleBody, UndoChange,
     },
     tools::{
         ask_user, duration_to_string, ensure_login, format_date, identifier_to_string,
-        parse_identifier, parse_vault_uri, render_size_bytes, to_csv_string,
+        is_archive_path, parse_identifier, parse_vault_uri, render_size_bytes, to_csv_string,
     },
 };
 
 use super::{
     get_vault_url,
+    import_dry_run::run_cmd_import_media_dry_run,
     media_attachments::{
         run_cmd_delete_media_attachment, run_cmd_rename_media_attachment,
         run_cmd_upload_media_attachment,
@@ -32,10 +33,13 @@ use super::{
         run_cmd_upload_media_audio_track,
     },
     media_download::run_cmd_download_media,
-    media_export::run_cmd_export_media,
+    media_export::{get_export_encryption, run_cmd_export_media},
     media_extended_description::run_cmd_set_media_extended_description,
+    media_find_duplicates::run_cmd_media_find_duplicates,
+    media_find_orphans::run_cmd_media_find_orphans,
     media_image_notes::run_cmd_set_media_image_notes,
-    media_import::run_cmd_import_media,
+    media_import::{run_cmd_import_media, run_cmd_import_media_archive},
+    media_import_sidecar::run_cmd_import_media_sidecar,
     media_replace::run_cmd_replace_media,
     media_resolutions::{run_cmd_media_add_resolution, run_cmd_media_remove_resolution},
     media_subtitles::{
@@ -44,7 +48,9 @@ use super::{
     media_thumbnail::run_cmd_upload_media_thumbnail,
     media_time_slices::{run_cmd_get_media_time_slices, run_cmd_set_media_time_slices},
     media_upload::run_cmd_upload_media,
-    print_request_error, CommandGlobalOptions,
+    print_request_error,
+    undo::record_undo_entry,
+    CommandGlobalOptions,
 };
 
 #[derive(Subcommand)]
@@ -61,6 +67,48 @@ pub enum MediaCommand {
         media: String,
     },
 
+    /// Finds groups of possible duplicate media assets, comparing the chosen criteria. Only media assets of the same type are compared.
+    FindDuplicates {
+        /// Criteria to compare, separated by commas. Can be: title, duration, dimensions or size (of the original asset). By default: title,duration
+        #[arg(short, long)]
+        by: Option<String>,
+
+        /// Minimum similarity of the titles to consider them duplicates, as a percentage, from 1 to 100. Titles are compared ignoring case, accents, punctuation and repeated spaces. By default: 90
+        #[arg(long)]
+        similarity: Option<u32>,
+
+        /// Confirm the duplicates by downloading and hashing an asset. Can be: thumbnail or original
+        #[arg(long)]
+        verify: Option<String>,
+
+        /// CSV format
+        #[arg(short, long)]
+        csv: bool,
+    },
+
+    /// Lists the media assets not included in any album. The CSV output can be used as input for other tools.
+    FindOrphans {
+        /// Only list the media assets that also have no tags
+        #[arg(short, long)]
+        untagged: bool,
+
+        /// Extended version of the results table
+        #[arg(short, long)]
+        extended: bool,
+
+        /// CSV format
+        #[arg(short, long)]
+        csv: bool,
+
+        /// JSON Lines format, one result per line
+        #[arg(long, conflicts_with = "csv")]
+        jsonl: bool,
+
+        /// Print only the IDs of the media assets, one per line. Can be used as input for batch --ids-from.
+        #[arg(long, conflicts_with_all = ["csv", "extended", "jsonl"])]
+        ids: bool,
+    },
+
     /// Downloads a media asset
     Download {
         /// Media asset ID
@@ -81,11 +129,28 @@ pub enum MediaCommand {
     /// Exports a media asset, downloading everything (metadata + assets) into a folder
     Export {
         /// Media asset ID
-        media: String,
+        #[arg(required_unless_present = "search")]
+        media: Option<String>,
 
         /// Path to the folder to download the files into
         #[arg(short, long)]
         output: Option<String>,
+
+        /// Write an archive instead of a folder. The format is chosen by the extension (.tar or .zip). Use - to write a tar archive to the standard output.
+        #[arg(long, conflicts_with = "output")]
+        archive: Option<String>,
+
+        /// Encrypt the archive with a passphrase, using the age format. The passphrase is asked for, unless the PMV_ARCHIVE_PASSPHRASE environment variable is set.
+        #[arg(long, requires = "archive")]
+        passphrase: bool,
+
+        /// Encrypt the archive for an age public key (age1...), instead of using a passphrase. Can be specified multiple times.
+        #[arg(long, requires = "archive", conflicts_with = "passphrase")]
+        recipient: Vec<String>,
+
+        /// Export every media asset matching a saved search instead, each one into a media_ID sub-folder
+        #[arg(short = 'S', long, conflicts_with = "media")]
+        search: Option<String>,
     },
 
     /// Uploads a new media asset, waits for encryption and adds tags if specified
@@ -112,12 +177,34 @@ pub enum MediaCommand {
 
     /// Imports a media asset, expecting a folder with the same format the export command uses.
     Import {
-        /// Path to the folder to import
+        /// Path to the folder or archive (tar or zip) to import. Use - to read a tar archive from the standard input.
         path: String,
 
         /// Album to upload the media asset into
         #[arg(short, long)]
         album: Option<String>,
+
+        /// Age identity file to decrypt the archive, if it was encrypted for a public key. For passphrase encrypted archives, the passphrase is asked for, unless the PMV_ARCHIVE_PASSPHRASE environment variable is set.
+        #[arg(long)]
+        identity: Option<String>,
+
+        /// Validate the folder and print the planned operations, without changing anything in the vault
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Uploads a media file, reading its metadata (title, description, tags, chapters and thumbnail) from a sidecar file. Supports Jellyfin / Kodi NFO files and yt-dlp info.json files.
+    ImportSidecar {
+        /// Path to the media file to upload
+        path: String,
+
+        /// Path to the sidecar file. By default, a .info.json or .nfo file with the same name as the media file.
+        #[arg(long)]
+        sidecar: Option<String>,
+
+        /// Album to upload the media asset into
+        #[arg(short, long)]
+        album: Option<String>,
     },
 
     /// Changes the title of a media asset
@@ -360,6 +447,23 @@ pub async fn run_media_cmd(global_opts: CommandGlobalOptions, cmd: MediaCommand)
         MediaCommand::Stats { media } => {
             run_cmd_get_media_stats(global_opts, media).await;
         }
+        MediaCommand::FindDuplicates {
+            by,
+            similarity,
+            verify,
+            csv,
+        } => {
+            run_cmd_media_find_duplicates(global_opts, by, similarity, verify, csv).await;
+        }
+        MediaCommand::FindOrphans {
+            untagged,
+            extended,
+            csv,
+            jsonl,
+            ids,
+        } => {
+            run_cmd_media_find_orphans(global_opts, untagged, extended, csv, jsonl, ids).await;
+        }
         MediaCommand::Download {
             media,
             asset,
@@ -448,11 +552,40 @@ pub async fn run_media_cmd(global_opts: CommandGlobalOptions, cmd: MediaCommand)
         MediaCommand::SetExtendedDescription { media, path } => {
             run_cmd_set_media_extended_description(global_opts, media, path).await;
         }
-        MediaCommand::Export { media, output } => {
-            run_cmd_export_media(global_opts, media, output, false).await;
+        MediaCommand::Export {
+            media,
+            output,
+            archive,
+            passphrase,
+            recipient,
+            search,
+        } => {
+            let encryption = get_export_encryption(passphrase, recipient).await;
+            run_cmd_export_media(global_opts, media, output, archive, encryption, search).await;
         }
-        MediaCommand::Import { path, album } => {
-            run_cmd_import_media(global_opts, path, album, false).await;
+        MediaCommand::Import {
+            path,
+            album,
+            identity,
+            dry_run,
+        } => {
+            if dry_run {
+                run_cmd_import_media_dry_run(path, album, identity).await;
+            } else if is_archive_path(&path) {
+                run_cmd_import_media_archive(global_opts, path, album, identity).await;
+            } else if run_cmd_import_media(global_opts, path, album, false)
+                .await
+                .is_none()
+            {
+                process::exit(1);
+            }
+        }
+        MediaCommand::ImportSidecar {
+            path,
+            sidecar,
+            album,
+        } => {
+            run_cmd_import_media_sidecar(global_opts, path, sidecar, album).await;
         }
         MediaCommand::AddAttachment { media, path } => {
             run_cmd_upload_media_attachment(global_opts, media, path).await;
@@ -929,14 +1062,16 @@ pub async fn run_cmd_media_set_title(
     let media_id_res = parse_identifier(&media);
 
     let media_id_param: u64;
+    let previous_title: String;
 
     match media_id_res {
         Ok(media_id) => {
             let media_api_res = api_call_get_media(&vault_url, media_id, global_opts.debug).await;
 
             match media_api_res {
-                Ok(_) => {
+                Ok(metadata) => {
                     media_id_param = media_id;
+                    previous_title = metadata.title;
                 }
                 Err(e) => {
                     print_request_error(e);
@@ -985,6 +1120,19 @@ pub async fn run_cmd_media_set_title(
 
     match api_res {
         Ok(_) => {
+            if previous_title != title {
+                record_undo_entry(
+                    &global_opts,
+                    &vault_url,
+                    &format!("media set-title #{media_id_param}"),
+                    vec![UndoChange::MediaTitle {
+                        media_id: media_id_param,
+                        title: previous_title,
+                    }],
+                )
+                .await;
+            }
+
             if logout_after_operation {
                 let logout_res = do_logout(&global_opts, &vault_url).await;
 
@@ -1054,14 +1202,16 @@ pub async fn run_cmd_media_set_description(
     let media_id_res = parse_identifier(&media);
 
     let media_id_param: u64;
+    let previous_description: String;
 
     match media_id_res {
         Ok(media_id) => {
             let media_api_res = api_call_get_media(&vault_url, media_id, global_opts.debug).await;
 
             match media_api_res {
-                Ok(_) => {
+                Ok(metadata) => {
                     media_id_param = media_id;
+                    previous_description = metadata.description;
                 }
                 Err(e) => {
                     print_request_error(e);
@@ -1110,6 +1260,19 @@ pub async fn run_cmd_media_set_description(
 
     match api_res {
         Ok(_) => {
+            if previous_description != description {
+                record_undo_entry(
+                    &global_opts,
+                    &vault_url,
+                    &format!("media set-description #{media_id_param}"),
+                    vec![UndoChange::MediaDescription {
+                        media_id: media_id_param,
+                        description: previous_description,
+                    }],
+                )
+                .await;
+            }
+
             if logout_after_operation {
                 let logout_res = do_logout(&global_opts, &vault_url).await;
 
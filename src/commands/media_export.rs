@@ -6,21 +6,46 @@ use unicode_width::UnicodeWidthStr;
 
 use crate::{
     api::{api_call_get_media, api_call_get_tags},
-    commands::logout::do_logout,
-    models::{tags_map_from_list, MediaAttachmentExport, MediaMetadata, MediaMetadataExport, MediaSubtitleOrAudioExport},
+    commands::{
+        batch_operation::{collect_batch_media, BatchMediaFilter},
+        logout::do_logout,
+        search_saved::load_saved_search,
+    },
+    models::{
+        tags_map_from_list, MediaAttachmentExport, MediaMetadata, MediaMetadataExport,
+        MediaSubtitleOrAudioExport,
+    },
     tools::{
-        ask_user, do_get_download_request, ensure_login, get_extension_from_url, parse_identifier, parse_vault_uri, ProgressReceiver, VaultURI
+        ask_user, ensure_login, get_archive_encryption, get_extension_from_url,
+        identifier_to_string, parse_identifier, parse_vault_uri, write_html_gallery,
+        ArchiveEncryption, ArchiveWriter, ExportOutput, ProgressReceiver, RequestError, VaultURI,
+        ARCHIVE_STDIO,
     },
 };
 
 use super::{get_vault_url, print_request_error, CommandGlobalOptions};
 
+/// Result of exporting a media asset
+#[derive(Debug, PartialEq, Eq)]
+pub enum MediaExportResult {
+    Exported,
+
+    /// The media asset was not found, or it has no original asset yet
+    Skipped,
+
+    Failed,
+}
+
 pub async fn run_cmd_export_media(
     global_opts: CommandGlobalOptions,
-    media: String,
+    media: Option<String>,
     output: Option<String>,
-    is_internal: bool,
+    archive: Option<String>,
+    encryption: Option<ArchiveEncryption>,
+    search: Option<String>,
 ) {
+    let search = search.map(|name| load_saved_search(&name));
+
     let url_parse_res = parse_vault_uri(get_vault_url(&global_opts.vault_url));
 
     if url_parse_res.is_err() {
@@ -50,60 +75,93 @@ pub async fn run_cmd_export_media(
 
     // Params
 
-    let media_id_res = parse_identifier(&media);
-    let media_id: u64 = match media_id_res {
-        Ok(id) => id,
-        Err(_) => {
-            if logout_after_operation {
-                let logout_res = do_logout(&global_opts, &vault_url).await;
+    let (media_ids, sub_folders): (Vec<u64>, bool) = match search {
+        Some(saved_search) => {
+            let filter = BatchMediaFilter::default().with_saved_search(saved_search);
 
-                match logout_res {
-                    Ok(_) => {}
-                    Err(_) => {
-                        process::exit(1);
+            let media_list =
+                collect_batch_media(&global_opts, &vault_url, logout_after_operation, filter).await;
+
+            (media_list.iter().rev().map(|m| m.id).collect(), true)
+        }
+        None => {
+            let media_id_res = parse_identifier(&media.unwrap_or_default());
+            let media_id: u64 = match media_id_res {
+                Ok(id) => id,
+                Err(_) => {
+                    if logout_after_operation {
+                        let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                        match logout_res {
+                            Ok(_) => {}
+                            Err(_) => {
+                                process::exit(1);
+                            }
+                        }
                     }
+                    eprintln!("Invalid album identifier specified.");
+                    process::exit(1);
                 }
-            }
-            eprintln!("Invalid album identifier specified.");
-            process::exit(1);
+            };
+
+            (vec![media_id], false)
         }
     };
 
-    // Get tags
-
-    let tags_res = api_call_get_tags(&vault_url, global_opts.debug).await;
-
-    if tags_res.is_err() {
-        if logout_after_operation {
-            let logout_res = do_logout(&global_opts, &vault_url).await;
+    // Output
 
-            match logout_res {
-                Ok(_) => {}
-                Err(_) => {
-                    process::exit(1);
-                }
+    let out_folder: String = match output {
+        Some(o) => o,
+        None => {
+            if sub_folders {
+                "media_export".to_string()
+            } else {
+                format!("media_{}", media_ids[0])
             }
         }
-        print_request_error(tags_res.err().unwrap());
-        process::exit(1);
-    }
+    };
 
-    let tags_vec = tags_res.unwrap();
+    let mut export_output = create_export_output(
+        &global_opts,
+        &vault_url,
+        logout_after_operation,
+        &out_folder,
+        &archive,
+        encryption.as_ref(),
+    )
+    .await;
 
-    let tags_map = tags_map_from_list(&tags_vec);
+    // Export
 
-    // Get media metadata
+    let n_total = media_ids.len();
 
-    let api_get_media_res =
-        api_call_get_media(&vault_url, media_id, global_opts.debug).await;
+    for (i, media_id) in media_ids.iter().enumerate() {
+        let media_folder = if sub_folders {
+            let n_done = i + 1;
+            eprintln!("[{n_done}/{n_total}] Exporting media asset #{media_id}...");
 
-    let media_metadata: MediaMetadata = match api_get_media_res {
-        Ok(meta) => meta,
-        Err(e) => {
-            print_request_error(e);
-            if is_internal {
-                return;
-            }
+            "media_".to_string() + &media_id.to_string()
+        } else {
+            "".to_string()
+        };
+
+        let export_res = export_media(
+            &global_opts,
+            &vault_url,
+            *media_id,
+            &mut export_output,
+            &media_folder,
+        )
+        .await;
+
+        // When exporting the results of a search, the media assets that cannot be exported yet are skipped
+        let export_ok = match export_res {
+            MediaExportResult::Exported => true,
+            MediaExportResult::Skipped => sub_folders,
+            MediaExportResult::Failed => false,
+        };
+
+        if !export_ok {
             if logout_after_operation {
                 let logout_res = do_logout(&global_opts, &vault_url).await;
 
@@ -116,66 +174,99 @@ pub async fn run_cmd_export_media(
             }
             process::exit(1);
         }
-    };
+    }
 
-    // Output folder
+    finish_export_output(
+        &global_opts,
+        &vault_url,
+        logout_after_operation,
+        export_output,
+    )
+    .await;
 
-    let out_folder: String = match output {
-        Some(o) => o,
+    if logout_after_operation {
+        let logout_res = do_logout(&global_opts, &vault_url).await;
+
+        match logout_res {
+            Ok(_) => {}
+            Err(_) => {
+                process::exit(1);
+            }
+        }
+    }
+
+    match archive {
+        Some(archive_path) => {
+            eprintln!("Done. Successfully exported media into archive {archive_path}");
+        }
         None => {
-            format!("media_{media_id}")
+            eprintln!("Done. Successfully exported media into folder {out_folder}");
+        }
+    }
+}
+
+/// Exports a media asset into a folder of an export output (empty for the root)
+/// Errors are printed, instead of exiting.
+pub async fn export_media(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    media_id: u64,
+    output: &mut ExportOutput,
+    folder: &str,
+) -> MediaExportResult {
+    // Get tags
+
+    let tags_res = api_call_get_tags(vault_url, global_opts.debug).await;
+
+    let tags_vec = match tags_res {
+        Ok(t) => t,
+        Err(e) => {
+            print_request_error(e);
+            return MediaExportResult::Failed;
         }
     };
 
-    let out_exists = std::path::Path::new(&out_folder).exists();
+    let tags_map = tags_map_from_list(&tags_vec);
 
-    if out_exists && !global_opts.auto_confirm && !is_internal {
-        eprintln!("The folder {out_folder} already exists");
-        let confirmation = ask_user("Do you want to overwrite it? y/n: ")
-            .await
-            .unwrap_or("".to_string());
+    // Get media metadata
 
-        if confirmation.to_lowercase() != "y" {
-            if logout_after_operation {
-                let logout_res = do_logout(&global_opts, &vault_url).await;
+    let api_get_media_res = api_call_get_media(vault_url, media_id, global_opts.debug).await;
 
-                match logout_res {
-                    Ok(_) => {}
-                    Err(_) => {
-                        process::exit(1);
-                    }
-                }
-            }
-            process::exit(1);
+    let media_metadata: MediaMetadata = match api_get_media_res {
+        Ok(meta) => meta,
+        Err(RequestError::StatusCode(status)) | Err(RequestError::Api { status, .. })
+            if status == 404 =>
+        {
+            let media_id_str = identifier_to_string(media_id);
+            eprintln!("The media asset {media_id_str} does not exist. It was probably deleted.");
+            return MediaExportResult::Skipped;
         }
-    }
-
-    if !out_exists {
-        let create_dir_res = tokio::fs::create_dir(std::path::Path::new(&out_folder)).await;
+        Err(e) => {
+            print_request_error(e);
+            return MediaExportResult::Failed;
+        }
+    };
 
-        match create_dir_res {
-            Ok(_) => {}
-            Err(e) => {
-                let e_str = e.to_string();
-                eprintln!("Could not create the folder {out_folder}. Error: {e_str}");
-                if logout_after_operation {
-                    let logout_res = do_logout(&global_opts, &vault_url).await;
-
-                    match logout_res {
-                        Ok(_) => {}
-                        Err(_) => {
-                            process::exit(1);
-                        }
-                    }
-                }
-                process::exit(1);
-            }
+    let original_asset_url = match &media_metadata.url {
+        Some(u) if !u.is_empty() => u.clone(),
+        _ => {
+            eprintln!("The media has no original asset. It's probably still pending for upload or encryption.");
+            return MediaExportResult::Skipped;
         }
+    };
+
+    // Folder
+
+    if let Err(e_str) = output.create_folder(folder).await {
+        let folder_path = output.get_file_path(folder, "");
+        eprintln!("Could not create the folder {folder_path}. Error: {e_str}");
+        return MediaExportResult::Failed;
     }
 
     // Metadata
 
     let mut out_metadata = MediaMetadataExport {
+        id: Some(media_id),
         original: None,
         thumbnail: None,
         notes: None,
@@ -218,89 +309,54 @@ pub async fn run_cmd_export_media(
 
     // Original
 
-    match media_metadata.url {
-        Some(original_asset_url) => {
-            if original_asset_url.is_empty() {
-                eprintln!("The media has no original asset. It's probably still pending for upload or encryption.");
-                if logout_after_operation {
-                    let logout_res = do_logout(&global_opts, &vault_url).await;
-
-                    match logout_res {
-                        Ok(_) => {}
-                        Err(_) => {
-                            process::exit(1);
-                        }
-                    }
-                }
-                process::exit(1);
-            }
-
-            let default_ext: String = match media_metadata.media_type {
-                crate::models::MediaType::Deleted => "bin".to_string(),
-                crate::models::MediaType::Image => "png".to_string(),
-                crate::models::MediaType::Video => "mp4".to_string(),
-                crate::models::MediaType::Audio => "mp3".to_string(),
-            };
+    let default_ext: String = match media_metadata.media_type {
+        crate::models::MediaType::Deleted => "bin".to_string(),
+        crate::models::MediaType::Image => "png".to_string(),
+        crate::models::MediaType::Video => "mp4".to_string(),
+        crate::models::MediaType::Audio => "mp3".to_string(),
+    };
 
-            let ext = get_extension_from_url(&original_asset_url, &default_ext);
-            let out_file_name = "original".to_owned() + "." + &ext;
-
-            let original_out_path = std::path::Path::new(&out_folder)
-                .join(&out_file_name)
-                .to_str()
-                .unwrap()
-                .to_string();
-
-            download_media_asset(
-                global_opts.clone(),
-                &vault_url,
-                "original",
-                original_asset_url,
-                original_out_path,
-                logout_after_operation,
-            )
-            .await;
+    let ext = get_extension_from_url(&original_asset_url, &default_ext);
+    let out_file_name = "original".to_owned() + "." + &ext;
 
-            out_metadata.original = Some(out_file_name);
-        }
-        None => {
-            eprintln!("The media has no original asset. It's probably still pending for upload or encryption.");
-            if logout_after_operation {
-                let logout_res = do_logout(&global_opts, &vault_url).await;
+    let download_ok = download_export_asset(
+        global_opts,
+        vault_url,
+        output,
+        folder,
+        &out_file_name,
+        "original",
+        original_asset_url,
+    )
+    .await;
 
-                match logout_res {
-                    Ok(_) => {}
-                    Err(_) => {
-                        process::exit(1);
-                    }
-                }
-            }
-            process::exit(1);
-        }
+    if !download_ok {
+        return MediaExportResult::Failed;
     }
 
+    out_metadata.original = Some(out_file_name);
+
     // Thumbnail
 
     if !media_metadata.thumbnail.is_empty() {
         let ext = get_extension_from_url(&media_metadata.thumbnail, "jpg");
         let out_file_name = "thumbnail".to_owned() + "." + &ext;
 
-        let thumbnail_out_path = std::path::Path::new(&out_folder)
-            .join(&out_file_name)
-            .to_str()
-            .unwrap()
-            .to_string();
-
-        download_media_asset(
-            global_opts.clone(),
-            &vault_url,
+        let download_ok = download_export_asset(
+            global_opts,
+            vault_url,
+            output,
+            folder,
+            &out_file_name,
             "thumbnail",
             media_metadata.thumbnail,
-            thumbnail_out_path,
-            logout_after_operation,
         )
         .await;
 
+        if !download_ok {
+            return MediaExportResult::Failed;
+        }
+
         out_metadata.thumbnail = Some(out_file_name);
     }
 
@@ -311,22 +367,21 @@ pub async fn run_cmd_export_media(
             let ext = get_extension_from_url(&ext_desc_url, "txt");
             let out_file_name = "ext_desc".to_owned() + "." + &ext;
 
-            let ext_desc_out_path = std::path::Path::new(&out_folder)
-                .join(&out_file_name)
-                .to_str()
-                .unwrap()
-                .to_string();
-
-            download_media_asset(
-                global_opts.clone(),
-                &vault_url,
+            let download_ok = download_export_asset(
+                global_opts,
+                vault_url,
+                output,
+                folder,
+                &out_file_name,
                 "extended description",
                 ext_desc_url,
-                ext_desc_out_path,
-                logout_after_operation,
             )
             .await;
 
+            if !download_ok {
+                return MediaExportResult::Failed;
+            }
+
             out_metadata.ext_desc = Some(out_file_name);
         }
     }
@@ -338,22 +393,21 @@ pub async fn run_cmd_export_media(
             let ext = get_extension_from_url(&img_notes_url, "json");
             let out_file_name = "notes".to_owned() + "." + &ext;
 
-            let notes_out_path = std::path::Path::new(&out_folder)
-                .join(&out_file_name)
-                .to_str()
-                .unwrap()
-                .to_string();
-
-            download_media_asset(
-                global_opts.clone(),
-                &vault_url,
+            let download_ok = download_export_asset(
+                global_opts,
+                vault_url,
+                output,
+                folder,
+                &out_file_name,
                 "image notes",
                 img_notes_url,
-                notes_out_path,
-                logout_after_operation,
             )
             .await;
 
+            if !download_ok {
+                return MediaExportResult::Failed;
+            }
+
             out_metadata.notes = Some(out_file_name);
         }
     }
@@ -370,22 +424,21 @@ pub async fn run_cmd_export_media(
             let ext = get_extension_from_url(&subtitle.url, "srt");
             let out_file_name = format!("subtitle_{sub_counter}.{ext}");
 
-            let sub_out_path = std::path::Path::new(&out_folder)
-                .join(&out_file_name)
-                .to_str()
-                .unwrap()
-                .to_string();
-
-            download_media_asset(
-                global_opts.clone(),
-                &vault_url,
+            let download_ok = download_export_asset(
+                global_opts,
+                vault_url,
+                output,
+                folder,
+                &out_file_name,
                 &d_name,
                 subtitle.url,
-                sub_out_path,
-                logout_after_operation,
             )
             .await;
 
+            if !download_ok {
+                return MediaExportResult::Failed;
+            }
+
             subtitles_export.push(MediaSubtitleOrAudioExport {
                 id: subtitle.id.clone(),
                 name: subtitle.name.clone(),
@@ -408,22 +461,21 @@ pub async fn run_cmd_export_media(
             let ext = get_extension_from_url(&audio.url, "mp3");
             let out_file_name = format!("audio_track_{audio_counter}.{ext}");
 
-            let audio_out_path = std::path::Path::new(&out_folder)
-                .join(&out_file_name)
-                .to_str()
-                .unwrap()
-                .to_string();
-
-            download_media_asset(
-                global_opts.clone(),
-                &vault_url,
+            let download_ok = download_export_asset(
+                global_opts,
+                vault_url,
+                output,
+                folder,
+                &out_file_name,
                 &d_name,
                 audio.url,
-                audio_out_path,
-                logout_after_operation,
             )
             .await;
 
+            if !download_ok {
+                return MediaExportResult::Failed;
+            }
+
             audios_export.push(MediaSubtitleOrAudioExport {
                 id: audio.id.clone(),
                 name: audio.name.clone(),
@@ -446,23 +498,22 @@ pub async fn run_cmd_export_media(
             let ext = get_extension_from_url(&att.url, "bin");
             let out_file_name = format!("attachment_{attachment_counter}.{ext}");
 
-            let att_out_path = std::path::Path::new(&out_folder)
-                .join(&out_file_name)
-                .to_str()
-                .unwrap()
-                .to_string();
-
-            download_media_asset(
-                global_opts.clone(),
-                &vault_url,
+            let download_ok = download_export_asset(
+                global_opts,
+                vault_url,
+                output,
+                folder,
+                &out_file_name,
                 &d_name,
                 att.url,
-                att_out_path,
-                logout_after_operation,
             )
             .await;
 
-            attachments_export.push(MediaAttachmentExport{
+            if !download_ok {
+                return MediaExportResult::Failed;
+            }
+
+            attachments_export.push(MediaAttachmentExport {
                 name: att.name.clone(),
                 file: out_file_name,
             });
@@ -473,21 +524,64 @@ pub async fn run_cmd_export_media(
 
     // After everything is downloaded, write metadata
 
-    let metadata_file = "metadata.json".to_string();
-    let metadata_out_path = std::path::Path::new(&out_folder)
-        .join(&metadata_file)
-        .to_str()
-        .unwrap()
-        .to_string();
-
     let metadata_str = serde_json::to_string(&out_metadata).unwrap();
 
-    let meta_write_res = tokio::fs::write(metadata_out_path.clone(), metadata_str).await;
+    let meta_write_res = output
+        .write_file(folder, "metadata.json", metadata_str.as_bytes())
+        .await;
+
+    if let Err(e_str) = meta_write_res {
+        let metadata_out_path = output.get_file_path(folder, "metadata.json");
+        eprintln!("Could not write metadata file: {metadata_out_path}. Error: {e_str}");
+        return MediaExportResult::Failed;
+    }
 
-    match meta_write_res {
-        Ok(_) => {
-            if logout_after_operation && !is_internal {
-                let logout_res = do_logout(&global_opts, &vault_url).await;
+    // Manifest
+
+    if let Err(e_str) = output.write_manifest(folder) {
+        let folder_path = output.get_file_path(folder, "");
+        eprintln!("Could not write the manifest file into {folder_path}. Error: {e_str}");
+        return MediaExportResult::Failed;
+    }
+
+    MediaExportResult::Exported
+}
+
+/// Creates the output of an export: a folder, or an archive if specified
+/// Asks for confirmation before overwriting. Exits on error.
+pub async fn create_export_output(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    logout_after_operation: bool,
+    out_folder: &str,
+    archive: &Option<String>,
+    encryption: Option<&ArchiveEncryption>,
+) -> ExportOutput {
+    let (out_path, out_exists) = match archive {
+        Some(archive_path) => (
+            archive_path.clone(),
+            archive_path != ARCHIVE_STDIO && std::path::Path::new(archive_path).exists(),
+        ),
+        None => (
+            out_folder.to_string(),
+            std::path::Path::new(out_folder).exists(),
+        ),
+    };
+
+    if out_exists && !global_opts.auto_confirm {
+        if archive.is_some() {
+            eprintln!("The file {out_path} already exists");
+        } else {
+            eprintln!("The folder {out_path} already exists");
+        }
+
+        let confirmation = ask_user("Do you want to overwrite it? y/n: ")
+            .await
+            .unwrap_or("".to_string());
+
+        if confirmation.to_lowercase() != "y" {
+            if logout_after_operation {
+                let logout_res = do_logout(global_opts, vault_url).await;
 
                 match logout_res {
                     Ok(_) => {}
@@ -496,13 +590,37 @@ pub async fn run_cmd_export_media(
                     }
                 }
             }
-            eprintln!("Done. Successfully exported media into folder {out_folder}");
+            process::exit(1);
         }
-        Err(e) => {
-            let e_str = e.to_string();
-            eprintln!("Could not write metadata file: {metadata_out_path}. Error: {e_str}");
+    }
+
+    let output_res = match archive {
+        Some(archive_path) => ArchiveWriter::create(archive_path, encryption)
+            .map(|a| ExportOutput::Archive(Box::new(a)))
+            .map_err(|e_str| {
+                format!("Could not create the archive {archive_path}. Error: {e_str}")
+            }),
+        None => {
+            if out_exists {
+                Ok(ExportOutput::Folder(out_folder.to_string()))
+            } else {
+                tokio::fs::create_dir(std::path::Path::new(out_folder))
+                    .await
+                    .map(|_| ExportOutput::Folder(out_folder.to_string()))
+                    .map_err(|e| {
+                        let e_str = e.to_string();
+                        format!("Could not create the folder {out_folder}. Error: {e_str}")
+                    })
+            }
+        }
+    };
+
+    match output_res {
+        Ok(o) => o,
+        Err(e_str) => {
+            eprintln!("{e_str}");
             if logout_after_operation {
-                let logout_res = do_logout(&global_opts, &vault_url).await;
+                let logout_res = do_logout(global_opts, vault_url).await;
 
                 match logout_res {
                     Ok(_) => {}
@@ -516,42 +634,103 @@ pub async fn run_cmd_export_media(
     }
 }
 
-pub async fn download_media_asset(
-    global_opts: CommandGlobalOptions,
+/// Finishes the output of an export, exiting on error
+pub async fn finish_export_output(
+    global_opts: &CommandGlobalOptions,
     vault_url: &VaultURI,
-    download_name: &str,
-    download_path: String,
-    out_file: String,
     logout_after_operation: bool,
+    output: ExportOutput,
 ) {
+    if let Err(e_str) = output.finish() {
+        eprintln!("Could not write the archive. Error: {e_str}");
+        if logout_after_operation {
+            let logout_res = do_logout(global_opts, vault_url).await;
+
+            match logout_res {
+                Ok(_) => {}
+                Err(_) => {
+                    process::exit(1);
+                }
+            }
+        }
+        process::exit(1);
+    }
+}
+
+/// Writes a HTML gallery into an export folder, exiting on error
+pub async fn write_export_html_gallery(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    logout_after_operation: bool,
+    folder: &str,
+    title: &str,
+    media_folders: &[String],
+) {
+    let gallery_res = write_html_gallery(folder, title, media_folders);
+
+    if let Err(e_str) = gallery_res {
+        eprintln!("Could not write the HTML gallery into {folder}. Error: {e_str}");
+        if logout_after_operation {
+            let logout_res = do_logout(global_opts, vault_url).await;
+
+            match logout_res {
+                Ok(_) => {}
+                Err(_) => {
+                    process::exit(1);
+                }
+            }
+        }
+        process::exit(1);
+    }
+}
+
+/// Gets the encryption options for an export, exiting on error
+pub async fn get_export_encryption(
+    passphrase: bool,
+    recipients: Vec<String>,
+) -> Option<ArchiveEncryption> {
+    match get_archive_encryption(passphrase, recipients).await {
+        Ok(e) => e,
+        Err(e_str) => {
+            eprintln!("{e_str}");
+            process::exit(1);
+        }
+    }
+}
+
+/// Downloads an asset of an exported media into the export output, printing the progress
+/// Returns false on error
+pub async fn download_export_asset(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    output: &mut ExportOutput,
+    folder: &str,
+    name: &str,
+    download_name: &str,
+    download_path: String,
+) -> bool {
     let mut progress_printer = DownloaderProgressPrinter::new(download_name);
 
-    let download_result = do_get_download_request(
-        vault_url,
-        download_path,
-        out_file.clone(),
-        global_opts.debug,
-        &mut progress_printer,
-    )
-    .await;
+    let download_result = output
+        .download(
+            vault_url,
+            download_path,
+            folder,
+            name,
+            global_opts.debug,
+            &mut progress_printer,
+        )
+        .await;
 
     match download_result {
         Ok(_) => {
+            let out_file = output.get_file_path(folder, name);
             eprintln!("Download completed: {out_file}");
+            true
         }
         Err(e) => {
             print_request_error(e);
-            if logout_after_operation {
-                let logout_res = do_logout(&global_opts, vault_url).await;
-
-                match logout_res {
-                    Ok(_) => {}
-                    Err(_) => {
-                        process::exit(1);
-                    }
-                }
-            }
-            process::exit(1);
+            false
         }
     }
 }
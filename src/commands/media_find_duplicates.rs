@@ -0,0 +1,469 @@
+// Command to find possible duplicate media assets
+
+use std::{collections::HashMap, process};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    api::{api_call_get_media_stats, api_call_search, MAX_SEARCH_PAGE_LIMIT},
+    models::{MediaListItem, MediaMetadata},
+    tools::{
+        compute_file_checksum, do_get_download_request, do_get_download_request_memory,
+        ensure_login, get_duplicate_title_key, get_temp_folder_path, identifier_to_string,
+        parse_duplicate_criteria, parse_duplicate_verification, parse_vault_uri, print_table,
+        refine_duplicate_groups, refine_duplicate_groups_by_title, remove_temp_folder,
+        render_media_duration, to_csv_string, DuplicateVerification, ProgressReceiver,
+        RequestError, VaultURI,
+    },
+};
+
+use super::{
+    get_vault_url, logout::do_logout, media_metadata_fetch::fetch_media_metadata_list,
+    print_request_error, CommandGlobalOptions,
+};
+
+const DEFAULT_DUPLICATE_CRITERIA: &str = "title,duration";
+
+const DEFAULT_TITLE_SIMILARITY: u32 = 90;
+
+pub async fn run_cmd_media_find_duplicates(
+    global_opts: CommandGlobalOptions,
+    by: Option<String>,
+    similarity: Option<u32>,
+    verify: Option<String>,
+    csv: bool,
+) {
+    let criteria =
+        match parse_duplicate_criteria(by.as_deref().unwrap_or(DEFAULT_DUPLICATE_CRITERIA)) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+        };
+
+    let similarity = similarity.unwrap_or(DEFAULT_TITLE_SIMILARITY);
+
+    if similarity == 0 || similarity > 100 {
+        eprintln!("The title similarity must be a percentage, from 1 to 100.");
+        process::exit(1);
+    }
+
+    let verification = match verify {
+        Some(v) => match parse_duplicate_verification(&v) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let url_parse_res = parse_vault_uri(get_vault_url(&global_opts.vault_url));
+
+    if url_parse_res.is_err() {
+        match url_parse_res.err().unwrap() {
+            crate::tools::VaultURIParseError::InvalidProtocol => {
+                eprintln!("Invalid vault URL provided. Must be an HTTP or HTTPS URL.");
+            }
+            crate::tools::VaultURIParseError::URLError(e) => {
+                let err_msg = e.to_string();
+                eprintln!("Invalid vault URL provided: {err_msg}");
+            }
+        }
+
+        process::exit(1);
+    }
+
+    let mut vault_url = url_parse_res.unwrap();
+
+    let logout_after_operation = vault_url.is_login();
+    let login_result = ensure_login(&vault_url, &None, global_opts.debug).await;
+
+    if login_result.is_err() {
+        process::exit(1);
+    }
+
+    vault_url = login_result.unwrap();
+
+    // Scan the vault
+
+    let mut media_map: HashMap<u64, MediaListItem> = HashMap::new();
+    let mut page: u32 = 0;
+    let mut finished = false;
+
+    while !finished {
+        let api_res = api_call_search(
+            &vault_url,
+            None,
+            true,
+            page,
+            MAX_SEARCH_PAGE_LIMIT as u32,
+            global_opts.debug,
+        )
+        .await;
+
+        match api_res {
+            Ok(search_result) => {
+                for item in search_result.page_items.iter() {
+                    media_map.insert(item.id, item.clone());
+                }
+
+                let scanned = media_map.len();
+                let total = search_result.total_count;
+                eprintln!("Scanned {scanned} / {total} media assets");
+
+                page += 1;
+                finished = search_result.page_items.is_empty() || page >= search_result.page_count;
+            }
+            Err(e) => {
+                print_request_error(e);
+                if logout_after_operation {
+                    let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                process::exit(1);
+            }
+        }
+    }
+
+    // Group by the information included in the list (type, title and duration)
+
+    let mut groups: Vec<Vec<u64>> = vec![media_map.keys().copied().collect()];
+
+    groups = refine_duplicate_groups(groups, |id| {
+        media_map.get(&id).map(|m| m.media_type.to_type_string())
+    });
+
+    if criteria.duration {
+        groups = refine_duplicate_groups(groups, |id| {
+            media_map
+                .get(&id)
+                .and_then(|m| m.duration.map(|d| d.round() as i64))
+        });
+    }
+
+    // Titles are compared after the duration, since checking the similarity is slower than grouping by a key
+
+    if criteria.title {
+        groups = refine_duplicate_groups_by_title(
+            groups,
+            |id| {
+                media_map
+                    .get(&id)
+                    .and_then(|m| get_duplicate_title_key(&m.title))
+            },
+            similarity as f64 / 100.0,
+        );
+    }
+
+    // Group by dimensions
+
+    let mut metadata_map: HashMap<u64, MediaMetadata> = HashMap::new();
+
+    if criteria.dimensions || verification.is_some() {
+        let media_ids: Vec<u64> = groups.iter().flatten().copied().collect();
+
+        if !media_ids.is_empty() {
+            let total = media_ids.len();
+            eprintln!("Fetching metadata of {total} media assets...");
+        }
+
+        metadata_map =
+            fetch_media_metadata_list(&global_opts, &vault_url, logout_after_operation, &media_ids)
+                .await;
+    }
+
+    if criteria.dimensions {
+        groups = refine_duplicate_groups(groups, |id| {
+            metadata_map
+                .get(&id)
+                .and_then(|m| Some((m.width?, m.height?)))
+        });
+    }
+
+    // Group by size of the original asset
+
+    if criteria.size {
+        let media_ids: Vec<u64> = groups.iter().flatten().copied().collect();
+        let total = media_ids.len();
+        let mut sizes: HashMap<u64, u64> = HashMap::new();
+
+        for (i, media_id) in media_ids.iter().enumerate() {
+            let current = i + 1;
+            eprintln!("Fetching size stats [{current}/{total}]...");
+
+            let api_res = api_call_get_media_stats(&vault_url, *media_id, global_opts.debug).await;
+
+            match api_res {
+                Ok(stats) => {
+                    let original = stats
+                        .assets
+                        .iter()
+                        .find(|a| a.name.eq_ignore_ascii_case("original"));
+
+                    if let Some(original) = original {
+                        sizes.insert(*media_id, original.size);
+                    }
+                }
+                Err(RequestError::StatusCode(status)) | Err(RequestError::Api { status, .. })
+                    if status == 404 =>
+                {
+                    // Media deleted in the meantime
+                }
+                Err(e) => {
+                    print_request_error(e);
+                    if logout_after_operation {
+                        let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                        match logout_res {
+                            Ok(_) => {}
+                            Err(_) => {
+                                process::exit(1);
+                            }
+                        }
+                    }
+                    process::exit(1);
+                }
+            }
+        }
+
+        groups = refine_duplicate_groups(groups, |id| sizes.get(&id).copied());
+    }
+
+    // Confirm by hashing the assets
+
+    if let Some(verification) = verification {
+        let media_ids: Vec<u64> = groups.iter().flatten().copied().collect();
+        let hashes = get_duplicate_asset_hashes(
+            &global_opts,
+            &vault_url,
+            logout_after_operation,
+            verification,
+            &media_ids,
+            &metadata_map,
+        )
+        .await;
+
+        groups = refine_duplicate_groups(groups, |id| hashes.get(&id).cloned());
+    }
+
+    if logout_after_operation {
+        let logout_res = do_logout(&global_opts, &vault_url).await;
+
+        match logout_res {
+            Ok(_) => {}
+            Err(_) => {
+                process::exit(1);
+            }
+        }
+    }
+
+    // Print results
+
+    let groups_count = groups.len();
+    eprintln!("Found {groups_count} groups of possible duplicates");
+
+    if csv {
+        println!("\"Group\",\"Id\",\"Type\",\"Title\",\"Duration\"");
+
+        for (i, group) in groups.iter().enumerate() {
+            let row_group = i + 1;
+
+            for media_id in group {
+                if let Some(item) = media_map.get(media_id) {
+                    let row_id = item.id.to_string();
+                    let row_type = to_csv_string(&item.media_type.to_type_string());
+                    let row_title = to_csv_string(&item.title);
+                    let row_duration =
+                        render_media_duration(item.media_type, item.duration.unwrap_or(0.0));
+
+                    println!("{row_group},{row_id},{row_type},{row_title},{row_duration}");
+                }
+            }
+        }
+    } else {
+        let table_head: Vec<String> = vec![
+            "Group".to_string(),
+            "Id".to_string(),
+            "Type".to_string(),
+            "Title".to_string(),
+            "Duration".to_string(),
+        ];
+        let mut table_body: Vec<Vec<String>> = Vec::new();
+
+        for (i, group) in groups.iter().enumerate() {
+            for media_id in group {
+                if let Some(item) = media_map.get(media_id) {
+                    table_body.push(vec![
+                        (i + 1).to_string(),
+                        identifier_to_string(item.id).clone(),
+                        item.media_type.to_type_string(),
+                        to_csv_string(&item.title),
+                        render_media_duration(item.media_type, item.duration.unwrap_or(0.0)),
+                    ]);
+                }
+            }
+        }
+
+        print_table(&table_head, &table_body, false);
+    }
+}
+
+/// Downloads the thumbnails or the original assets of a list of media assets, computing their SHA-256 checksums
+/// Media assets without the asset are not included in the result
+async fn get_duplicate_asset_hashes(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    logout_after_operation: bool,
+    verification: DuplicateVerification,
+    media_ids: &[u64],
+    metadata_map: &HashMap<u64, MediaMetadata>,
+) -> HashMap<u64, String> {
+    let mut hashes: HashMap<u64, String> = HashMap::new();
+    let total = media_ids.len();
+
+    let temp_folder = get_temp_folder_path("duplicates");
+
+    if verification == DuplicateVerification::Original {
+        if let Err(e) = tokio::fs::create_dir_all(&temp_folder).await {
+            if logout_after_operation {
+                let logout_res = do_logout(global_opts, vault_url).await;
+
+                match logout_res {
+                    Ok(_) => {}
+                    Err(_) => {
+                        process::exit(1);
+                    }
+                }
+            }
+            let e_str = e.to_string();
+            eprintln!("Could not create temporary folder {temp_folder}. Error: {e_str}");
+            process::exit(1);
+        }
+    }
+
+    for (i, media_id) in media_ids.iter().enumerate() {
+        let current = i + 1;
+        let media_id_str = identifier_to_string(*media_id);
+
+        eprintln!("Verifying [{current}/{total}] {media_id_str}...");
+
+        let metadata = match metadata_map.get(media_id) {
+            Some(m) => m,
+            None => {
+                continue;
+            }
+        };
+
+        let hash_res: Result<Option<String>, RequestError> = match verification {
+            DuplicateVerification::Thumbnail => {
+                if metadata.thumbnail.is_empty() {
+                    Ok(None)
+                } else {
+                    do_get_download_request_memory(
+                        vault_url,
+                        metadata.thumbnail.clone(),
+                        global_opts.debug,
+                    )
+                    .await
+                    .map(|bytes| {
+                        Some(
+                            Sha256::digest(&bytes)
+                                .iter()
+                                .map(|b| format!("{b:02x}"))
+                                .collect(),
+                        )
+                    })
+                }
+            }
+            DuplicateVerification::Original => match &metadata.url {
+                Some(url) => {
+                    let file_path = std::path::Path::new(&temp_folder)
+                        .join("original")
+                        .to_str()
+                        .unwrap()
+                        .to_string();
+
+                    let mut progress = SilentProgressReceiver {};
+
+                    match do_get_download_request(
+                        vault_url,
+                        url.clone(),
+                        file_path.clone(),
+                        global_opts.debug,
+                        &mut progress,
+                    )
+                    .await
+                    {
+                        Ok(_) => {
+                            let checksum_res =
+                                compute_file_checksum(std::path::Path::new(&file_path));
+
+                            let _ = tokio::fs::remove_file(&file_path).await;
+
+                            match checksum_res {
+                                Ok((_, checksum)) => Ok(Some(checksum)),
+                                Err(e) => {
+                                    eprintln!(
+                                        "Warning: Could not compute the checksum of {media_id_str}. Error: {e}"
+                                    );
+                                    Ok(None)
+                                }
+                            }
+                        }
+                        Err(e) => Err(e),
+                    }
+                }
+                None => Ok(None),
+            },
+        };
+
+        match hash_res {
+            Ok(Some(hash)) => {
+                hashes.insert(*media_id, hash);
+            }
+            Ok(None) => {}
+            Err(RequestError::StatusCode(status)) | Err(RequestError::Api { status, .. })
+                if status == 404 => {}
+            Err(e) => {
+                remove_temp_folder(&temp_folder).await;
+                print_request_error(e);
+                if logout_after_operation {
+                    let logout_res = do_logout(global_opts, vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                process::exit(1);
+            }
+        }
+    }
+
+    remove_temp_folder(&temp_folder).await;
+
+    hashes
+}
+
+/// Progress receiver that does not print anything
+/// The progress of the verification is printed per media asset instead
+struct SilentProgressReceiver {}
+
+impl ProgressReceiver for SilentProgressReceiver {
+    fn progress_start(&mut self) {}
+
+    fn progress_finish(&mut self) {}
+
+    fn progress_update(&mut self, _loaded: u64, _total: u64) {}
+}
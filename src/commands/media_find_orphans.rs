@@ -0,0 +1,202 @@
+// Command to find media assets not included in any album
+
+use std::{collections::HashSet, process};
+
+use crate::{
+    api::{
+        api_call_get_album, api_call_get_albums, api_call_get_tags, api_call_search,
+        MAX_SEARCH_PAGE_LIMIT,
+    },
+    models::{tags_map_from_list, MediaListItem},
+    tools::{
+        ensure_login, get_search_results_format, parse_vault_uri, print_search_progress,
+        RequestError, SearchResultsStream,
+    },
+};
+
+use super::{get_vault_url, logout::do_logout, print_request_error, CommandGlobalOptions};
+
+pub async fn run_cmd_media_find_orphans(
+    global_opts: CommandGlobalOptions,
+    untagged: bool,
+    extended: bool,
+    csv: bool,
+    jsonl: bool,
+    ids: bool,
+) {
+    let url_parse_res = parse_vault_uri(get_vault_url(&global_opts.vault_url));
+
+    if url_parse_res.is_err() {
+        match url_parse_res.err().unwrap() {
+            crate::tools::VaultURIParseError::InvalidProtocol => {
+                eprintln!("Invalid vault URL provided. Must be an HTTP or HTTPS URL.");
+            }
+            crate::tools::VaultURIParseError::URLError(e) => {
+                let err_msg = e.to_string();
+                eprintln!("Invalid vault URL provided: {err_msg}");
+            }
+        }
+
+        process::exit(1);
+    }
+
+    let mut vault_url = url_parse_res.unwrap();
+
+    let logout_after_operation = vault_url.is_login();
+    let login_result = ensure_login(&vault_url, &None, global_opts.debug).await;
+
+    if login_result.is_err() {
+        process::exit(1);
+    }
+
+    vault_url = login_result.unwrap();
+
+    // Get tags
+
+    let tags_res = api_call_get_tags(&vault_url, global_opts.debug).await;
+
+    if tags_res.is_err() {
+        if logout_after_operation {
+            let logout_res = do_logout(&global_opts, &vault_url).await;
+
+            match logout_res {
+                Ok(_) => {}
+                Err(_) => {
+                    process::exit(1);
+                }
+            }
+        }
+        print_request_error(tags_res.err().unwrap());
+        process::exit(1);
+    }
+
+    let tags = tags_map_from_list(&tags_res.unwrap());
+
+    // Find the media assets included in albums
+
+    let albums_res = api_call_get_albums(&vault_url, global_opts.debug).await;
+
+    let albums = match albums_res {
+        Ok(a) => a,
+        Err(e) => {
+            print_request_error(e);
+            if logout_after_operation {
+                let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                match logout_res {
+                    Ok(_) => {}
+                    Err(_) => {
+                        process::exit(1);
+                    }
+                }
+            }
+            process::exit(1);
+        }
+    };
+
+    let total_albums = albums.len();
+    let mut media_in_albums: HashSet<u64> = HashSet::new();
+
+    for (i, album) in albums.iter().enumerate() {
+        let api_res = api_call_get_album(&vault_url, album.id, global_opts.debug).await;
+
+        match api_res {
+            Ok(album_data) => {
+                media_in_albums.extend(album_data.list.iter().map(|m| m.id));
+            }
+            Err(RequestError::StatusCode(status)) | Err(RequestError::Api { status, .. })
+                if status == 404 =>
+            {
+                // Album deleted in the meantime
+            }
+            Err(e) => {
+                print_request_error(e);
+                if logout_after_operation {
+                    let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                process::exit(1);
+            }
+        }
+
+        let albums_scanned = i + 1;
+        eprintln!("Scanned albums: {albums_scanned} / {total_albums}");
+    }
+
+    // Scan the vault
+
+    let mut orphans: Vec<MediaListItem> = Vec::new();
+    let mut page: u32 = 0;
+    let mut scanned: u64 = 0;
+    let mut finished = false;
+
+    while !finished {
+        let api_res = api_call_search(
+            &vault_url,
+            None,
+            false,
+            page,
+            MAX_SEARCH_PAGE_LIMIT as u32,
+            global_opts.debug,
+        )
+        .await;
+
+        match api_res {
+            Ok(search_result) => {
+                scanned += search_result.page_items.len() as u64;
+
+                orphans.extend(
+                    search_result
+                        .page_items
+                        .iter()
+                        .filter(|m| !media_in_albums.contains(&m.id))
+                        .filter(|m| !untagged || m.tags.is_empty())
+                        .cloned(),
+                );
+
+                print_search_progress(scanned, search_result.total_count, orphans.len() as u64);
+
+                page += 1;
+                finished = search_result.page_items.is_empty() || page >= search_result.page_count;
+            }
+            Err(e) => {
+                print_request_error(e);
+                if logout_after_operation {
+                    let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                process::exit(1);
+            }
+        }
+    }
+
+    if logout_after_operation {
+        let logout_res = do_logout(&global_opts, &vault_url).await;
+
+        match logout_res {
+            Ok(_) => {}
+            Err(_) => {
+                process::exit(1);
+            }
+        }
+    }
+
+    // Print results
+
+    let mut results_stream =
+        SearchResultsStream::new(get_search_results_format(csv, jsonl, ids), extended, &tags);
+
+    results_stream.write_items(&orphans);
+}
@@ -1,6 +1,8 @@
 // Media import command
 
 use std::{
+    collections::HashSet,
+    path::Path,
     process,
     sync::{Arc, Mutex},
 };
@@ -16,12 +18,13 @@ use crate::{
     },
     commands::logout::do_logout,
     models::{
-        AddTagBody, ImageNote, MediaMetadataExport, MediaRenameAttachmentBody,
+        parse_tag_name, AddTagBody, ImageNote, MediaMetadataExport, MediaRenameAttachmentBody,
         MediaUpdateDescriptionBody, MediaUpdateExtendedDescriptionBody, MediaUpdateExtraBody,
     },
     tools::{
-        ensure_login, identifier_to_string, parse_identifier, parse_vault_uri, to_csv_string,
-        ProgressReceiver,
+        ensure_login, extract_archive, find_extracted_archive_root, get_archive_decryption,
+        get_temp_folder_path, identifier_to_string, parse_identifier, parse_vault_uri,
+        remove_temp_folder, to_csv_string, ProgressReceiver, VaultURI,
     },
 };
 
@@ -31,12 +34,56 @@ use super::{
     print_request_error, CommandGlobalOptions,
 };
 
+/// Imports a media asset from an archive (tar or zip) created by the export command
+pub async fn run_cmd_import_media_archive(
+    global_opts: CommandGlobalOptions,
+    path: String,
+    album: Option<String>,
+    identity: Option<String>,
+) {
+    let (temp_folder, import_folder) = extract_import_archive(&path, identity).await;
+
+    let import_res = run_cmd_import_media(global_opts, import_folder, album, false).await;
+
+    remove_temp_folder(&temp_folder).await;
+
+    if import_res.is_none() {
+        process::exit(1);
+    }
+}
+
+/// Extracts an archive into a temporary folder, decrypting it if necessary
+/// Returns the temporary folder and the folder to import
+pub async fn extract_import_archive(path: &str, identity: Option<String>) -> (String, String) {
+    let decryption = get_archive_decryption(path, identity).await;
+
+    let temp_folder = get_temp_folder_path("import");
+
+    eprintln!("Extracting archive {path}...");
+
+    let extract_res = extract_archive(path, &temp_folder, decryption.as_ref());
+
+    if let Err(e_str) = extract_res {
+        remove_temp_folder(&temp_folder).await;
+        eprintln!("Could not extract archive: {path}. Error: {e_str}");
+        process::exit(1);
+    }
+
+    let import_folder = find_extracted_archive_root(&temp_folder);
+
+    (temp_folder, import_folder)
+}
+
+/// Imports a media asset from a folder created by the export command
+/// Returns the ID of the new media asset, or None if the import failed.
+/// Errors are printed instead of exiting, so the callers can clean up before.
+/// In internal mode, the session is kept open.
 pub async fn run_cmd_import_media(
     global_opts: CommandGlobalOptions,
     path: String,
     album: Option<String>,
     is_internal: bool,
-) {
+) -> Option<u64> {
     let url_parse_res = parse_vault_uri(get_vault_url(&global_opts.vault_url));
 
     if url_parse_res.is_err() {
@@ -50,7 +97,7 @@ pub async fn run_cmd_import_media(
             }
         }
 
-        process::exit(1);
+        return None;
     }
 
     let mut vault_url = url_parse_res.unwrap();
@@ -59,7 +106,7 @@ pub async fn run_cmd_import_media(
     let login_result = ensure_login(&vault_url, &None, global_opts.debug).await;
 
     if login_result.is_err() {
-        process::exit(1);
+        return None;
     }
 
     vault_url = login_result.unwrap();
@@ -77,18 +124,11 @@ pub async fn run_cmd_import_media(
                     album_param = Some(id);
                 }
                 Err(_) => {
-                    if logout_after_operation {
-                        let logout_res = do_logout(&global_opts, &vault_url).await;
-
-                        match logout_res {
-                            Ok(_) => {}
-                            Err(_) => {
-                                process::exit(1);
-                            }
-                        }
-                    }
                     eprintln!("Invalid album identifier specified.");
-                    process::exit(1);
+                    if !is_internal && logout_after_operation {
+                        let _ = do_logout(&global_opts, &vault_url).await;
+                    }
+                    return None;
                 }
             }
         }
@@ -97,124 +137,288 @@ pub async fn run_cmd_import_media(
         }
     }
 
-    let metadata_file = std::path::Path::new(&path)
+    let import_metadata = match read_media_import_metadata(&path).await {
+        Ok(m) => m,
+        Err(e_str) => {
+            eprintln!("Could not read metadata file. Error: {e_str}");
+            if !is_internal && logout_after_operation {
+                let _ = do_logout(&global_opts, &vault_url).await;
+            }
+            return None;
+        }
+    };
+
+    // Upload
+
+    let upload_res = upload_media_import(
+        &global_opts,
+        &vault_url,
+        &path,
+        &import_metadata,
+        album_param,
+    )
+    .await;
+
+    let media_id: u64 = match upload_res {
+        Some(id) => id,
+        None => {
+            if !is_internal && logout_after_operation {
+                let _ = do_logout(&global_opts, &vault_url).await;
+            }
+            return None;
+        }
+    };
+
+    // Encryption and metadata
+
+    let finalize_res =
+        finalize_media_import(&global_opts, &vault_url, &path, media_id, &import_metadata).await;
+
+    if !finalize_res {
+        if !is_internal && logout_after_operation {
+            let _ = do_logout(&global_opts, &vault_url).await;
+        }
+        return None;
+    }
+
+    // Done
+
+    if !is_internal && logout_after_operation {
+        let logout_res = do_logout(&global_opts, &vault_url).await;
+
+        if logout_res.is_err() {
+            return None;
+        }
+    }
+
+    eprintln!("Done. Successfully imported media asset #{media_id}");
+
+    Some(media_id)
+}
+
+/// Reads the metadata file of a media export folder
+pub async fn read_media_import_metadata(path: &str) -> Result<MediaMetadataExport, String> {
+    let metadata_file = std::path::Path::new(path)
         .join("metadata.json")
         .to_str()
         .unwrap()
         .to_string();
 
-    let import_metadata: MediaMetadataExport;
-    let metadata_file_res = tokio::fs::read_to_string(&metadata_file).await;
-
-    match metadata_file_res {
-        Ok(metadata_str) => {
-            let parsed_metadata: Result<MediaMetadataExport, _> =
-                serde_json::from_str(&metadata_str);
+    let metadata_str = tokio::fs::read_to_string(&metadata_file)
+        .await
+        .map_err(|e| e.to_string())?;
 
-            match parsed_metadata {
-                Ok(m) => {
-                    import_metadata = m;
-                }
-                Err(e) => {
-                    let e_str = e.to_string();
-                    eprintln!("Could not read metadata file. Error: {e_str}");
-                    if is_internal {
-                        return;
-                    }
-                    if logout_after_operation {
-                        let logout_res = do_logout(&global_opts, &vault_url).await;
+    serde_json::from_str(&metadata_str).map_err(|e| e.to_string())
+}
 
-                        match logout_res {
-                            Ok(_) => {}
-                            Err(_) => {
-                                process::exit(1);
-                            }
-                        }
-                    }
-                    process::exit(1);
-                }
-            }
+/// Uploads the original file of a media export folder
+/// Returns the ID of the new media asset, or None if the upload failed
+pub async fn upload_media_import(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    path: &str,
+    import_metadata: &MediaMetadataExport,
+    album: Option<u64>,
+) -> Option<u64> {
+    // Validate everything before uploading, so an invalid folder does not leave a half imported media asset
+
+    let problems = validate_media_import(path, import_metadata);
+
+    if !problems.is_empty() {
+        for problem in problems.iter() {
+            eprintln!("Error: {problem}");
         }
-        Err(e) => {
-            let e_str = e.to_string();
-            eprintln!("Could not read metadata file. Error: {e_str}");
-            if logout_after_operation {
-                let logout_res = do_logout(&global_opts, &vault_url).await;
 
-                match logout_res {
-                    Ok(_) => {}
-                    Err(_) => {
-                        process::exit(1);
-                    }
-                }
-            }
-            process::exit(1);
-        }
+        eprintln!("Invalid media export folder: {path}. Nothing was uploaded.");
+        return None;
     }
 
-    // Upload
-
-    let original_file_path: String = match import_metadata.original {
-        Some(original_file) => std::path::Path::new(&path)
+    let original_file_path: String = match &import_metadata.original {
+        Some(original_file) => std::path::Path::new(path)
             .join(original_file)
             .to_str()
             .unwrap()
             .to_string(),
         None => {
-            if logout_after_operation {
-                let logout_res = do_logout(&global_opts, &vault_url).await;
-
-                match logout_res {
-                    Ok(_) => {}
-                    Err(_) => {
-                        process::exit(1);
-                    }
-                }
-            }
             eprintln!("Invalid metadata: No original file specified.");
-            process::exit(1);
+            return None;
         }
     };
 
     let progress_printer = Arc::new(Mutex::new(UploaderProgressPrinter::new()));
 
     let upload_api_res = api_call_upload_media(
-        &vault_url,
+        vault_url,
         original_file_path.clone(),
-        import_metadata.title,
-        album_param,
+        import_metadata.title.clone(),
+        album,
         global_opts.debug,
         progress_printer,
     )
     .await;
 
-    let media_id: u64;
-    let media_id_str: String;
-
     match upload_api_res {
         Ok(upload_res) => {
-            media_id = upload_res.media_id;
-            media_id_str = identifier_to_string(upload_res.media_id);
+            let media_id_str = identifier_to_string(upload_res.media_id);
 
             eprintln!("Upload completed: {original_file_path}");
             eprintln!("Media asset created: {media_id_str}");
+
+            Some(upload_res.media_id)
         }
         Err(e) => {
-            if logout_after_operation {
-                let logout_res = do_logout(&global_opts, &vault_url).await;
+            print_request_error(e);
+            None
+        }
+    }
+}
 
-                match logout_res {
-                    Ok(_) => {}
-                    Err(_) => {
-                        process::exit(1);
-                    }
-                }
+/// Validates the metadata of a media export folder, and the files it references
+/// Returns the list of problems found (empty if the folder can be imported)
+pub fn validate_media_import(path: &str, import_metadata: &MediaMetadataExport) -> Vec<String> {
+    let mut problems: Vec<String> = Vec::new();
+
+    // Original
+
+    match &import_metadata.original {
+        Some(original) => {
+            validate_import_file_exists(&mut problems, path, original);
+        }
+        None => {
+            problems.push("Invalid metadata: No original file specified.".to_string());
+        }
+    }
+
+    // Tags
+
+    if let Some(tags) = &import_metadata.tags {
+        for tag in tags {
+            if parse_tag_name(tag).is_empty() {
+                problems.push(format!("Invalid tag name: {tag}"));
+            }
+        }
+    }
+
+    // Thumbnail
+
+    if let Some(thumbnail) = &import_metadata.thumbnail {
+        validate_import_file_exists(&mut problems, path, thumbnail);
+    }
+
+    // Extended description
+
+    if let Some(ext_desc) = &import_metadata.ext_desc {
+        if validate_import_file_exists(&mut problems, path, ext_desc) {
+            if let Err(e) = std::fs::read_to_string(Path::new(path).join(ext_desc)) {
+                let e_str = e.to_string();
+                problems.push(format!("Could not read the file {ext_desc}: {e_str}"));
+            }
+        }
+    }
+
+    // Time slices
+
+    if let Some(time_slices) = &import_metadata.time_slices {
+        for time_slice in time_slices {
+            if !time_slice.time.is_finite() || time_slice.time < 0.0 {
+                let time_slice_name = &time_slice.name;
+                problems.push(format!(
+                    "Invalid time slice: {time_slice_name}. The time must be a positive number."
+                ));
             }
-            print_request_error(e);
-            process::exit(1);
         }
     }
 
+    // Image notes
+
+    if let Some(notes) = &import_metadata.notes {
+        if validate_import_file_exists(&mut problems, path, notes) {
+            let parsed_notes: Result<Vec<ImageNote>, String> =
+                match std::fs::read_to_string(Path::new(path).join(notes)) {
+                    Ok(s) => serde_json::from_str(&s).map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+
+            if let Err(e_str) = parsed_notes {
+                problems.push(format!(
+                    "The file {notes} does not contain a valid set of image notes: {e_str}"
+                ));
+            }
+        }
+    }
+
+    // Subtitles
+
+    if let Some(subtitles) = &import_metadata.subtitles {
+        let mut seen_ids: HashSet<&String> = HashSet::new();
+
+        for subtitle in subtitles {
+            validate_import_track_id(&mut problems, "subtitles", &subtitle.id, &mut seen_ids);
+            validate_import_file_exists(&mut problems, path, &subtitle.file);
+        }
+    }
+
+    // Audios
+
+    if let Some(audios) = &import_metadata.audios {
+        let mut seen_ids: HashSet<&String> = HashSet::new();
+
+        for audio in audios {
+            validate_import_track_id(&mut problems, "audio track", &audio.id, &mut seen_ids);
+            validate_import_file_exists(&mut problems, path, &audio.file);
+        }
+    }
+
+    // Attachments
+
+    if let Some(attachments) = &import_metadata.attachments {
+        for att in attachments {
+            if att.name.is_empty() {
+                let att_name = &att.name;
+                problems.push(format!("Invalid attachment name: {att_name}"));
+            }
+
+            validate_import_file_exists(&mut problems, path, &att.file);
+        }
+    }
+
+    problems
+}
+
+fn validate_import_file_exists(problems: &mut Vec<String>, path: &str, file: &str) -> bool {
+    if Path::new(path).join(file).is_file() {
+        true
+    } else {
+        problems.push(format!("Missing file: {file}"));
+        false
+    }
+}
+
+fn validate_import_track_id<'a>(
+    problems: &mut Vec<String>,
+    kind: &str,
+    id: &'a String,
+    seen_ids: &mut HashSet<&'a String>,
+) {
+    if id.is_empty() {
+        problems.push(format!("Invalid {kind} identifier: {id}"));
+    } else if !seen_ids.insert(id) {
+        problems.push(format!("Duplicated {kind} identifier: {id}"));
+    }
+}
+
+/// Waits for an uploaded media asset to be encrypted, and then sets
+/// the rest of the metadata of the media export folder
+/// Returns false if the encryption could not be checked
+pub async fn finalize_media_import(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    path: &str,
+    media_id: u64,
+    import_metadata: &MediaMetadataExport,
+) -> bool {
+    let media_id_str = identifier_to_string(media_id);
+
     // Wait for encryption
 
     let mut encryption_progress_printer = EncryptionProgressPrinter::new();
@@ -224,7 +428,7 @@ pub async fn run_cmd_import_media(
     let mut encryption_done = false;
 
     while !encryption_done {
-        let api_get_res = api_call_get_media(&vault_url, media_id, global_opts.debug).await;
+        let api_get_res = api_call_get_media(vault_url, media_id, global_opts.debug).await;
 
         match api_get_res {
             Ok(media_data) => {
@@ -240,32 +444,22 @@ pub async fn run_cmd_import_media(
             }
             Err(e) => {
                 encryption_progress_printer.progress_finish();
-                if logout_after_operation {
-                    let logout_res = do_logout(&global_opts, &vault_url).await;
-
-                    match logout_res {
-                        Ok(_) => {}
-                        Err(_) => {
-                            process::exit(1);
-                        }
-                    }
-                }
                 print_request_error(e);
-                process::exit(1);
+                return false;
             }
         }
     }
 
     // Add tags
 
-    if let Some(tags) = import_metadata.tags {
+    if let Some(tags) = &import_metadata.tags {
         for tag in tags {
             if global_opts.debug {
                 eprintln!("Adding tag {tag} to {media_id_str}...");
             }
 
             let api_tag_res = api_call_tag_add(
-                &vault_url,
+                vault_url,
                 AddTagBody {
                     media_id,
                     tag_name: tag.clone(),
@@ -287,10 +481,10 @@ pub async fn run_cmd_import_media(
 
     // Set description
 
-    if let Some(description) = import_metadata.description {
+    if let Some(description) = &import_metadata.description {
         if !description.is_empty() {
             let api_res = api_call_media_change_description(
-                &vault_url,
+                vault_url,
                 media_id,
                 MediaUpdateDescriptionBody {
                     description: description.clone(),
@@ -301,7 +495,7 @@ pub async fn run_cmd_import_media(
 
             match api_res {
                 Ok(_) => {
-                    let description_csv = to_csv_string(&description);
+                    let description_csv = to_csv_string(description);
 
                     eprintln!(
                         "Successfully updated the description of {media_id_str}: {description_csv}"
@@ -317,7 +511,7 @@ pub async fn run_cmd_import_media(
     // Set extra configuration
 
     let api_res = api_call_media_change_extra(
-        &vault_url,
+        vault_url,
         media_id,
         MediaUpdateExtraBody {
             force_start_beginning: import_metadata.force_start_beginning,
@@ -338,9 +532,9 @@ pub async fn run_cmd_import_media(
 
     // Set thumbnail
 
-    if let Some(thumbnail_file) = import_metadata.thumbnail {
-        let thumbnail_file_path = std::path::Path::new(&path)
-            .join(&thumbnail_file)
+    if let Some(thumbnail_file) = &import_metadata.thumbnail {
+        let thumbnail_file_path = std::path::Path::new(path)
+            .join(thumbnail_file)
             .to_str()
             .unwrap()
             .to_string();
@@ -348,7 +542,7 @@ pub async fn run_cmd_import_media(
         let progress_printer = Arc::new(Mutex::new(UploaderProgressPrinter::new()));
 
         let api_res = api_call_media_change_thumbnail(
-            &vault_url,
+            vault_url,
             media_id,
             thumbnail_file_path.clone(),
             global_opts.debug,
@@ -372,9 +566,9 @@ pub async fn run_cmd_import_media(
 
     // Set extended description
 
-    if let Some(ext_desc_file) = import_metadata.ext_desc {
-        let ext_desc_file_path = std::path::Path::new(&path)
-            .join(&ext_desc_file)
+    if let Some(ext_desc_file) = &import_metadata.ext_desc {
+        let ext_desc_file_path = std::path::Path::new(path)
+            .join(ext_desc_file)
             .to_str()
             .unwrap()
             .to_string();
@@ -384,7 +578,7 @@ pub async fn run_cmd_import_media(
         match ext_desc_read_res {
             Ok(ext_desc) => {
                 let api_res = api_call_media_change_extended_description(
-                    &vault_url,
+                    vault_url,
                     media_id,
                     MediaUpdateExtendedDescriptionBody { ext_desc },
                     global_opts.debug,
@@ -411,12 +605,12 @@ pub async fn run_cmd_import_media(
 
     // Set time slices
 
-    if let Some(time_slices) = import_metadata.time_slices {
+    if let Some(time_slices) = &import_metadata.time_slices {
         if !time_slices.is_empty() {
             let api_res = api_call_media_change_time_slices(
-                &vault_url,
+                vault_url,
                 media_id,
-                time_slices,
+                time_slices.clone(),
                 global_opts.debug,
             )
             .await;
@@ -434,9 +628,9 @@ pub async fn run_cmd_import_media(
 
     // Set image notes
 
-    if let Some(notes_file) = import_metadata.notes {
-        let notes_file_path = std::path::Path::new(&path)
-            .join(&notes_file)
+    if let Some(notes_file) = &import_metadata.notes {
+        let notes_file_path = std::path::Path::new(path)
+            .join(notes_file)
             .to_str()
             .unwrap()
             .to_string();
@@ -451,7 +645,7 @@ pub async fn run_cmd_import_media(
                 match parsed_notes_res {
                     Ok(image_notes) => {
                         let api_res = api_call_media_change_notes(
-                            &vault_url,
+                            vault_url,
                             media_id,
                             image_notes,
                             global_opts.debug,
@@ -483,10 +677,10 @@ pub async fn run_cmd_import_media(
 
     // Subtitles
 
-    if let Some(subtitles) = import_metadata.subtitles {
+    if let Some(subtitles) = &import_metadata.subtitles {
         for subtitle in subtitles {
             let sub_id = subtitle.id.clone();
-            let subtitle_file_path = std::path::Path::new(&path)
+            let subtitle_file_path = std::path::Path::new(path)
                 .join(&subtitle.file)
                 .to_str()
                 .unwrap()
@@ -495,7 +689,7 @@ pub async fn run_cmd_import_media(
             let progress_printer = Arc::new(Mutex::new(UploaderProgressPrinter::new()));
 
             let api_res = api_call_media_set_subtitle(
-                &vault_url,
+                vault_url,
                 media_id,
                 sub_id.clone(),
                 subtitle.name.clone(),
@@ -521,10 +715,10 @@ pub async fn run_cmd_import_media(
 
     // Audios
 
-    if let Some(audios) = import_metadata.audios {
+    if let Some(audios) = &import_metadata.audios {
         for audio in audios {
             let track_id = audio.id.clone();
-            let audio_file_path = std::path::Path::new(&path)
+            let audio_file_path = std::path::Path::new(path)
                 .join(&audio.file)
                 .to_str()
                 .unwrap()
@@ -533,7 +727,7 @@ pub async fn run_cmd_import_media(
             let progress_printer = Arc::new(Mutex::new(UploaderProgressPrinter::new()));
 
             let api_res = api_call_media_set_audio(
-                &vault_url,
+                vault_url,
                 media_id,
                 track_id.clone(),
                 audio.name.clone(),
@@ -559,10 +753,10 @@ pub async fn run_cmd_import_media(
 
     // Attachments
 
-    if let Some(attachments) = import_metadata.attachments {
+    if let Some(attachments) = &import_metadata.attachments {
         for att in attachments {
             let att_name = att.name.clone();
-            let att_file_path = std::path::Path::new(&path)
+            let att_file_path = std::path::Path::new(path)
                 .join(&att.file)
                 .to_str()
                 .unwrap()
@@ -571,7 +765,7 @@ pub async fn run_cmd_import_media(
             let progress_printer = Arc::new(Mutex::new(UploaderProgressPrinter::new()));
 
             let api_res = api_call_media_add_attachment(
-                &vault_url,
+                vault_url,
                 media_id,
                 att_file_path.clone(),
                 global_opts.debug,
@@ -586,7 +780,7 @@ pub async fn run_cmd_import_media(
                     // Rename the attachment
 
                     let api_rename_res = api_call_media_rename_attachment(
-                        &vault_url,
+                        vault_url,
                         media_id,
                         MediaRenameAttachmentBody {
                             id: uploaded_att.id,
@@ -614,18 +808,5 @@ pub async fn run_cmd_import_media(
         }
     }
 
-    // Done
-
-    if !is_internal && logout_after_operation {
-        let logout_res = do_logout(&global_opts, &vault_url).await;
-
-        match logout_res {
-            Ok(_) => {}
-            Err(_) => {
-                process::exit(1);
-            }
-        }
-    }
-
-    eprintln!("Done. Successfully imported media asset #{media_id}");
+    true
 }
@@ -0,0 +1,146 @@
+// Media import command (sidecar metadata)
+
+use std::{path::Path, process};
+
+use crate::{
+    models::MediaMetadataExport,
+    tools::{
+        find_sidecar_file, get_temp_folder_path, read_sidecar_metadata, remove_temp_folder,
+        to_csv_string,
+    },
+};
+
+use super::{media_import::run_cmd_import_media, CommandGlobalOptions};
+
+/// Imports a media file, reading its metadata from a sidecar file (NFO or yt-dlp info.json)
+/// The metadata is converted into the export format, so the regular import command can be used
+pub async fn run_cmd_import_media_sidecar(
+    global_opts: CommandGlobalOptions,
+    path: String,
+    sidecar: Option<String>,
+    album: Option<String>,
+) {
+    let media_file = match std::fs::canonicalize(&path) {
+        Ok(p) => p,
+        Err(e) => {
+            let e_str = e.to_string();
+            eprintln!("Could not find the file {path}: {e_str}");
+            process::exit(1);
+        }
+    };
+
+    if !media_file.is_file() {
+        eprintln!("The path {path} is not a file.");
+        process::exit(1);
+    }
+
+    let sidecar_file = match sidecar {
+        Some(s) => Path::new(&s).to_path_buf(),
+        None => match find_sidecar_file(&media_file) {
+            Some(s) => s,
+            None => {
+                eprintln!("Could not find a sidecar file for {path}. Expected a .nfo or .info.json file with the same name.");
+                eprintln!("Use the --sidecar option to specify it.");
+                process::exit(1);
+            }
+        },
+    };
+
+    let sidecar_file_str = sidecar_file.to_string_lossy().to_string();
+
+    let sidecar_metadata = match read_sidecar_metadata(&media_file, &sidecar_file) {
+        Ok(m) => m,
+        Err(e_str) => {
+            eprintln!("Could not read the sidecar file {sidecar_file_str}. Error: {e_str}");
+            process::exit(1);
+        }
+    };
+
+    eprintln!("Read metadata from {sidecar_file_str}");
+
+    if let Some(title) = &sidecar_metadata.title {
+        let title_csv = to_csv_string(title);
+        eprintln!("Title: {title_csv}");
+    }
+
+    if !sidecar_metadata.tags.is_empty() {
+        let tags_str = sidecar_metadata.tags.join(" ");
+        eprintln!("Tags: {tags_str}");
+    }
+
+    if !sidecar_metadata.time_slices.is_empty() {
+        let chapters_count = sidecar_metadata.time_slices.len();
+        eprintln!("Chapters: {chapters_count}");
+    }
+
+    // Prepare the import folder
+
+    let temp_folder = get_temp_folder_path("sidecar");
+
+    if let Err(e) = tokio::fs::create_dir_all(&temp_folder).await {
+        let e_str = e.to_string();
+        remove_temp_folder(&temp_folder).await;
+        eprintln!("Could not create temporary folder: {temp_folder}. Error: {e_str}");
+        process::exit(1);
+    }
+
+    let ext_desc_file: Option<String> = match &sidecar_metadata.ext_desc {
+        Some(ext_desc) => {
+            let write_res =
+                tokio::fs::write(Path::new(&temp_folder).join("ext_desc.md"), ext_desc).await;
+
+            if let Err(e) = write_res {
+                let e_str = e.to_string();
+                remove_temp_folder(&temp_folder).await;
+                eprintln!("Could not write the extended description. Error: {e_str}");
+                process::exit(1);
+            }
+
+            Some("ext_desc.md".to_string())
+        }
+        None => None,
+    };
+
+    // Absolute paths are kept as they are when joined with the import folder
+
+    let thumbnail_file: Option<String> = sidecar_metadata
+        .thumbnail
+        .and_then(|t| std::fs::canonicalize(t).ok())
+        .map(|t| t.to_string_lossy().to_string());
+
+    let import_metadata = MediaMetadataExport {
+        id: None,
+        original: Some(media_file.to_string_lossy().to_string()),
+        thumbnail: thumbnail_file,
+        notes: None,
+        ext_desc: ext_desc_file,
+        title: sidecar_metadata.title,
+        description: sidecar_metadata.description,
+        tags: Some(sidecar_metadata.tags),
+        force_start_beginning: None,
+        is_anim: None,
+        time_slices: Some(sidecar_metadata.time_slices),
+        subtitles: None,
+        audios: None,
+        attachments: None,
+    };
+
+    let metadata_str = serde_json::to_string(&import_metadata).unwrap();
+    let write_res =
+        tokio::fs::write(Path::new(&temp_folder).join("metadata.json"), metadata_str).await;
+
+    if let Err(e) = write_res {
+        let e_str = e.to_string();
+        remove_temp_folder(&temp_folder).await;
+        eprintln!("Could not write the metadata file. Error: {e_str}");
+        process::exit(1);
+    }
+
+    let import_res = run_cmd_import_media(global_opts, temp_folder.clone(), album, false).await;
+
+    remove_temp_folder(&temp_folder).await;
+
+    if import_res.is_none() {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,139 @@
+// Concurrent fetching of media metadata
+
+use std::{collections::HashMap, process};
+
+use tokio::task::JoinSet;
+
+use crate::{
+    api::api_call_get_media,
+    models::{MediaListItem, MediaMetadata},
+    tools::{sort_media_list, MediaSort, RequestError, VaultURI},
+};
+
+use super::{logout::do_logout, print_request_error, CommandGlobalOptions};
+
+/// Max number of media metadata requests to run at the same time
+const METADATA_FETCH_CONCURRENCY: usize = 8;
+
+/// Fetches the metadata of a list of media assets, with bounded concurrency
+/// Media assets deleted in the meantime are not included in the result
+/// On any other error, the process exits
+pub async fn fetch_media_metadata_list(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    logout_after_operation: bool,
+    media_ids: &[u64],
+) -> HashMap<u64, MediaMetadata> {
+    let mut result: HashMap<u64, MediaMetadata> = HashMap::new();
+    let mut pending = media_ids.iter().copied();
+    let mut tasks: JoinSet<(u64, Result<MediaMetadata, RequestError>)> = JoinSet::new();
+
+    loop {
+        while tasks.len() < METADATA_FETCH_CONCURRENCY {
+            match pending.next() {
+                Some(media_id) => {
+                    let task_url = vault_url.clone();
+                    let debug = global_opts.debug;
+
+                    tasks.spawn(async move {
+                        (
+                            media_id,
+                            api_call_get_media(&task_url, media_id, debug).await,
+                        )
+                    });
+                }
+                None => {
+                    break;
+                }
+            }
+        }
+
+        let (media_id, metadata_res) = match tasks.join_next().await {
+            Some(Ok(r)) => r,
+            Some(Err(e)) => {
+                if logout_after_operation {
+                    let logout_res = do_logout(global_opts, vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                eprintln!("Error: {e}");
+                process::exit(1);
+            }
+            None => {
+                break;
+            }
+        };
+
+        match metadata_res {
+            Ok(metadata) => {
+                result.insert(media_id, metadata);
+            }
+            Err(RequestError::StatusCode(status)) | Err(RequestError::Api { status, .. })
+                if status == 404 => {}
+            Err(e) => {
+                tasks.abort_all();
+                print_request_error(e);
+                if logout_after_operation {
+                    let logout_res = do_logout(global_opts, vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                process::exit(1);
+            }
+        }
+    }
+
+    result
+}
+
+/// Gets the upload times of a list of media assets, only if required by the sorting criteria
+pub async fn get_sort_upload_times(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    logout_after_operation: bool,
+    sort: &MediaSort,
+    media_ids: &[u64],
+) -> HashMap<u64, i64> {
+    if !sort.requires_upload_time() {
+        return HashMap::new();
+    }
+
+    fetch_media_metadata_list(global_opts, vault_url, logout_after_operation, media_ids)
+        .await
+        .into_iter()
+        .map(|(id, metadata)| (id, metadata.upload_time))
+        .collect()
+}
+
+/// Sorts a list of media assets, fetching the upload times if required
+pub async fn sort_media_results(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    logout_after_operation: bool,
+    sort: &Option<MediaSort>,
+    media_list: &mut [MediaListItem],
+) {
+    if let Some(s) = sort {
+        let media_ids: Vec<u64> = media_list.iter().map(|m| m.id).collect();
+        let upload_times = get_sort_upload_times(
+            global_opts,
+            vault_url,
+            logout_after_operation,
+            s,
+            &media_ids,
+        )
+        .await;
+
+        sort_media_list(media_list, s, &upload_times);
+    }
+}
@@ -0,0 +1,63 @@
+// Playlists of media assets
+
+use crate::{
+    models::MediaListItem,
+    tools::{
+        get_playlist_asset_path, identifier_to_string, render_playlist, PlaylistEntry,
+        PlaylistOptions, VaultURI,
+    },
+};
+
+use super::{media_metadata_fetch::fetch_media_metadata_list, CommandGlobalOptions};
+
+/// Prints a playlist of media assets, with session-authenticated links
+/// Media assets not ready yet are skipped
+/// The session is kept open, since the links depend on it
+pub async fn print_media_playlist(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    logout_after_operation: bool,
+    playlist: &PlaylistOptions,
+    media_list: &[MediaListItem],
+) {
+    let media_ids: Vec<u64> = media_list.iter().map(|m| m.id).collect();
+    let metadata_map =
+        fetch_media_metadata_list(global_opts, vault_url, logout_after_operation, &media_ids).await;
+
+    let mut entries: Vec<PlaylistEntry> = Vec::with_capacity(media_list.len());
+
+    for item in media_list {
+        let metadata = match metadata_map.get(&item.id) {
+            Some(m) => m,
+            None => {
+                continue;
+            }
+        };
+
+        match get_playlist_asset_path(metadata, &playlist.resolutions) {
+            Some(path) => {
+                let title = if metadata.title.is_empty() {
+                    identifier_to_string(item.id)
+                } else {
+                    metadata.title.clone()
+                };
+
+                entries.push(PlaylistEntry {
+                    title,
+                    duration: metadata.duration,
+                    url: vault_url.resolve_asset(&path),
+                });
+            }
+            None => {
+                let media_id_str = identifier_to_string(item.id);
+                eprintln!("Skipped {media_id_str}: The media asset is not ready yet");
+            }
+        }
+    }
+
+    print!("{}", render_playlist(playlist.format, &entries));
+
+    if logout_after_operation {
+        eprintln!("The session was kept open, so the links of the playlist remain valid.");
+    }
+}
@@ -15,15 +15,33 @@ use album_import::*;
 mod album_thumbnail;
 use album_thumbnail::*;
 
+mod batch_dry_run;
+mod batch_execution;
 mod batch_operation;
 use batch_operation::*;
 
+mod catalog;
+use catalog::*;
+
+mod catalog_apply;
+mod catalog_export;
+
 mod config;
 use config::*;
 
 mod disk_usage;
 pub use disk_usage::*;
 
+mod export;
+use export::*;
+
+mod export_verify;
+
+mod import_dry_run;
+
+mod import_tree;
+use import_tree::*;
+
 mod invites;
 use invites::*;
 
@@ -41,8 +59,13 @@ mod media_audio_tracks;
 mod media_download;
 mod media_export;
 mod media_extended_description;
+mod media_find_duplicates;
+mod media_find_orphans;
 mod media_image_notes;
 mod media_import;
+mod media_import_sidecar;
+mod media_metadata_fetch;
+mod media_playlist;
 mod media_replace;
 mod media_resolutions;
 mod media_subtitles;
@@ -59,15 +82,30 @@ use search_advanced::*;
 mod search_basic;
 use search_basic::*;
 
+mod search_saved;
+use search_saved::*;
+
 mod server_info;
 use server_info::*;
 
+mod sync;
+use sync::*;
+
 mod tag;
 use tag::*;
 
 mod task;
 use task::*;
 
+mod undo;
+use undo::*;
+
+mod vault;
+use vault::*;
+
+mod vault_export;
+mod vault_restore;
+
 use clap::Subcommand;
 
 use crate::tools::RequestError;
@@ -77,6 +115,7 @@ pub struct CommandGlobalOptions {
     pub debug: bool,
     pub auto_confirm: bool,
     pub vault_url: Option<String>,
+    pub undo_log: bool,
 }
 
 #[derive(Subcommand)]
@@ -136,9 +175,26 @@ pub enum Commands {
         /// CSV format
         #[arg(short, long)]
         csv: bool,
+
+        /// Sort the results on the client side. Can be: title, duration, id, type or upload
+        #[arg(long)]
+        sort: Option<String>,
+
+        /// Sort in descending order. Requires --sort.
+        #[arg(long, requires = "sort")]
+        desc: bool,
+
+        /// Print a playlist of the results instead, with links to the media assets. Can be: m3u or xspf
+        #[arg(long, conflicts_with = "csv")]
+        format: Option<String>,
+
+        /// Preferred resolutions for the playlist, in order, separated by commas. Example: 720p,480p. The original is used if none of them is available. Requires --format.
+        #[arg(long, requires = "format")]
+        resolution: Option<String>,
     },
 
-    /// Searches for media assets in the vault (Basic)
+    /// Searches for media assets in the vault (Basic). Also manages saved searches.
+    #[command(args_conflicts_with_subcommands = true)]
     Search {
         /// Selects the results page. The fist page is the page 1.
         #[arg(short, long)]
@@ -163,6 +219,33 @@ pub enum Commands {
         /// CSV format
         #[arg(short, long)]
         csv: bool,
+
+        /// Get all the pages, printing the results as they arrive. The progress is printed into the standard error.
+        #[arg(long, conflicts_with = "page")]
+        all: bool,
+
+        /// JSON Lines format, one result per line. Requires --all.
+        #[arg(long, requires = "all")]
+        jsonl: bool,
+
+        /// Sort the results on the client side. Can be: title, duration, id, type or upload
+        #[arg(long)]
+        sort: Option<String>,
+
+        /// Sort in descending order. Requires --sort.
+        #[arg(long, requires = "sort")]
+        desc: bool,
+
+        /// Print a playlist of the results instead, with links to the media assets. Can be: m3u or xspf
+        #[arg(long, conflicts_with_all = ["csv", "all"])]
+        format: Option<String>,
+
+        /// Preferred resolutions for the playlist, in order, separated by commas. Example: 720p,480p. The original is used if none of them is available. Requires --format.
+        #[arg(long, requires = "format")]
+        resolution: Option<String>,
+
+        #[command(subcommand)]
+        search_cmd: Option<SearchCommand>,
     },
 
     /// Searches for media assets in the vault (Advanced)
@@ -192,6 +275,22 @@ pub enum Commands {
         #[arg(short, long)]
         album: Option<String>,
 
+        /// Filter with a search query. Example: type:video tag:travel -tag:draft title:"beach" duration>10m uploaded:2024 -resolution:1080p. Fields: title, desc, type, tag, is (untagged, ready, encoded), has (subtitles, audios, attachments, chapters), duration, uploaded, width, height, fps and resolution.
+        #[arg(long)]
+        query: Option<String>,
+
+        /// Match the title and description filters as case-insensitive regular expressions
+        #[arg(long, conflicts_with = "fuzzy")]
+        regex: bool,
+
+        /// Match the title and description filters ignoring accents and repeated spaces
+        #[arg(long)]
+        fuzzy: bool,
+
+        /// Use the criteria of a saved search. Other filter options override the saved ones.
+        #[arg(short = 'S', long)]
+        search: Option<String>,
+
         /// Limit on the number of results to get. 25 by default.
         #[arg(short, long)]
         limit: Option<u32>,
@@ -211,6 +310,38 @@ pub enum Commands {
         /// CSV format
         #[arg(short, long)]
         csv: bool,
+
+        /// Scan the entire vault, printing the results as they arrive. The progress is printed into the standard error.
+        #[arg(long, conflicts_with = "limit")]
+        all: bool,
+
+        /// JSON Lines format, one result per line. Requires --all.
+        #[arg(long, requires = "all")]
+        jsonl: bool,
+
+        /// Print only the IDs of the results, one per line. Can be used as input for batch --ids-from.
+        #[arg(long, conflicts_with_all = ["csv", "extended", "jsonl", "format"])]
+        ids: bool,
+
+        /// Sort the results on the client side. Can be: title, duration, id, type or upload
+        #[arg(long)]
+        sort: Option<String>,
+
+        /// Sort in descending order. Requires --sort.
+        #[arg(long, requires = "sort")]
+        desc: bool,
+
+        /// Print a playlist of the results instead, with links to the media assets. Can be: m3u or xspf
+        #[arg(long, conflicts_with_all = ["csv", "all"])]
+        format: Option<String>,
+
+        /// Preferred resolutions for the playlist, in order, separated by commas. Example: 720p,480p. The original is used if none of them is available. Requires --format.
+        #[arg(long, requires = "format")]
+        resolution: Option<String>,
+
+        /// Export the results into a folder, as a browsable offline HTML gallery
+        #[arg(long)]
+        export_html: Option<String>,
     },
 
     /// Manages tags
@@ -247,6 +378,85 @@ pub enum Commands {
         invites_cmd: InvitesCommand,
     },
 
+    /// Exports and restores the entire vault
+    Vault {
+        #[command(subcommand)]
+        vault_cmd: VaultCommand,
+    },
+
+    /// Exports and applies catalogs of the vault metadata
+    Catalog {
+        #[command(subcommand)]
+        catalog_cmd: CatalogCommand,
+    },
+
+    /// Verifies exports
+    Export {
+        #[command(subcommand)]
+        export_cmd: ExportCommand,
+    },
+
+    /// Copies media assets from a vault into another, keeping a mapping file to avoid copying the same media twice
+    Sync {
+        /// Vault URL of the source vault
+        #[arg(long)]
+        from: String,
+
+        /// Vault URL of the target vault
+        #[arg(long)]
+        to: String,
+
+        /// Filter by title
+        #[arg(short = 'q', long)]
+        title: Option<String>,
+
+        /// Filter by description.
+        #[arg(short, long)]
+        description: Option<String>,
+
+        /// Filter by media type. Can be: video, audio or image
+        #[arg(short = 'k', long)]
+        media_type: Option<String>,
+
+        /// Filter by tags. Expected a list of tag names, separated by spaces.
+        #[arg(short, long)]
+        tags: Option<String>,
+
+        /// Tag filtering mode. Can be: all, any, none or untagged
+        #[arg(short = 'm', long)]
+        tags_mode: Option<String>,
+
+        /// Filter by album of the source vault. Expected an album ID, like: #1
+        #[arg(short, long)]
+        album: Option<String>,
+
+        /// Use the criteria of a saved search. Other filter options override the saved ones.
+        #[arg(short = 'S', long)]
+        search: Option<String>,
+
+        /// Do not filter. Copy the entire vault instead.
+        #[arg(short, long)]
+        everything: bool,
+
+        /// Path to the file to store the mapping of source IDs to target IDs. By default, sync_mapping.json
+        #[arg(long)]
+        mapping: Option<String>,
+    },
+
+    /// Imports a directory tree. Each sub-directory becomes an album, ordered by file name, using its first image as thumbnail.
+    ImportTree {
+        /// Path to the root directory. The media files directly inside it are imported without album.
+        root: String,
+
+        /// Add the names of the directories in the path of each file as tags
+        #[arg(short, long)]
+        path_tags: bool,
+
+        /// Tags to add to every imported media asset. Expected a list of tag names, separated by spaces.
+        #[arg(short, long)]
+        tags: Option<String>,
+    },
+
     /// Applies a batch operation to a list of media assets
     Batch {
         /// Filter by title
@@ -273,14 +483,68 @@ pub enum Commands {
         #[arg(short, long)]
         album: Option<String>,
 
+        /// Filter with a search query. Example: type:video tag:travel -tag:draft title:"beach" duration>10m uploaded:2024 -resolution:1080p. Fields: title, desc, type, tag, is (untagged, ready, encoded), has (subtitles, audios, attachments, chapters), duration, uploaded, width, height, fps and resolution.
+        #[arg(long)]
+        query: Option<String>,
+
+        /// Match the title and description filters as case-insensitive regular expressions
+        #[arg(long, conflicts_with = "fuzzy")]
+        regex: bool,
+
+        /// Match the title and description filters ignoring accents and repeated spaces
+        #[arg(long)]
+        fuzzy: bool,
+
+        /// Use the criteria of a saved search. Other filter options override the saved ones.
+        #[arg(short = 'S', long)]
+        search: Option<String>,
+
+        /// Apply to the media assets listed in a file, one ID per line. Use - to read the list from the standard input (requires -y, since the confirmation cannot be asked). Other filters are applied to the listed media assets.
+        #[arg(long)]
+        ids_from: Option<String>,
+
         /// Do not filter. Apply to the entire vault instead.
-        #[arg(short, long)]
+        #[arg(short, long, conflicts_with = "ids_from")]
         everything: bool,
 
+        /// Print the matched media assets and the changes to apply to each of them, without applying them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Print the dry run as JSON, so it can be saved for review. Requires --dry-run.
+        #[arg(long, requires = "dry_run")]
+        json: bool,
+
+        /// Number of media assets to process at the same time (1 by default)
+        #[arg(long)]
+        concurrency: Option<u32>,
+
+        /// Do not stop on the first error. Continue with the rest of the media assets and report the failures at the end.
+        #[arg(long)]
+        continue_on_error: bool,
+
+        /// Record the completed media assets in a journal file. If the operation is interrupted, running it again with the same journal skips the already completed media assets.
+        #[arg(long, conflicts_with = "dry_run")]
+        journal: Option<String>,
+
         #[command(subcommand)]
         batch_command: BatchCommand,
     },
 
+    /// Reverts the last operation recorded in the local undo journal, or a specific one. Operations are recorded when using the --undo-log option.
+    Undo {
+        /// Undo journal entry ID. By default, the last one not reverted yet.
+        entry: Option<String>,
+
+        /// Lists the undo journal entries, instead of reverting one
+        #[arg(short, long, conflicts_with = "entry")]
+        list: bool,
+
+        /// CSV format. Requires --list.
+        #[arg(short, long, requires = "list")]
+        csv: bool,
+    },
+
     /// Gets server information, like the version it is using.
     #[clap(alias("server-info"))]
     GetServerInformation,
@@ -315,8 +579,24 @@ pub async fn run_cmd(global_opts: CommandGlobalOptions, cmd: Commands) {
             tag,
             extended,
             csv,
+            sort,
+            desc,
+            format,
+            resolution,
         } => {
-            run_cmd_random(global_opts, seed, page_size, tag, extended, csv).await;
+            run_cmd_random(
+                global_opts,
+                seed,
+                page_size,
+                tag,
+                extended,
+                csv,
+                sort,
+                desc,
+                format,
+                resolution,
+            )
+            .await;
         }
         Commands::Search {
             page,
@@ -325,9 +605,36 @@ pub async fn run_cmd(global_opts: CommandGlobalOptions, cmd: Commands) {
             reverse,
             extended,
             csv,
-        } => {
-            run_cmd_search_basic(global_opts, page, page_size, tag, reverse, extended, csv).await;
-        }
+            all,
+            jsonl,
+            sort,
+            desc,
+            format,
+            resolution,
+            search_cmd,
+        } => match search_cmd {
+            Some(cmd) => {
+                run_search_cmd(global_opts, cmd).await;
+            }
+            None => {
+                run_cmd_search_basic(
+                    global_opts,
+                    page,
+                    page_size,
+                    tag,
+                    reverse,
+                    extended,
+                    csv,
+                    all,
+                    jsonl,
+                    sort,
+                    desc,
+                    format,
+                    resolution,
+                )
+                .await;
+            }
+        },
         Commands::AdvancedSearch {
             title,
             description,
@@ -335,25 +642,66 @@ pub async fn run_cmd(global_opts: CommandGlobalOptions, cmd: Commands) {
             tags,
             tags_mode,
             album,
+            query,
+            regex,
+            fuzzy,
+            search,
             limit,
             start_from,
             reverse,
             extended,
             csv,
+            all,
+            jsonl,
+            ids,
+            sort,
+            desc,
+            format,
+            resolution,
+            export_html,
         } => {
-            run_cmd_search_advanced(
-                global_opts,
+            let mut filter = BatchMediaFilter {
                 title,
                 description,
                 media_type,
                 tags,
                 tags_mode,
                 album,
+                query,
+                regex,
+                fuzzy,
+                ids: None,
+                everything: false,
+            };
+
+            if let Some(search_name) = search {
+                filter = filter.with_saved_search(load_saved_search(&search_name));
+            }
+
+            run_cmd_search_advanced(
+                global_opts,
+                filter.title,
+                filter.description,
+                filter.media_type,
+                filter.tags,
+                filter.tags_mode,
+                filter.album,
+                filter.query,
+                filter.regex,
+                filter.fuzzy,
                 limit,
                 start_from,
                 reverse,
                 extended,
                 csv,
+                all,
+                jsonl,
+                ids,
+                sort,
+                desc,
+                format,
+                resolution,
+                export_html,
             )
             .await;
         }
@@ -369,6 +717,51 @@ pub async fn run_cmd(global_opts: CommandGlobalOptions, cmd: Commands) {
         Commands::Task { task_cmd } => {
             run_task_cmd(global_opts, task_cmd).await;
         }
+        Commands::Vault { vault_cmd } => {
+            run_vault_cmd(global_opts, vault_cmd).await;
+        }
+        Commands::Catalog { catalog_cmd } => {
+            run_catalog_cmd(global_opts, catalog_cmd).await;
+        }
+        Commands::Export { export_cmd } => {
+            run_export_cmd(global_opts, export_cmd).await;
+        }
+        Commands::Sync {
+            from,
+            to,
+            title,
+            description,
+            media_type,
+            tags,
+            tags_mode,
+            album,
+            search,
+            everything,
+            mapping,
+        } => {
+            run_cmd_sync(
+                global_opts,
+                from,
+                to,
+                title,
+                description,
+                media_type,
+                tags,
+                tags_mode,
+                album,
+                search,
+                everything,
+                mapping,
+            )
+            .await;
+        }
+        Commands::ImportTree {
+            root,
+            path_tags,
+            tags,
+        } => {
+            run_cmd_import_tree(global_opts, root, path_tags, tags).await;
+        }
         Commands::Batch {
             title,
             description,
@@ -376,7 +769,17 @@ pub async fn run_cmd(global_opts: CommandGlobalOptions, cmd: Commands) {
             tags,
             tags_mode,
             album,
+            query,
+            regex,
+            fuzzy,
+            search,
+            ids_from,
             everything,
+            dry_run,
+            json,
+            concurrency,
+            continue_on_error,
+            journal,
             batch_command,
         } => {
             run_cmd_batch_operation(
@@ -387,7 +790,17 @@ pub async fn run_cmd(global_opts: CommandGlobalOptions, cmd: Commands) {
                 tags,
                 tags_mode,
                 album,
+                query,
+                regex,
+                fuzzy,
+                search,
+                ids_from,
                 everything,
+                dry_run,
+                json,
+                concurrency,
+                continue_on_error,
+                journal,
                 batch_command,
             )
             .await;
@@ -395,6 +808,13 @@ pub async fn run_cmd(global_opts: CommandGlobalOptions, cmd: Commands) {
         Commands::Invites { invites_cmd } => {
             run_invites_cmd(global_opts, invites_cmd).await;
         }
+        Commands::Undo { entry, list, csv } => {
+            if list {
+                run_cmd_undo_list(csv);
+            } else {
+                run_cmd_undo(global_opts, entry).await;
+            }
+        }
         Commands::GetServerInformation => {
             run_cmd_server_info(global_opts).await;
         }
@@ -4,7 +4,7 @@ use std::{process, time::Duration};
 
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::tools::{render_media_duration, to_csv_string};
+use crate::tools::{get_media_sort, get_playlist_options, render_media_duration, to_csv_string};
 use crate::{
     api::{api_call_get_tags, api_call_random},
     models::{tags_map_from_list, tags_names_from_ids},
@@ -14,10 +14,15 @@ use crate::{
     },
 };
 
-use super::{get_vault_url, logout::do_logout, print_request_error, CommandGlobalOptions};
+use super::{
+    get_vault_url, logout::do_logout, media_metadata_fetch::sort_media_results,
+    media_playlist::print_media_playlist,
+    print_request_error, CommandGlobalOptions,
+};
 
 const DEFAULT_PAGE_SIZE: u32 = 10;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn run_cmd_random(
     global_opts: CommandGlobalOptions,
     seed: Option<i64>,
@@ -25,7 +30,27 @@ pub async fn run_cmd_random(
     tag: Option<String>,
     extended: bool,
     csv: bool,
+    sort: Option<String>,
+    desc: bool,
+    format: Option<String>,
+    resolution: Option<String>,
 ) {
+    let media_sort = match get_media_sort(sort, desc) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
+
+    let playlist_options = match get_playlist_options(format, resolution) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
+
     let url_parse_res = parse_vault_uri(get_vault_url(&global_opts.vault_url));
 
     if url_parse_res.is_err() {
@@ -120,7 +145,29 @@ pub async fn run_cmd_random(
     .await;
 
     match api_res {
-        Ok(random_result) => {
+        Ok(mut random_result) => {
+            sort_media_results(
+                &global_opts,
+                &vault_url,
+                logout_after_operation,
+                &media_sort,
+                &mut random_result.page_items,
+            )
+            .await;
+
+            if let Some(playlist) = &playlist_options {
+                print_media_playlist(
+                    &global_opts,
+                    &vault_url,
+                    logout_after_operation,
+                    playlist,
+                    &random_result.page_items,
+                )
+                .await;
+
+                return;
+            }
+
             if logout_after_operation {
                 let logout_res = do_logout(&global_opts, &vault_url).await;
 
@@ -2,19 +2,35 @@
 
 use std::process;
 
-use crate::api::{api_call_get_album, api_call_search_advanced, MAX_API_TAGS_FILTER};
+use crate::api::{
+    api_call_get_album, api_call_search_advanced, MAX_API_TAGS_FILTER, MAX_SEARCH_PAGE_LIMIT,
+};
 use crate::models::{
     parse_media_type, parse_tag_name, parse_tag_search_mode, tags_reverse_map_from_list,
     MediaListItem, MediaType, TagSearchMode,
 };
-use crate::tools::{render_media_duration, to_csv_string};
+use crate::tools::{
+    get_media_sort, get_playlist_options, get_search_results_format, get_text_filter, get_text_match_mode, parse_search_query,
+    print_search_progress, render_media_duration, to_csv_string, SearchQuery,
+    SearchResultsStream, TextFilter,
+};
 use crate::{
     api::api_call_get_tags,
     models::{tags_map_from_list, tags_names_from_ids},
-    tools::{ensure_login, identifier_to_string, parse_identifier, parse_vault_uri, print_table},
+    tools::{
+        ensure_login, identifier_to_string, parse_identifier, parse_vault_uri, print_table,
+        ExportOutput, VaultURI,
+    },
 };
 
-use super::{get_vault_url, logout::do_logout, print_request_error, CommandGlobalOptions};
+use super::{
+    get_vault_url,
+    logout::do_logout,
+    media_export::{export_media, write_export_html_gallery, MediaExportResult},
+    media_metadata_fetch::{fetch_media_metadata_list, sort_media_results},
+    media_playlist::print_media_playlist,
+    print_request_error, CommandGlobalOptions,
+};
 
 const DEFAULT_RESULTS_LIMIT: u32 = 25;
 
@@ -27,12 +43,39 @@ pub async fn run_cmd_search_advanced(
     tags: Option<String>,
     tags_mode: Option<String>,
     album: Option<String>,
+    query: Option<String>,
+    regex: bool,
+    fuzzy: bool,
     limit: Option<u32>,
     start_from: Option<String>,
     reverse: bool,
     extended: bool,
     csv: bool,
+    all: bool,
+    jsonl: bool,
+    ids: bool,
+    sort: Option<String>,
+    desc: bool,
+    format: Option<String>,
+    resolution: Option<String>,
+    export_html: Option<String>,
 ) {
+    let media_sort = match get_media_sort(sort, desc) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
+
+    let playlist_options = match get_playlist_options(format, resolution) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
+
     let url_parse_res = parse_vault_uri(get_vault_url(&global_opts.vault_url));
 
     if url_parse_res.is_err() {
@@ -151,10 +194,55 @@ pub async fn run_cmd_search_advanced(
         }
     }
 
-    let limit_param = limit.unwrap_or(DEFAULT_RESULTS_LIMIT);
+    let limit_param = if all {
+        u32::MAX
+    } else {
+        limit.unwrap_or(DEFAULT_RESULTS_LIMIT)
+    };
+
+    let page_size_param = if all {
+        MAX_SEARCH_PAGE_LIMIT as u32
+    } else {
+        limit_param
+    };
+
+    let text_match_mode = get_text_match_mode(regex, fuzzy);
+
+    let title_filter = match get_text_filter(title, text_match_mode) {
+        Ok(f) => f,
+        Err(e) => {
+            if logout_after_operation {
+                let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                match logout_res {
+                    Ok(_) => {}
+                    Err(_) => {
+                        process::exit(1);
+                    }
+                }
+            }
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
+
+    let description_filter = match get_text_filter(description, text_match_mode) {
+        Ok(f) => f,
+        Err(e) => {
+            if logout_after_operation {
+                let logout_res = do_logout(&global_opts, &vault_url).await;
 
-    let title_filter = title.unwrap_or("".to_string());
-    let description_filter = description.unwrap_or("".to_string());
+                match logout_res {
+                    Ok(_) => {}
+                    Err(_) => {
+                        process::exit(1);
+                    }
+                }
+            }
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
 
     let mut media_type_filter: Option<MediaType> = None;
 
@@ -253,7 +341,7 @@ pub async fn run_cmd_search_advanced(
         }
     }
 
-    let tag_mode_api_param: String = match tags_filter_mode {
+    let mut tag_mode_api_param: String = match tags_filter_mode {
         TagSearchMode::All => "allof".to_string(),
         TagSearchMode::Any => {
             if tags_filter_count > MAX_API_TAGS_FILTER {
@@ -277,46 +365,123 @@ pub async fn run_cmd_search_advanced(
         TagSearchMode::Untagged => tag_param = None,
     }
 
+    let mut search_query: Option<SearchQuery> = None;
+
+    if let Some(query_str) = query {
+        match parse_search_query(&query_str, &tags_reverse_map, text_match_mode) {
+            Ok(q) => {
+                if tag_param.is_none() {
+                    if let Some((query_tags, query_tags_mode)) =
+                        q.get_server_tags_filter(MAX_API_TAGS_FILTER)
+                    {
+                        tag_param = Some(query_tags);
+                        tag_mode_api_param = query_tags_mode;
+                    }
+                }
+
+                search_query = Some(q);
+            }
+            Err(e) => {
+                if logout_after_operation {
+                    let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                eprintln!("Invalid search query: {e}");
+                process::exit(1);
+            }
+        }
+    }
+
     // Search
 
     let mut advanced_search_results: Vec<MediaListItem> = Vec::new();
 
+    // With --all, results are printed as they arrive, and only kept if they must be exported or sorted
+    let mut results_stream = if all {
+        Some(SearchResultsStream::new(
+            get_search_results_format(csv, jsonl, ids),
+            extended,
+            &tags_map,
+        ))
+    } else {
+        None
+    };
+    let sorting = media_sort.is_some();
+    let keep_results = export_html.is_some() || sorting;
+
     match album_filter {
         Some(album_list) => {
-            if reverse {
-                for item in album_list.iter().rev() {
-                    if media_matches_filter(
+            let mut album_matches: Vec<MediaListItem> = album_list
+                .into_iter()
+                .filter(|item| {
+                    media_matches_filter(
                         item,
                         &title_filter,
                         &description_filter,
                         &media_type_filter,
                         &tags_filter,
                         &tags_filter_mode,
-                    ) {
-                        advanced_search_results.push(item.clone());
+                        &search_query,
+                    )
+                })
+                .collect();
 
-                        if advanced_search_results.len() as u32 >= limit_param {
-                            break;
-                        }
+            if reverse {
+                album_matches.reverse();
+            }
+
+            let album_matches_count = album_matches.len() as u64;
+            let mut album_scanned: u64 = 0;
+
+            for chunk in album_matches.chunks(page_size_param.max(1) as usize) {
+                let chunk_matches = filter_media_by_metadata(
+                    &global_opts,
+                    &vault_url,
+                    logout_after_operation,
+                    &search_query,
+                    chunk.to_vec(),
+                )
+                .await;
+
+                if let Some(stream) = &mut results_stream {
+                    album_scanned += chunk.len() as u64;
+
+                    if !sorting {
+                        stream.write_items(&chunk_matches);
+                    }
+
+                    if keep_results {
+                        advanced_search_results.extend(chunk_matches);
                     }
+
+                    let found = if sorting {
+                        advanced_search_results.len() as u64
+                    } else {
+                        stream.count()
+                    };
+
+                    print_search_progress(album_scanned, album_matches_count, found);
+
+                    continue;
                 }
-            } else {
-                for item in album_list {
-                    if media_matches_filter(
-                        &item,
-                        &title_filter,
-                        &description_filter,
-                        &media_type_filter,
-                        &tags_filter,
-                        &tags_filter_mode,
-                    ) {
-                        advanced_search_results.push(item);
 
-                        if advanced_search_results.len() as u32 >= limit_param {
-                            break;
-                        }
+                for item in chunk_matches {
+                    advanced_search_results.push(item);
+
+                    if advanced_search_results.len() as u32 >= limit_param {
+                        break;
                     }
                 }
+
+                if advanced_search_results.len() as u32 >= limit_param {
+                    break;
+                }
             }
         }
         None => {
@@ -334,7 +499,7 @@ pub async fn run_cmd_search_advanced(
                     tag_param.as_deref(),
                     &tag_mode_api_param,
                     reverse,
-                    limit_param,
+                    page_size_param,
                     continue_ref,
                     global_opts.debug,
                 )
@@ -342,15 +507,53 @@ pub async fn run_cmd_search_advanced(
 
                 match api_res {
                     Ok(search_result) => {
-                        for item in search_result.page_items {
-                            if media_matches_filter(
-                                &item,
-                                &title_filter,
-                                &description_filter,
-                                &media_type_filter,
-                                &tags_filter,
-                                &tags_filter_mode,
-                            ) {
+                        let page_matches: Vec<MediaListItem> = search_result
+                            .page_items
+                            .into_iter()
+                            .filter(|item| {
+                                media_matches_filter(
+                                    item,
+                                    &title_filter,
+                                    &description_filter,
+                                    &media_type_filter,
+                                    &tags_filter,
+                                    &tags_filter_mode,
+                                    &search_query,
+                                )
+                            })
+                            .collect();
+
+                        let page_matches = filter_media_by_metadata(
+                            &global_opts,
+                            &vault_url,
+                            logout_after_operation,
+                            &search_query,
+                            page_matches,
+                        )
+                        .await;
+
+                        if let Some(stream) = &mut results_stream {
+                            if !sorting {
+                                stream.write_items(&page_matches);
+                            }
+
+                            if keep_results {
+                                advanced_search_results.extend(page_matches);
+                            }
+
+                            let found = if sorting {
+                                advanced_search_results.len() as u64
+                            } else {
+                                stream.count()
+                            };
+
+                            print_search_progress(
+                                search_result.scanned,
+                                search_result.total_count,
+                                found,
+                            );
+                        } else {
+                            for item in page_matches {
                                 advanced_search_results.push(item);
 
                                 if advanced_search_results.len() as u32 >= limit_param {
@@ -385,6 +588,51 @@ pub async fn run_cmd_search_advanced(
         }
     }
 
+    // Sort results
+
+    if sorting {
+        sort_media_results(
+            &global_opts,
+            &vault_url,
+            logout_after_operation,
+            &media_sort,
+            &mut advanced_search_results,
+        )
+        .await;
+
+        if let Some(stream) = &mut results_stream {
+            stream.write_items(&advanced_search_results);
+        }
+    }
+
+    // Export results
+
+    if let Some(export_folder) = export_html {
+        export_search_results_html(
+            &global_opts,
+            &vault_url,
+            logout_after_operation,
+            &export_folder,
+            &advanced_search_results,
+        )
+        .await;
+    }
+
+    // Print playlist
+
+    if let Some(playlist) = &playlist_options {
+        print_media_playlist(
+            &global_opts,
+            &vault_url,
+            logout_after_operation,
+            playlist,
+            &advanced_search_results,
+        )
+        .await;
+
+        return;
+    }
+
     // Print results
 
     if logout_after_operation {
@@ -398,6 +646,18 @@ pub async fn run_cmd_search_advanced(
         }
     }
 
+    if all {
+        return;
+    }
+
+    if ids {
+        for item in advanced_search_results {
+            println!("{}", item.id);
+        }
+
+        return;
+    }
+
     let items_count = advanced_search_results.len();
 
     println!("items retrieved: {items_count}");
@@ -470,20 +730,94 @@ pub async fn run_cmd_search_advanced(
     }
 }
 
+/// Exports the search results as a HTML gallery
+async fn export_search_results_html(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    logout_after_operation: bool,
+    folder: &str,
+    results: &[MediaListItem],
+) {
+    if let Err(e) = tokio::fs::create_dir_all(folder).await {
+        let e_str = e.to_string();
+        eprintln!("Could not create the folder {folder}. Error: {e_str}");
+        if logout_after_operation {
+            let logout_res = do_logout(global_opts, vault_url).await;
+
+            match logout_res {
+                Ok(_) => {}
+                Err(_) => {
+                    process::exit(1);
+                }
+            }
+        }
+        process::exit(1);
+    }
+
+    let mut export_output = ExportOutput::Folder(folder.to_string());
+
+    let media_folders: Vec<String> = results
+        .iter()
+        .map(|m| "media_".to_string() + &m.id.to_string())
+        .collect();
+
+    for (item, media_folder) in results.iter().zip(media_folders.iter()) {
+        let export_res = export_media(
+            global_opts,
+            vault_url,
+            item.id,
+            &mut export_output,
+            media_folder,
+        )
+        .await;
+
+        if export_res == MediaExportResult::Failed {
+            if logout_after_operation {
+                let logout_res = do_logout(global_opts, vault_url).await;
+
+                match logout_res {
+                    Ok(_) => {}
+                    Err(_) => {
+                        process::exit(1);
+                    }
+                }
+            }
+            process::exit(1);
+        }
+    }
+
+    write_export_html_gallery(
+        global_opts,
+        vault_url,
+        logout_after_operation,
+        folder,
+        "Search results",
+        &media_folders,
+    )
+    .await;
+
+    eprintln!("Successfully exported the search results into {folder}");
+}
+
 pub fn media_matches_filter(
     media: &MediaListItem,
-    title: &str,
-    description: &str,
+    title_filter: &Option<TextFilter>,
+    description_filter: &Option<TextFilter>,
     media_type_filter: &Option<MediaType>,
     tags_filter: &Option<Vec<u64>>,
     tags_filter_mode: &TagSearchMode,
+    search_query: &Option<SearchQuery>,
 ) -> bool {
-    if !title.is_empty() && !media.title.contains(title) {
-        return false;
+    if let Some(f) = title_filter {
+        if !f.matches(&media.title) {
+            return false;
+        }
     }
 
-    if !description.is_empty() && !media.description.contains(description) {
-        return false;
+    if let Some(f) = description_filter {
+        if !f.matches(&media.description) {
+            return false;
+        }
     }
 
     if let Some(t) = media_type_filter {
@@ -533,5 +867,65 @@ pub fn media_matches_filter(
         }
     }
 
+    // Terms requiring the metadata are checked later, by filter_media_by_metadata
+    if let Some(q) = search_query {
+        if q.evaluate(media, None) == Some(false) {
+            return false;
+        }
+    }
+
     true
 }
+
+/// Filters a list of media assets using the query terms requiring the media metadata
+/// The metadata is only fetched for the media assets the query cannot discard or accept without it
+/// Media assets deleted in the meantime are discarded
+pub async fn filter_media_by_metadata(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    logout_after_operation: bool,
+    search_query: &Option<SearchQuery>,
+    media_list: Vec<MediaListItem>,
+) -> Vec<MediaListItem> {
+    let query = match search_query {
+        Some(q) => {
+            if !q.requires_metadata() {
+                return media_list;
+            }
+
+            q
+        }
+        None => {
+            return media_list;
+        }
+    };
+
+    let mut pending: Vec<u64> = Vec::new();
+    let mut matched: Vec<bool> = vec![false; media_list.len()];
+
+    for (i, media) in media_list.iter().enumerate() {
+        match query.evaluate(media, None) {
+            Some(r) => {
+                matched[i] = r;
+            }
+            None => {
+                pending.push(media.id);
+            }
+        }
+    }
+
+    let metadata_map =
+        fetch_media_metadata_list(global_opts, vault_url, logout_after_operation, &pending).await;
+
+    for (i, media) in media_list.iter().enumerate() {
+        if let Some(metadata) = metadata_map.get(&media.id) {
+            matched[i] = query.evaluate(media, Some(metadata)) == Some(true);
+        }
+    }
+
+    media_list
+        .into_iter()
+        .zip(matched)
+        .filter_map(|(media, m)| if m { Some(media) } else { None })
+        .collect()
+}
@@ -2,8 +2,12 @@
 
 use std::process;
 
-use crate::api::api_call_search;
-use crate::tools::{render_media_duration, to_csv_string};
+use crate::api::{api_call_search, MAX_SEARCH_PAGE_LIMIT};
+use crate::models::MediaListItem;
+use crate::tools::{
+    get_media_sort, get_playlist_options, get_search_results_format, print_search_progress, render_media_duration,
+    to_csv_string, SearchResultsStream,
+};
 use crate::{
     api::api_call_get_tags,
     models::{tags_map_from_list, tags_names_from_ids},
@@ -13,10 +17,15 @@ use crate::{
     },
 };
 
-use super::{get_vault_url, logout::do_logout, print_request_error, CommandGlobalOptions};
+use super::{
+    get_vault_url, logout::do_logout, media_metadata_fetch::sort_media_results,
+    media_playlist::print_media_playlist,
+    print_request_error, CommandGlobalOptions,
+};
 
 const DEFAULT_PAGE_SIZE: u32 = 10;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn run_cmd_search_basic(
     global_opts: CommandGlobalOptions,
     page: Option<u32>,
@@ -25,7 +34,29 @@ pub async fn run_cmd_search_basic(
     reverse: bool,
     extended: bool,
     csv: bool,
+    all: bool,
+    jsonl: bool,
+    sort: Option<String>,
+    desc: bool,
+    format: Option<String>,
+    resolution: Option<String>,
 ) {
+    let media_sort = match get_media_sort(sort, desc) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
+
+    let playlist_options = match get_playlist_options(format, resolution) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
+
     let url_parse_res = parse_vault_uri(get_vault_url(&global_opts.vault_url));
 
     if url_parse_res.is_err() {
@@ -103,6 +134,94 @@ pub async fn run_cmd_search_basic(
         }
     }
 
+    // Stream all the pages
+
+    if all {
+        let all_page_size = page_size.unwrap_or(MAX_SEARCH_PAGE_LIMIT as u32);
+        let mut results_stream =
+            SearchResultsStream::new(get_search_results_format(csv, jsonl, false), extended, &tags);
+        let mut page: u32 = 0;
+        let mut scanned: u64 = 0;
+        let mut finished = false;
+
+        // When sorting, the results can only be printed at the end
+        let mut sorted_results: Vec<MediaListItem> = Vec::new();
+
+        while !finished {
+            let api_res = api_call_search(
+                &vault_url,
+                tag_param.clone(),
+                reverse,
+                page,
+                all_page_size,
+                global_opts.debug,
+            )
+            .await;
+
+            match api_res {
+                Ok(search_result) => {
+                    scanned += search_result.page_items.len() as u64;
+
+                    if media_sort.is_some() {
+                        sorted_results.extend(search_result.page_items.iter().cloned());
+                    } else {
+                        results_stream.write_items(&search_result.page_items);
+                    }
+
+                    print_search_progress(
+                        scanned,
+                        search_result.total_count,
+                        results_stream.count() + sorted_results.len() as u64,
+                    );
+
+                    page += 1;
+                    finished =
+                        search_result.page_items.is_empty() || page >= search_result.page_count;
+                }
+                Err(e) => {
+                    print_request_error(e);
+                    if logout_after_operation {
+                        let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                        match logout_res {
+                            Ok(_) => {}
+                            Err(_) => {
+                                process::exit(1);
+                            }
+                        }
+                    }
+                    process::exit(1);
+                }
+            }
+        }
+
+        if media_sort.is_some() {
+            sort_media_results(
+                &global_opts,
+                &vault_url,
+                logout_after_operation,
+                &media_sort,
+                &mut sorted_results,
+            )
+            .await;
+
+            results_stream.write_items(&sorted_results);
+        }
+
+        if logout_after_operation {
+            let logout_res = do_logout(&global_opts, &vault_url).await;
+
+            match logout_res {
+                Ok(_) => {}
+                Err(_) => {
+                    process::exit(1);
+                }
+            }
+        }
+
+        return;
+    }
+
     // Call API
 
     let api_res = api_call_search(
@@ -116,7 +235,29 @@ pub async fn run_cmd_search_basic(
     .await;
 
     match api_res {
-        Ok(search_result) => {
+        Ok(mut search_result) => {
+            sort_media_results(
+                &global_opts,
+                &vault_url,
+                logout_after_operation,
+                &media_sort,
+                &mut search_result.page_items,
+            )
+            .await;
+
+            if let Some(playlist) = &playlist_options {
+                print_media_playlist(
+                    &global_opts,
+                    &vault_url,
+                    logout_after_operation,
+                    playlist,
+                    &search_result.page_items,
+                )
+                .await;
+
+                return;
+            }
+
             if logout_after_operation {
                 let logout_res = do_logout(&global_opts, &vault_url).await;
 
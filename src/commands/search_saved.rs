@@ -0,0 +1,336 @@
+// Saved searches
+
+use std::process;
+
+use clap::Subcommand;
+
+use crate::{
+    models::SavedSearch,
+    tools::{
+        ask_user, get_saved_search, print_table, read_local_config, to_csv_string,
+        write_local_config,
+    },
+};
+
+use super::{search_advanced::run_cmd_search_advanced, CommandGlobalOptions};
+
+#[derive(Subcommand)]
+pub enum SearchCommand {
+    /// Saves advanced search criteria with a name, in the local configuration. Saved searches can be run later, or used as the filter of batch, sync, adv-search and the export commands.
+    Save {
+        /// Name of the saved search
+        name: String,
+
+        /// Filter by title
+        #[arg(short = 'q', long)]
+        title: Option<String>,
+
+        /// Filter by description.
+        #[arg(short, long)]
+        description: Option<String>,
+
+        /// Filter by media type. Can be: video, audio or image
+        #[arg(short = 'k', long)]
+        media_type: Option<String>,
+
+        /// Filter by tags. Expected a list of tag names, separated by spaces.
+        #[arg(short, long)]
+        tags: Option<String>,
+
+        /// Tag filtering mode. Can be: all, any, none or untagged
+        #[arg(short = 'm', long)]
+        tags_mode: Option<String>,
+
+        /// Filter by album. Expected an album ID, like: #1
+        #[arg(short, long)]
+        album: Option<String>,
+
+        /// Filter with a search query. Example: type:video tag:travel -tag:draft title:"beach" duration>10m
+        #[arg(long)]
+        query: Option<String>,
+
+        /// Match the title and description filters as case-insensitive regular expressions
+        #[arg(long, conflicts_with = "fuzzy")]
+        regex: bool,
+
+        /// Match the title and description filters ignoring accents and repeated spaces
+        #[arg(long)]
+        fuzzy: bool,
+    },
+
+    /// Runs a saved search
+    Run {
+        /// Name of the saved search
+        name: String,
+
+        /// Limit on the number of results to get. 25 by default.
+        #[arg(short, long)]
+        limit: Option<u32>,
+
+        /// Media id to use as a stating point for the scanning process
+        #[arg(short, long)]
+        start_from: Option<String>,
+
+        /// Reverses results sorting. By default newest results are first. With this option, oldest results are first.
+        #[arg(short, long)]
+        reverse: bool,
+
+        /// Extended version of the results table
+        #[arg(short, long)]
+        extended: bool,
+
+        /// CSV format
+        #[arg(short, long)]
+        csv: bool,
+
+        /// Scan the entire vault, printing the results as they arrive. The progress is printed into the standard error.
+        #[arg(long, conflicts_with = "limit")]
+        all: bool,
+
+        /// JSON Lines format, one result per line. Requires --all.
+        #[arg(long, requires = "all")]
+        jsonl: bool,
+
+        /// Print only the IDs of the results, one per line. Can be used as input for batch --ids-from.
+        #[arg(long, conflicts_with_all = ["csv", "extended", "jsonl", "format"])]
+        ids: bool,
+
+        /// Sort the results on the client side. Can be: title, duration, id, type or upload
+        #[arg(long)]
+        sort: Option<String>,
+
+        /// Sort in descending order. Requires --sort.
+        #[arg(long, requires = "sort")]
+        desc: bool,
+
+        /// Print a playlist of the results instead, with links to the media assets. Can be: m3u or xspf
+        #[arg(long, conflicts_with_all = ["csv", "all"])]
+        format: Option<String>,
+
+        /// Preferred resolutions for the playlist, in order, separated by commas. Example: 720p,480p. The original is used if none of them is available. Requires --format.
+        #[arg(long, requires = "format")]
+        resolution: Option<String>,
+
+        /// Export the results into a folder, as a browsable offline HTML gallery
+        #[arg(long)]
+        export_html: Option<String>,
+    },
+
+    /// Lists the saved searches
+    #[clap(alias("ls"))]
+    List {
+        /// CSV format
+        #[arg(short, long)]
+        csv: bool,
+    },
+
+    /// Deletes a saved search
+    #[clap(alias("rm"))]
+    Delete {
+        /// Name of the saved search
+        name: String,
+    },
+}
+
+pub async fn run_search_cmd(global_opts: CommandGlobalOptions, cmd: SearchCommand) {
+    match cmd {
+        SearchCommand::Save {
+            name,
+            title,
+            description,
+            media_type,
+            tags,
+            tags_mode,
+            album,
+            query,
+            regex,
+            fuzzy,
+        } => {
+            run_cmd_search_save(
+                global_opts,
+                name,
+                SavedSearch {
+                    title,
+                    description,
+                    media_type,
+                    tags,
+                    tags_mode,
+                    album,
+                    query,
+                    regex,
+                    fuzzy,
+                },
+            )
+            .await;
+        }
+        SearchCommand::Run {
+            name,
+            limit,
+            start_from,
+            reverse,
+            extended,
+            csv,
+            all,
+            jsonl,
+            ids,
+            sort,
+            desc,
+            format,
+            resolution,
+            export_html,
+        } => {
+            let saved_search = load_saved_search(&name);
+
+            run_cmd_search_advanced(
+                global_opts,
+                saved_search.title,
+                saved_search.description,
+                saved_search.media_type,
+                saved_search.tags,
+                saved_search.tags_mode,
+                saved_search.album,
+                saved_search.query,
+                saved_search.regex,
+                saved_search.fuzzy,
+                limit,
+                start_from,
+                reverse,
+                extended,
+                csv,
+                all,
+                jsonl,
+                ids,
+                sort,
+                desc,
+                format,
+                resolution,
+                export_html,
+            )
+            .await;
+        }
+        SearchCommand::List { csv } => {
+            run_cmd_search_list(csv);
+        }
+        SearchCommand::Delete { name } => {
+            run_cmd_search_delete(global_opts, name).await;
+        }
+    }
+}
+
+/// Loads a saved search, exiting the process if it cannot be found
+pub fn load_saved_search(name: &str) -> SavedSearch {
+    match get_saved_search(name) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    }
+}
+
+pub async fn run_cmd_search_save(
+    global_opts: CommandGlobalOptions,
+    name: String,
+    saved_search: SavedSearch,
+) {
+    if name.trim().is_empty() {
+        eprintln!("The name of the saved search cannot be empty.");
+        process::exit(1);
+    }
+
+    if saved_search.is_empty() {
+        eprintln!("Error: You must specify at least one filter to save.");
+        process::exit(1);
+    }
+
+    let mut config = match read_local_config() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
+
+    if config.saved_searches.contains_key(&name) && !global_opts.auto_confirm {
+        eprintln!("The saved search {name} already exists");
+        let confirmation = ask_user("Do you want to overwrite it? y/n: ")
+            .await
+            .unwrap_or("".to_string());
+
+        if confirmation.to_lowercase() != "y" {
+            process::exit(1);
+        }
+    }
+
+    config.saved_searches.insert(name.clone(), saved_search);
+
+    if let Err(e) = write_local_config(&config) {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+
+    eprintln!("Successfully saved search: {name}");
+}
+
+pub fn run_cmd_search_list(csv: bool) {
+    let config = match read_local_config() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
+
+    if csv {
+        println!("\"Name\",\"Criteria\"");
+
+        for (name, saved_search) in config.saved_searches.iter() {
+            let row_name = to_csv_string(name);
+            let row_criteria = to_csv_string(&saved_search.to_options_string());
+            println!("{row_name},{row_criteria}");
+        }
+    } else {
+        let table_head: Vec<String> = vec!["Name".to_string(), "Criteria".to_string()];
+
+        let table_body: Vec<Vec<String>> = config
+            .saved_searches
+            .iter()
+            .map(|(name, saved_search)| vec![name.clone(), saved_search.to_options_string()])
+            .collect();
+
+        print_table(&table_head, &table_body, false);
+    }
+}
+
+pub async fn run_cmd_search_delete(global_opts: CommandGlobalOptions, name: String) {
+    let mut config = match read_local_config() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
+
+    if !config.saved_searches.contains_key(&name) {
+        eprintln!("Could not find a saved search with name: {name}");
+        process::exit(1);
+    }
+
+    if !global_opts.auto_confirm {
+        eprintln!("Are you sure you want to delete the saved search {name}?");
+        let confirmation = ask_user("Continue? y/n: ").await.unwrap_or("".to_string());
+
+        if confirmation.to_lowercase() != "y" {
+            process::exit(1);
+        }
+    }
+
+    config.saved_searches.remove(&name);
+
+    if let Err(e) = write_local_config(&config) {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+
+    eprintln!("Successfully deleted saved search: {name}");
+}
@@ -0,0 +1,437 @@
+// Vault synchronization command
+
+use std::process;
+
+use crate::{
+    api::api_call_get_media,
+    commands::{
+        batch_operation::{collect_batch_media, BatchMediaFilter},
+        logout::do_logout,
+        media_export::{export_media, MediaExportResult},
+        media_import::{finalize_media_import, read_media_import_metadata, upload_media_import},
+        search_saved::load_saved_search,
+    },
+    models::VaultSyncMapping,
+    tools::{
+        ask_user, ensure_login, get_temp_folder_path, identifier_to_string, parse_vault_uri,
+        remove_temp_folder, ExportOutput, RequestError, VaultURI,
+    },
+};
+
+use super::{print_request_error, CommandGlobalOptions};
+
+#[allow(clippy::too_many_arguments)]
+pub async fn run_cmd_sync(
+    global_opts: CommandGlobalOptions,
+    from: String,
+    to: String,
+    title: Option<String>,
+    description: Option<String>,
+    media_type: Option<String>,
+    tags: Option<String>,
+    tags_mode: Option<String>,
+    album: Option<String>,
+    search: Option<String>,
+    everything: bool,
+    mapping: Option<String>,
+) {
+    let mut filter = BatchMediaFilter {
+        title,
+        description,
+        media_type,
+        tags,
+        tags_mode,
+        album,
+        query: None,
+        regex: false,
+        fuzzy: false,
+        ids: None,
+        everything,
+    };
+
+    if let Some(search_name) = search {
+        filter = filter.with_saved_search(load_saved_search(&search_name));
+    }
+
+    // Source vault
+
+    let mut source_url = parse_sync_vault_uri(&from);
+
+    let source_logout = source_url.is_login();
+    let source_login_result = ensure_login(&source_url, &None, global_opts.debug).await;
+
+    if source_login_result.is_err() {
+        process::exit(1);
+    }
+
+    source_url = source_login_result.unwrap();
+
+    let mut source_opts = global_opts.clone();
+    source_opts.vault_url = Some(source_url.to_url_string());
+
+    // Find the media assets to synchronize
+
+    let mut media_list =
+        collect_batch_media(&source_opts, &source_url, source_logout, filter).await;
+
+    // Copy the oldest media first, so the target vault keeps the same order
+    media_list.reverse();
+
+    // Target vault
+
+    let mut target_url = parse_sync_vault_uri(&to);
+
+    let target_logout = target_url.is_login();
+    let target_login_result = ensure_login(&target_url, &None, global_opts.debug).await;
+
+    if target_login_result.is_err() {
+        logout_sync_vault(&global_opts, &source_url, source_logout).await;
+        process::exit(1);
+    }
+
+    target_url = target_login_result.unwrap();
+
+    let mut target_opts = global_opts.clone();
+    target_opts.vault_url = Some(target_url.to_url_string());
+
+    // Mapping
+
+    let mapping_file: String = match mapping {
+        Some(m) => m,
+        None => "sync_mapping.json".to_string(),
+    };
+
+    let mut sync_mapping = VaultSyncMapping {
+        source: source_url.to_base_url(),
+        target: target_url.to_base_url(),
+        media: Default::default(),
+        pending_media: Default::default(),
+    };
+
+    if std::path::Path::new(&mapping_file).exists() {
+        let mapping_file_res = tokio::fs::read_to_string(&mapping_file).await;
+
+        let parsed_mapping: Result<VaultSyncMapping, String> = match mapping_file_res {
+            Ok(s) => serde_json::from_str(&s).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+
+        match parsed_mapping {
+            Ok(m) => {
+                if m.source != sync_mapping.source || m.target != sync_mapping.target {
+                    let m_source = &m.source;
+                    let m_target = &m.target;
+                    eprintln!("The mapping file {mapping_file} belongs to a different pair of vaults: {m_source} -> {m_target}");
+                    eprintln!("Use the --mapping option to specify a different mapping file.");
+                    logout_sync_vaults(
+                        &global_opts,
+                        &source_url,
+                        source_logout,
+                        &target_url,
+                        target_logout,
+                    )
+                    .await;
+                    process::exit(1);
+                }
+
+                sync_mapping.media = m.media;
+                sync_mapping.pending_media = m.pending_media;
+            }
+            Err(e_str) => {
+                eprintln!("Could not read mapping file: {mapping_file}. Error: {e_str}");
+                logout_sync_vaults(
+                    &global_opts,
+                    &source_url,
+                    source_logout,
+                    &target_url,
+                    target_logout,
+                )
+                .await;
+                process::exit(1);
+            }
+        }
+    }
+
+    let n_total = media_list.len();
+    let n_mapped = media_list
+        .iter()
+        .filter(|m| sync_mapping.media.contains_key(&m.id.to_string()))
+        .count();
+
+    if n_total == 0 {
+        logout_sync_vaults(
+            &global_opts,
+            &source_url,
+            source_logout,
+            &target_url,
+            target_logout,
+        )
+        .await;
+        eprintln!("No media assets found matching the filters.");
+        return;
+    }
+
+    // Ask confirmation
+
+    if !global_opts.auto_confirm {
+        let source_str = source_url.to_base_url();
+        let target_str = target_url.to_base_url();
+        eprintln!("Synchronizing {n_total} media assets from {source_str} into {target_str}");
+        if n_mapped > 0 {
+            eprintln!("{n_mapped} of them were already copied according to {mapping_file}");
+        }
+        let confirmation = ask_user("Continue? y/n: ").await.unwrap_or("".to_string());
+
+        if confirmation.to_lowercase() != "y" {
+            logout_sync_vaults(
+                &global_opts,
+                &source_url,
+                source_logout,
+                &target_url,
+                target_logout,
+            )
+            .await;
+            process::exit(1);
+        }
+    }
+
+    // Copy media
+
+    let mut n_copied: usize = 0;
+    let mut n_skipped: usize = 0;
+    let mut n_failed: usize = 0;
+
+    for (i, media_item) in media_list.iter().enumerate() {
+        let n_done = i + 1;
+        let media_id_str = identifier_to_string(media_item.id);
+        let media_key = media_item.id.to_string();
+
+        if let Some(target_media_id) = sync_mapping.media.get(&media_key).copied() {
+            let target_media_id_str = identifier_to_string(target_media_id);
+            let target_media_res =
+                api_call_get_media(&target_url, target_media_id, global_opts.debug).await;
+
+            match target_media_res {
+                Ok(_) => {
+                    if !sync_mapping.pending_media.contains(&media_key) {
+                        eprintln!("[{n_done}/{n_total}] Skipped {media_id_str}, already copied as {target_media_id_str}");
+                        n_skipped += 1;
+                        continue;
+                    }
+
+                    eprintln!("[{n_done}/{n_total}] {media_id_str} was uploaded as {target_media_id_str}, but its metadata was not copied. Resuming...");
+                }
+                Err(RequestError::StatusCode(status)) | Err(RequestError::Api { status, .. })
+                    if status == 404 =>
+                {
+                    eprintln!("[{n_done}/{n_total}] {media_id_str} was copied as {target_media_id_str}, but it no longer exists in the target vault. Copying it again...");
+
+                    sync_mapping.media.remove(&media_key);
+                    sync_mapping.pending_media.remove(&media_key);
+                }
+                Err(e) => {
+                    print_request_error(e);
+                    logout_sync_vaults(
+                        &global_opts,
+                        &source_url,
+                        source_logout,
+                        &target_url,
+                        target_logout,
+                    )
+                    .await;
+                    process::exit(1);
+                }
+            }
+        } else {
+            eprintln!("[{n_done}/{n_total}] Copying {media_id_str}...");
+        }
+
+        // The media is staged in a temporary folder, removed right after the import
+
+        let temp_folder = get_temp_folder_path("sync");
+
+        let copy_res = copy_sync_media(
+            &global_opts,
+            &source_url,
+            &target_url,
+            media_item.id,
+            &temp_folder,
+            &mapping_file,
+            &mut sync_mapping,
+        )
+        .await;
+
+        remove_temp_folder(&temp_folder).await;
+
+        match copy_res {
+            Ok(Some(target_media_id)) => {
+                let target_media_id_str = identifier_to_string(target_media_id);
+                eprintln!("[{n_done}/{n_total}] Copied {media_id_str} as {target_media_id_str}");
+
+                n_copied += 1;
+            }
+            Ok(None) => {
+                eprintln!("[{n_done}/{n_total}] Could not copy {media_id_str}");
+                n_failed += 1;
+            }
+            Err(e_str) => {
+                eprintln!("Could not write mapping file: {mapping_file}. Error: {e_str}");
+                logout_sync_vaults(
+                    &global_opts,
+                    &source_url,
+                    source_logout,
+                    &target_url,
+                    target_logout,
+                )
+                .await;
+                process::exit(1);
+            }
+        }
+    }
+
+    // Done
+
+    logout_sync_vaults(
+        &global_opts,
+        &source_url,
+        source_logout,
+        &target_url,
+        target_logout,
+    )
+    .await;
+
+    eprintln!("Done. Copied: {n_copied}, Skipped: {n_skipped}, Failed: {n_failed}");
+
+    if n_failed > 0 {
+        eprintln!("Run the same command again to retry the failed media assets.");
+        process::exit(1);
+    }
+}
+
+/// Copies a media asset into the target vault, staging it in a temporary folder
+/// The mapping is written as soon as the media asset is uploaded,
+/// so an interrupted copy only needs to finish the metadata.
+/// Returns the ID in the target vault, or None if the copy failed.
+/// Fails only if the mapping file could not be written.
+async fn copy_sync_media(
+    global_opts: &CommandGlobalOptions,
+    source_url: &VaultURI,
+    target_url: &VaultURI,
+    media_id: u64,
+    temp_folder: &str,
+    mapping_file: &str,
+    sync_mapping: &mut VaultSyncMapping,
+) -> Result<Option<u64>, String> {
+    let media_key = media_id.to_string();
+
+    let mut export_output = ExportOutput::Folder(temp_folder.to_string());
+
+    let export_res = export_media(global_opts, source_url, media_id, &mut export_output, "").await;
+
+    if export_res != MediaExportResult::Exported {
+        return Ok(None);
+    }
+
+    let media_metadata = match read_media_import_metadata(temp_folder).await {
+        Ok(m) => m,
+        Err(e_str) => {
+            eprintln!("Could not read metadata file. Error: {e_str}");
+            return Ok(None);
+        }
+    };
+
+    let target_media_id: u64 = match sync_mapping.media.get(&media_key) {
+        Some(id) if sync_mapping.pending_media.contains(&media_key) => *id,
+        _ => {
+            let upload_res =
+                upload_media_import(global_opts, target_url, temp_folder, &media_metadata, None)
+                    .await;
+
+            match upload_res {
+                Some(id) => {
+                    sync_mapping.media.insert(media_key.clone(), id);
+                    sync_mapping.pending_media.insert(media_key.clone());
+                    write_sync_mapping(mapping_file, sync_mapping).await?;
+                    id
+                }
+                None => {
+                    return Ok(None);
+                }
+            }
+        }
+    };
+
+    let finalize_res = finalize_media_import(
+        global_opts,
+        target_url,
+        temp_folder,
+        target_media_id,
+        &media_metadata,
+    )
+    .await;
+
+    if !finalize_res {
+        return Ok(None);
+    }
+
+    sync_mapping.pending_media.remove(&media_key);
+    write_sync_mapping(mapping_file, sync_mapping).await?;
+
+    Ok(Some(target_media_id))
+}
+
+async fn write_sync_mapping(
+    mapping_file: &str,
+    sync_mapping: &VaultSyncMapping,
+) -> Result<(), String> {
+    let mapping_str = serde_json::to_string(sync_mapping).unwrap();
+
+    tokio::fs::write(mapping_file, mapping_str)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+fn parse_sync_vault_uri(url: &str) -> VaultURI {
+    let url_parse_res = parse_vault_uri(url.to_string());
+
+    match url_parse_res {
+        Ok(u) => u,
+        Err(crate::tools::VaultURIParseError::InvalidProtocol) => {
+            eprintln!("Invalid vault URL provided: {url} | Must be an HTTP or HTTPS URL.");
+            process::exit(1);
+        }
+        Err(crate::tools::VaultURIParseError::URLError(e)) => {
+            let err_msg = e.to_string();
+            eprintln!("Invalid vault URL provided: {err_msg}");
+            process::exit(1);
+        }
+    }
+}
+
+async fn logout_sync_vault(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    logout_after_operation: bool,
+) {
+    if logout_after_operation {
+        let logout_res = do_logout(global_opts, vault_url).await;
+
+        match logout_res {
+            Ok(_) => {}
+            Err(_) => {
+                process::exit(1);
+            }
+        }
+    }
+}
+
+async fn logout_sync_vaults(
+    global_opts: &CommandGlobalOptions,
+    source_url: &VaultURI,
+    source_logout: bool,
+    target_url: &VaultURI,
+    target_logout: bool,
+) {
+    logout_sync_vault(global_opts, source_url, source_logout).await;
+    logout_sync_vault(global_opts, target_url, target_logout).await;
+}
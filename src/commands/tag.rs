@@ -10,6 +10,7 @@ use crate::{
     commands::logout::do_logout,
     models::{
         parse_tag_name, tags_map_from_list, tags_reverse_map_from_list, AddTagBody, RemoveTagBody,
+        UndoChange,
     },
     tools::{
         ensure_login, identifier_to_string, is_identifier, parse_identifier, parse_vault_uri,
@@ -17,7 +18,7 @@ use crate::{
     },
 };
 
-use super::{get_vault_url, print_request_error, CommandGlobalOptions};
+use super::{get_vault_url, print_request_error, undo::record_undo_entry, CommandGlobalOptions};
 
 #[derive(Subcommand)]
 pub enum TagCommand {
@@ -123,7 +124,7 @@ pub async fn run_cmd_list_tags(
             if alphabetically {
                 tags.sort_by(|a, b| a.name.cmp(&b.name));
             } else {
-                tags.sort_by(|a, b| a.id.cmp(&b.id));
+                tags.sort_by_key(|a| a.id);
             }
 
             let total = tags.len();
@@ -201,6 +202,7 @@ pub async fn run_cmd_tag_add(global_opts: CommandGlobalOptions, tag: String, med
     let media_id_res = parse_identifier(&media);
 
     let media_id_param: u64;
+    let media_tags: Vec<u64>;
 
     match media_id_res {
         Ok(media_id) => {
@@ -208,8 +210,9 @@ pub async fn run_cmd_tag_add(global_opts: CommandGlobalOptions, tag: String, med
                 api_call_get_media(&vault_url, media_id, global_opts.debug).await;
 
             match media_api_res {
-                Ok(_) => {
+                Ok(metadata) => {
                     media_id_param = media_id;
+                    media_tags = metadata.tags;
                 }
                 Err(e) => {
                     match e {
@@ -287,6 +290,7 @@ pub async fn run_cmd_tag_add(global_opts: CommandGlobalOptions, tag: String, med
     let tags_vec = tags_res.unwrap();
 
     let tags_map = tags_map_from_list(&tags_vec);
+    let tags_reverse_map = tags_reverse_map_from_list(&tags_vec);
 
     // Tag
 
@@ -311,6 +315,26 @@ pub async fn run_cmd_tag_add(global_opts: CommandGlobalOptions, tag: String, med
         tag_param = tag;
     }
 
+    // Undo journal
+
+    let parsed_tag_param = parse_tag_name(&tag_param);
+
+    let has_tag = match tags_reverse_map.get(&parsed_tag_param) {
+        Some(tag_id) => media_tags.contains(tag_id),
+        None => false,
+    };
+
+    let undo_operation = format!("tag add {parsed_tag_param} #{media_id_param}");
+    let mut undo_changes: Vec<UndoChange> = Vec::new();
+
+    if !has_tag {
+        undo_changes.push(UndoChange::MediaTag {
+            media_id: media_id_param,
+            tag: parsed_tag_param,
+            present: false,
+        });
+    }
+
     // Call API
 
     let api_res = api_call_tag_add(
@@ -325,6 +349,8 @@ pub async fn run_cmd_tag_add(global_opts: CommandGlobalOptions, tag: String, med
 
     match api_res {
         Ok(added_tag) => {
+            record_undo_entry(&global_opts, &vault_url, &undo_operation, undo_changes).await;
+
             if logout_after_operation {
                 let logout_res = do_logout(&global_opts, &vault_url).await;
 
@@ -492,6 +518,40 @@ pub async fn run_cmd_tag_remove(
     let default_tag_name = "???".to_string();
     let tag_name = tags_map.get(&tag_param).unwrap_or(&default_tag_name);
 
+    // Undo journal
+
+    let mut undo_changes: Vec<UndoChange> = Vec::new();
+
+    if global_opts.undo_log {
+        let media_api_res = api_call_get_media(&vault_url, media_id_param, global_opts.debug).await;
+
+        match media_api_res {
+            Ok(metadata) => {
+                if metadata.tags.contains(&tag_param) {
+                    undo_changes.push(UndoChange::MediaTag {
+                        media_id: media_id_param,
+                        tag: tag_name.clone(),
+                        present: true,
+                    });
+                }
+            }
+            Err(e) => {
+                print_request_error(e);
+                if logout_after_operation {
+                    let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                process::exit(1);
+            }
+        }
+    }
+
     // Call API
 
     let api_res = api_call_tag_remove(
@@ -506,6 +566,14 @@ pub async fn run_cmd_tag_remove(
 
     match api_res {
         Ok(_) => {
+            record_undo_entry(
+                &global_opts,
+                &vault_url,
+                &format!("tag remove {tag_name} #{media_id_param}"),
+                undo_changes,
+            )
+            .await;
+
             if logout_after_operation {
                 let logout_res = do_logout(&global_opts, &vault_url).await;
 
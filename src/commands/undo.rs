@@ -0,0 +1,579 @@
+// Undo command, to revert operations recorded in the local undo journal
+
+use std::{
+    collections::{HashMap, HashSet},
+    process,
+};
+
+use crate::{
+    api::{
+        api_call_album_add_media, api_call_album_move_media, api_call_album_remove_media,
+        api_call_get_album, api_call_get_tags, api_call_media_change_description,
+        api_call_media_change_title, api_call_tag_add, api_call_tag_remove,
+    },
+    models::{
+        tags_reverse_map_from_list, AddTagBody, AlbumMediaBody, AlbumMoveMediaBody, MediaMetadata,
+        MediaUpdateDescriptionBody, MediaUpdateTitleBody, RemoveTagBody, UndoChange,
+    },
+    tools::{
+        append_undo_entry, ask_user, ensure_login, format_date, identifier_to_string,
+        parse_identifier, parse_vault_uri, print_table, read_undo_journal, to_csv_string,
+        write_undo_journal, RequestError, VaultURI,
+    },
+};
+
+use super::{
+    get_vault_url, logout::do_logout, media_metadata_fetch::fetch_media_metadata_list,
+    print_request_error, CommandGlobalOptions,
+};
+
+/// Records the previous state of an operation in the undo journal,
+/// if the --undo-log option is enabled. Must be called after the changes were applied.
+/// If the journal cannot be written, a warning is printed, since the changes cannot be reverted at that point.
+pub async fn record_undo_entry(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    operation: &str,
+    changes: Vec<UndoChange>,
+) {
+    if !global_opts.undo_log || changes.is_empty() {
+        return;
+    }
+
+    match append_undo_entry(&vault_url.to_base_url(), operation, changes) {
+        Ok(entry) => {
+            let entry_id_str = identifier_to_string(entry.id);
+            eprintln!("Recorded undo entry {entry_id_str}. Run the undo command to revert it.");
+        }
+        Err(e) => {
+            eprintln!("Warning: Could not record the operation in the undo journal. Error: {e}");
+        }
+    }
+}
+
+pub fn run_cmd_undo_list(csv: bool) {
+    let entries = match read_undo_journal() {
+        Ok(e) => e,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
+
+    let rows: Vec<Vec<String>> = entries
+        .iter()
+        .rev()
+        .map(|entry| {
+            vec![
+                identifier_to_string(entry.id),
+                format_date(entry.timestamp),
+                entry.operation.clone(),
+                entry.changes.len().to_string(),
+                entry.vault.clone(),
+                if entry.undone {
+                    "Undone".to_string()
+                } else {
+                    "".to_string()
+                },
+            ]
+        })
+        .collect();
+
+    if csv {
+        println!("\"Id\",\"Date\",\"Operation\",\"Changes\",\"Vault\",\"Status\"");
+
+        for row in rows.iter() {
+            let row_str: Vec<String> = row.iter().map(|c| to_csv_string(c)).collect();
+            println!("{}", row_str.join(","));
+        }
+    } else {
+        let table_head: Vec<String> = vec![
+            "Id".to_string(),
+            "Date".to_string(),
+            "Operation".to_string(),
+            "Changes".to_string(),
+            "Vault".to_string(),
+            "Status".to_string(),
+        ];
+
+        print_table(&table_head, &rows, false);
+    }
+}
+
+pub async fn run_cmd_undo(global_opts: CommandGlobalOptions, entry: Option<String>) {
+    let mut entries = match read_undo_journal() {
+        Ok(e) => e,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
+
+    // Find the entry
+
+    let entry_index = match entry {
+        Some(entry_id_str) => {
+            let entry_id = match parse_identifier(&entry_id_str) {
+                Ok(id) => id,
+                Err(_) => {
+                    eprintln!("Invalid undo entry identifier specified.");
+                    process::exit(1);
+                }
+            };
+
+            match entries.iter().position(|e| e.id == entry_id) {
+                Some(i) => i,
+                None => {
+                    eprintln!("Could not find the undo entry: #{entry_id}");
+                    process::exit(1);
+                }
+            }
+        }
+        None => match entries.iter().rposition(|e| !e.undone) {
+            Some(i) => i,
+            None => {
+                eprintln!("There is nothing to undo.");
+                return;
+            }
+        },
+    };
+
+    let undo_entry = entries[entry_index].clone();
+    let entry_id_str = identifier_to_string(undo_entry.id);
+    let operation = undo_entry.operation.clone();
+
+    if undo_entry.undone {
+        eprintln!("The undo entry {entry_id_str} was already reverted.");
+        process::exit(1);
+    }
+
+    let url_parse_res = parse_vault_uri(get_vault_url(&global_opts.vault_url));
+
+    if url_parse_res.is_err() {
+        match url_parse_res.err().unwrap() {
+            crate::tools::VaultURIParseError::InvalidProtocol => {
+                eprintln!("Invalid vault URL provided. Must be an HTTP or HTTPS URL.");
+            }
+            crate::tools::VaultURIParseError::URLError(e) => {
+                let err_msg = e.to_string();
+                eprintln!("Invalid vault URL provided: {err_msg}");
+            }
+        }
+
+        process::exit(1);
+    }
+
+    let mut vault_url = url_parse_res.unwrap();
+
+    if vault_url.to_base_url() != undo_entry.vault {
+        let entry_vault = &undo_entry.vault;
+        eprintln!("The undo entry {entry_id_str} belongs to a different vault: {entry_vault}");
+        process::exit(1);
+    }
+
+    // Ask confirmation
+
+    let n_total = undo_entry.changes.len();
+
+    if !global_opts.auto_confirm {
+        eprintln!("Are you sure you want to revert {entry_id_str} ({operation}), restoring {n_total} changes?");
+        let confirmation = ask_user("Continue? y/n: ").await.unwrap_or("".to_string());
+
+        if confirmation.to_lowercase() != "y" {
+            process::exit(1);
+        }
+    }
+
+    let logout_after_operation = vault_url.is_login();
+    let login_result = ensure_login(&vault_url, &None, global_opts.debug).await;
+
+    if login_result.is_err() {
+        process::exit(1);
+    }
+
+    vault_url = login_result.unwrap();
+
+    // Get the current state
+
+    let tags_res = api_call_get_tags(&vault_url, global_opts.debug).await;
+
+    let mut tags_reverse_map = match tags_res {
+        Ok(tags_vec) => tags_reverse_map_from_list(&tags_vec),
+        Err(e) => {
+            print_request_error(e);
+            if logout_after_operation {
+                let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                match logout_res {
+                    Ok(_) => {}
+                    Err(_) => {
+                        process::exit(1);
+                    }
+                }
+            }
+            process::exit(1);
+        }
+    };
+
+    let mut media_ids: Vec<u64> = Vec::new();
+    let mut album_ids: Vec<u64> = Vec::new();
+    let mut media_ids_set: HashSet<u64> = HashSet::new();
+    let mut album_ids_set: HashSet<u64> = HashSet::new();
+
+    for change in undo_entry.changes.iter() {
+        match change {
+            UndoChange::MediaTag { media_id, .. }
+            | UndoChange::MediaTitle { media_id, .. }
+            | UndoChange::MediaDescription { media_id, .. } => {
+                if media_ids_set.insert(*media_id) {
+                    media_ids.push(*media_id);
+                }
+            }
+            UndoChange::AlbumMedia { album_id, .. } => {
+                if album_ids_set.insert(*album_id) {
+                    album_ids.push(*album_id);
+                }
+            }
+        }
+    }
+
+    let mut media_map =
+        fetch_media_metadata_list(&global_opts, &vault_url, logout_after_operation, &media_ids)
+            .await;
+
+    let mut albums_map: HashMap<u64, Vec<u64>> = HashMap::new();
+
+    for album_id in album_ids {
+        let api_res = api_call_get_album(&vault_url, album_id, global_opts.debug).await;
+
+        match api_res {
+            Ok(album_data) => {
+                albums_map.insert(album_id, album_data.list.iter().map(|m| m.id).collect());
+            }
+            Err(RequestError::StatusCode(status)) | Err(RequestError::Api { status, .. })
+                if status == 404 =>
+            {
+                // Album deleted in the meantime
+            }
+            Err(e) => {
+                print_request_error(e);
+                if logout_after_operation {
+                    let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                process::exit(1);
+            }
+        }
+    }
+
+    // Revert the changes, from the last one to the first one
+
+    let mut n_done: usize = 0;
+    let mut n_failed: usize = 0;
+    let mut missing: HashSet<String> = HashSet::new();
+
+    for change in undo_entry.changes.iter().rev() {
+        n_done += 1;
+
+        let revert_res = revert_undo_change(
+            &vault_url,
+            global_opts.debug,
+            change,
+            &mut media_map,
+            &mut albums_map,
+            &mut tags_reverse_map,
+        )
+        .await;
+
+        match revert_res {
+            Ok(RevertResult::Reverted(line)) => {
+                eprintln!("[{n_done}/{n_total}] {line}");
+            }
+            Ok(RevertResult::Unchanged) => {}
+            Ok(RevertResult::Missing(element)) => {
+                if !missing.contains(&element) {
+                    eprintln!("[{n_done}/{n_total}] Skipped {element}, since it no longer exists");
+                    missing.insert(element);
+                }
+            }
+            Err(e) => {
+                eprintln!("[{n_done}/{n_total}] Could not revert a change");
+                print_request_error(e);
+                n_failed += 1;
+            }
+        }
+    }
+
+    if logout_after_operation {
+        let logout_res = do_logout(&global_opts, &vault_url).await;
+
+        match logout_res {
+            Ok(_) => {}
+            Err(_) => {
+                process::exit(1);
+            }
+        }
+    }
+
+    if n_failed > 0 {
+        eprintln!("Could not revert {n_failed} of {n_total} changes. The undo entry {entry_id_str} was kept, so the command can be run again.");
+        process::exit(1);
+    }
+
+    // Mark the entry as reverted
+
+    entries[entry_index].undone = true;
+
+    if let Err(e) = write_undo_journal(&entries) {
+        eprintln!("Warning: {e}");
+    }
+
+    eprintln!("Successfully reverted {entry_id_str}: {operation}");
+}
+
+/// Result of reverting a single change
+enum RevertResult {
+    /// The previous state was restored
+    Reverted(String),
+
+    /// The current state was already the previous one
+    Unchanged,
+
+    /// The media asset or album does not exist anymore
+    Missing(String),
+}
+
+/// Restores the previous state of a single change
+/// The current state (media, albums and tags) is updated after each change
+async fn revert_undo_change(
+    vault_url: &VaultURI,
+    debug: bool,
+    change: &UndoChange,
+    media_map: &mut HashMap<u64, MediaMetadata>,
+    albums_map: &mut HashMap<u64, Vec<u64>>,
+    tags_reverse_map: &mut HashMap<String, u64>,
+) -> Result<RevertResult, RequestError> {
+    match change {
+        UndoChange::MediaTag {
+            media_id,
+            tag,
+            present,
+        } => {
+            let media_id_str = identifier_to_string(*media_id);
+
+            let metadata = match media_map.get_mut(media_id) {
+                Some(m) => m,
+                None => {
+                    return Ok(RevertResult::Missing(format!("media {media_id_str}")));
+                }
+            };
+
+            let current_tag_id = tags_reverse_map.get(tag).copied();
+
+            let has_tag = match current_tag_id {
+                Some(tag_id) => metadata.tags.contains(&tag_id),
+                None => false,
+            };
+
+            if has_tag == *present {
+                return Ok(RevertResult::Unchanged);
+            }
+
+            let tag_str = to_csv_string(tag);
+
+            if *present {
+                let added_tag = api_call_tag_add(
+                    vault_url,
+                    AddTagBody {
+                        media_id: *media_id,
+                        tag_name: tag.clone(),
+                    },
+                    debug,
+                )
+                .await?;
+
+                metadata.tags.push(added_tag.id);
+                tags_reverse_map.insert(added_tag.name, added_tag.id);
+
+                Ok(RevertResult::Reverted(format!(
+                    "Added tag {tag_str} back to media {media_id_str}"
+                )))
+            } else {
+                let tag_id = current_tag_id.unwrap();
+
+                api_call_tag_remove(
+                    vault_url,
+                    RemoveTagBody {
+                        media_id: *media_id,
+                        tag_id,
+                    },
+                    debug,
+                )
+                .await?;
+
+                metadata.tags.retain(|t| *t != tag_id);
+
+                Ok(RevertResult::Reverted(format!(
+                    "Removed tag {tag_str} from media {media_id_str}"
+                )))
+            }
+        }
+        UndoChange::MediaTitle { media_id, title } => {
+            let media_id_str = identifier_to_string(*media_id);
+
+            let metadata = match media_map.get_mut(media_id) {
+                Some(m) => m,
+                None => {
+                    return Ok(RevertResult::Missing(format!("media {media_id_str}")));
+                }
+            };
+
+            if metadata.title == *title {
+                return Ok(RevertResult::Unchanged);
+            }
+
+            api_call_media_change_title(
+                vault_url,
+                *media_id,
+                MediaUpdateTitleBody {
+                    title: title.clone(),
+                },
+                debug,
+            )
+            .await?;
+
+            metadata.title = title.clone();
+
+            let title_str = to_csv_string(title);
+
+            Ok(RevertResult::Reverted(format!(
+                "Restored the title of media {media_id_str}: {title_str}"
+            )))
+        }
+        UndoChange::MediaDescription {
+            media_id,
+            description,
+        } => {
+            let media_id_str = identifier_to_string(*media_id);
+
+            let metadata = match media_map.get_mut(media_id) {
+                Some(m) => m,
+                None => {
+                    return Ok(RevertResult::Missing(format!("media {media_id_str}")));
+                }
+            };
+
+            if metadata.description == *description {
+                return Ok(RevertResult::Unchanged);
+            }
+
+            api_call_media_change_description(
+                vault_url,
+                *media_id,
+                MediaUpdateDescriptionBody {
+                    description: description.clone(),
+                },
+                debug,
+            )
+            .await?;
+
+            metadata.description = description.clone();
+
+            Ok(RevertResult::Reverted(format!(
+                "Restored the description of media {media_id_str}"
+            )))
+        }
+        UndoChange::AlbumMedia {
+            album_id,
+            media_id,
+            position,
+        } => {
+            let media_id_str = identifier_to_string(*media_id);
+            let album_id_str = identifier_to_string(*album_id);
+
+            let album_list = match albums_map.get_mut(album_id) {
+                Some(l) => l,
+                None => {
+                    return Ok(RevertResult::Missing(format!("album {album_id_str}")));
+                }
+            };
+
+            let current_position = album_list.iter().position(|m| *m == *media_id);
+
+            match (*position, current_position) {
+                (None, None) => Ok(RevertResult::Unchanged),
+                (None, Some(current)) => {
+                    api_call_album_remove_media(
+                        vault_url,
+                        *album_id,
+                        AlbumMediaBody {
+                            media_id: *media_id,
+                        },
+                        debug,
+                    )
+                    .await?;
+
+                    album_list.remove(current);
+
+                    Ok(RevertResult::Reverted(format!(
+                        "Removed media {media_id_str} from album {album_id_str}"
+                    )))
+                }
+                (Some(previous), current) => {
+                    if current == Some(previous) {
+                        return Ok(RevertResult::Unchanged);
+                    }
+
+                    let current = match current {
+                        Some(c) => c,
+                        None => {
+                            api_call_album_add_media(
+                                vault_url,
+                                *album_id,
+                                AlbumMediaBody {
+                                    media_id: *media_id,
+                                },
+                                debug,
+                            )
+                            .await?;
+
+                            album_list.push(*media_id);
+
+                            album_list.len() - 1
+                        }
+                    };
+
+                    let target = previous.min(album_list.len() - 1);
+
+                    if current != target {
+                        api_call_album_move_media(
+                            vault_url,
+                            *album_id,
+                            AlbumMoveMediaBody {
+                                media_id: *media_id,
+                                position: target as u32,
+                            },
+                            debug,
+                        )
+                        .await?;
+
+                        album_list.remove(current);
+                        album_list.insert(target, *media_id);
+                    }
+
+                    let position_str = target + 1;
+
+                    Ok(RevertResult::Reverted(format!(
+                        "Restored media {media_id_str} into position {position_str} of album {album_id_str}"
+                    )))
+                }
+            }
+        }
+    }
+}
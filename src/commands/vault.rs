@@ -0,0 +1,70 @@
+// Vault command
+
+use clap::Subcommand;
+
+use super::{
+    media_export::get_export_encryption, vault_export::run_cmd_export_vault,
+    vault_restore::run_cmd_restore_vault, CommandGlobalOptions,
+};
+
+#[derive(Subcommand)]
+pub enum VaultCommand {
+    /// Exports the entire vault (media assets, albums, tags and configuration) into a folder
+    Export {
+        /// Path to the folder to download the files into
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Write an archive instead of a folder. The format is chosen by the extension (.tar or .zip). Use - to write a tar archive to the standard output.
+        #[arg(long, conflicts_with = "output")]
+        archive: Option<String>,
+
+        /// Encrypt the archive with a passphrase, using the age format. The passphrase is asked for, unless the PMV_ARCHIVE_PASSPHRASE environment variable is set.
+        #[arg(long, requires = "archive")]
+        passphrase: bool,
+
+        /// Encrypt the archive for an age public key (age1...), instead of using a passphrase. Can be specified multiple times.
+        #[arg(long, requires = "archive", conflicts_with = "passphrase")]
+        recipient: Vec<String>,
+
+        /// Only export the media assets matching a saved search. Albums only list the exported media assets.
+        #[arg(short = 'S', long)]
+        search: Option<String>,
+    },
+
+    /// Restores a vault backup, expecting a folder or archive with the same format the export command uses.
+    Restore {
+        /// Path to the folder or archive (tar or zip) to restore. Use - to read a tar archive from the standard input.
+        path: String,
+
+        /// Path to the file to store the mapping of old IDs to new IDs, used to resume an interrupted restore. By default, restore_mapping.json inside the backup folder, or next to the archive.
+        #[arg(short, long)]
+        mapping: Option<String>,
+
+        /// Age identity file to decrypt the archive, if it was encrypted for a public key. For passphrase encrypted archives, the passphrase is asked for, unless the PMV_ARCHIVE_PASSPHRASE environment variable is set.
+        #[arg(long)]
+        identity: Option<String>,
+    },
+}
+
+pub async fn run_vault_cmd(global_opts: CommandGlobalOptions, cmd: VaultCommand) {
+    match cmd {
+        VaultCommand::Export {
+            output,
+            archive,
+            passphrase,
+            recipient,
+            search,
+        } => {
+            let encryption = get_export_encryption(passphrase, recipient).await;
+            run_cmd_export_vault(global_opts, output, archive, encryption, search).await;
+        }
+        VaultCommand::Restore {
+            path,
+            mapping,
+            identity,
+        } => {
+            run_cmd_restore_vault(global_opts, path, mapping, identity).await;
+        }
+    }
+}
@@ -0,0 +1,427 @@
+// Vault export command
+
+use std::{collections::HashSet, process};
+
+use crate::{
+    api::{
+        api_call_get_album, api_call_get_albums, api_call_get_config, api_call_get_tags,
+        api_call_search_advanced, MAX_SEARCH_PAGE_LIMIT,
+    },
+    commands::{
+        batch_operation::{collect_batch_media, BatchMediaFilter},
+        logout::do_logout,
+        media_export::{
+            create_export_output, download_export_asset, export_media, finish_export_output,
+            MediaExportResult,
+        },
+        search_saved::load_saved_search,
+    },
+    models::{VaultAlbumExport, VaultMetadataExport},
+    tools::{
+        ensure_login, get_extension_from_url, identifier_to_string, parse_vault_uri,
+        ArchiveEncryption,
+    },
+};
+
+use super::{get_vault_url, print_request_error, CommandGlobalOptions};
+
+pub async fn run_cmd_export_vault(
+    global_opts: CommandGlobalOptions,
+    output: Option<String>,
+    archive: Option<String>,
+    encryption: Option<ArchiveEncryption>,
+    search: Option<String>,
+) {
+    let search = search.map(|name| load_saved_search(&name));
+
+    let url_parse_res = parse_vault_uri(get_vault_url(&global_opts.vault_url));
+
+    if url_parse_res.is_err() {
+        match url_parse_res.err().unwrap() {
+            crate::tools::VaultURIParseError::InvalidProtocol => {
+                eprintln!("Invalid vault URL provided. Must be an HTTP or HTTPS URL.");
+            }
+            crate::tools::VaultURIParseError::URLError(e) => {
+                let err_msg = e.to_string();
+                eprintln!("Invalid vault URL provided: {err_msg}");
+            }
+        }
+
+        process::exit(1);
+    }
+
+    let mut vault_url = url_parse_res.unwrap();
+
+    let logout_after_operation = vault_url.is_login();
+    let login_result = ensure_login(&vault_url, &None, global_opts.debug).await;
+
+    if login_result.is_err() {
+        process::exit(1);
+    }
+
+    vault_url = login_result.unwrap();
+
+    // Get tags
+
+    let tags_res = api_call_get_tags(&vault_url, global_opts.debug).await;
+
+    if tags_res.is_err() {
+        if logout_after_operation {
+            let logout_res = do_logout(&global_opts, &vault_url).await;
+
+            match logout_res {
+                Ok(_) => {}
+                Err(_) => {
+                    process::exit(1);
+                }
+            }
+        }
+        print_request_error(tags_res.err().unwrap());
+        process::exit(1);
+    }
+
+    let tags_vec = tags_res.unwrap();
+
+    // Get configuration
+
+    let config_res = api_call_get_config(&vault_url, global_opts.debug).await;
+
+    let vault_config = match config_res {
+        Ok(c) => c,
+        Err(e) => {
+            print_request_error(e);
+            if logout_after_operation {
+                let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                match logout_res {
+                    Ok(_) => {}
+                    Err(_) => {
+                        process::exit(1);
+                    }
+                }
+            }
+            process::exit(1);
+        }
+    };
+
+    // List the media assets to export, oldest first
+
+    let media_ids: Vec<u64> = match search {
+        Some(saved_search) => {
+            let filter = BatchMediaFilter::default().with_saved_search(saved_search);
+
+            let media_list =
+                collect_batch_media(&global_opts, &vault_url, logout_after_operation, filter).await;
+
+            media_list.iter().rev().map(|m| m.id).collect()
+        }
+        None => {
+            let mut media_ids: Vec<u64> = Vec::new();
+
+            let mut search_finished = false;
+            let mut continue_ref: Option<u64> = None;
+            let tags_mode_param = "allof".to_string();
+
+            while !search_finished {
+                let api_res = api_call_search_advanced(
+                    &vault_url,
+                    None,
+                    &tags_mode_param,
+                    true,
+                    MAX_SEARCH_PAGE_LIMIT as u32,
+                    continue_ref,
+                    global_opts.debug,
+                )
+                .await;
+
+                match api_res {
+                    Ok(search_result) => {
+                        for item in search_result.page_items {
+                            media_ids.push(item.id);
+                        }
+
+                        if search_result.scanned >= search_result.total_count {
+                            search_finished = true;
+                        }
+
+                        continue_ref = Some(search_result.continue_ref);
+                    }
+                    Err(e) => {
+                        print_request_error(e);
+                        if logout_after_operation {
+                            let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                            match logout_res {
+                                Ok(_) => {}
+                                Err(_) => {
+                                    process::exit(1);
+                                }
+                            }
+                        }
+                        process::exit(1);
+                    }
+                }
+            }
+
+            media_ids
+        }
+    };
+
+    // List albums
+
+    let albums_res = api_call_get_albums(&vault_url, global_opts.debug).await;
+
+    let mut albums_list = match albums_res {
+        Ok(a) => a,
+        Err(e) => {
+            print_request_error(e);
+            if logout_after_operation {
+                let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                match logout_res {
+                    Ok(_) => {}
+                    Err(_) => {
+                        process::exit(1);
+                    }
+                }
+            }
+            process::exit(1);
+        }
+    };
+
+    albums_list.sort_by_key(|a| a.id);
+
+    // Output
+
+    let out_folder: String = match output {
+        Some(o) => o,
+        None => "vault_export".to_string(),
+    };
+
+    let mut export_output = create_export_output(
+        &global_opts,
+        &vault_url,
+        logout_after_operation,
+        &out_folder,
+        &archive,
+        encryption.as_ref(),
+    )
+    .await;
+
+    // Metadata
+
+    let mut out_metadata = VaultMetadataExport {
+        tags: tags_vec.iter().map(|t| t.name.clone()).collect(),
+        config: Some(vault_config),
+        media_list: Vec::new(),
+        albums: Vec::with_capacity(albums_list.len()),
+    };
+
+    // Albums
+
+    for album_item in albums_list {
+        let album_id = album_item.id;
+
+        let api_get_album_res = api_call_get_album(&vault_url, album_id, global_opts.debug).await;
+
+        let album_data = match api_get_album_res {
+            Ok(a) => a,
+            Err(e) => {
+                print_request_error(e);
+                if logout_after_operation {
+                    let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                process::exit(1);
+            }
+        };
+
+        let mut album_export = VaultAlbumExport {
+            id: album_id,
+            name: album_data.name,
+            media_list: album_data
+                .list
+                .iter()
+                .map(|m| "media_".to_string() + &m.id.to_string())
+                .collect(),
+            thumbnail: None,
+        };
+
+        if let Some(album_thumbnail) = album_data.thumbnail {
+            if !album_thumbnail.is_empty() {
+                let ext = get_extension_from_url(&album_thumbnail, "jpg");
+                let out_file_name = format!("album_{album_id}_thumbnail.{ext}");
+
+                let download_ok = download_export_asset(
+                    &global_opts,
+                    &vault_url,
+                    &mut export_output,
+                    "",
+                    &out_file_name,
+                    &format!("thumbnail of album #{album_id}"),
+                    album_thumbnail,
+                )
+                .await;
+
+                if !download_ok {
+                    if logout_after_operation {
+                        let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                        match logout_res {
+                            Ok(_) => {}
+                            Err(_) => {
+                                process::exit(1);
+                            }
+                        }
+                    }
+                    process::exit(1);
+                }
+
+                album_export.thumbnail = Some(out_file_name);
+            }
+        }
+
+        out_metadata.albums.push(album_export);
+    }
+
+    // Export media
+
+    let n_total = media_ids.len();
+
+    let mut skipped_media: Vec<u64> = Vec::new();
+
+    for (i, media_id) in media_ids.iter().enumerate() {
+        let n_done = i + 1;
+        eprintln!("[{n_done}/{n_total}] Exporting media asset #{media_id}...");
+
+        let media_folder = "media_".to_string() + &media_id.to_string();
+
+        let export_res = export_media(
+            &global_opts,
+            &vault_url,
+            *media_id,
+            &mut export_output,
+            &media_folder,
+        )
+        .await;
+
+        match export_res {
+            MediaExportResult::Exported => {
+                out_metadata.media_list.push(media_folder);
+            }
+            MediaExportResult::Skipped => {
+                skipped_media.push(*media_id);
+            }
+            MediaExportResult::Failed => {
+                if logout_after_operation {
+                    let logout_res = do_logout(&global_opts, &vault_url).await;
+
+                    match logout_res {
+                        Ok(_) => {}
+                        Err(_) => {
+                            process::exit(1);
+                        }
+                    }
+                }
+                process::exit(1);
+            }
+        }
+    }
+
+    // Albums only list the exported media assets
+
+    let exported_media: HashSet<&String> = out_metadata.media_list.iter().collect();
+
+    for album_export in out_metadata.albums.iter_mut() {
+        album_export
+            .media_list
+            .retain(|m| exported_media.contains(m));
+    }
+
+    // Write metadata, after everything is downloaded
+
+    let metadata_str = serde_json::to_string(&out_metadata).unwrap();
+
+    let meta_write_res = export_output
+        .write_file("", "metadata.json", metadata_str.as_bytes())
+        .await;
+
+    if let Err(e_str) = meta_write_res {
+        let metadata_out_path = export_output.get_file_path("", "metadata.json");
+        eprintln!("Could not write metadata file: {metadata_out_path}. Error: {e_str}");
+        if logout_after_operation {
+            let logout_res = do_logout(&global_opts, &vault_url).await;
+
+            match logout_res {
+                Ok(_) => {}
+                Err(_) => {
+                    process::exit(1);
+                }
+            }
+        }
+        process::exit(1);
+    }
+
+    // Manifest
+
+    if let Err(e_str) = export_output.write_manifest("") {
+        eprintln!("Could not write the manifest file into {out_folder}. Error: {e_str}");
+        if logout_after_operation {
+            let logout_res = do_logout(&global_opts, &vault_url).await;
+
+            match logout_res {
+                Ok(_) => {}
+                Err(_) => {
+                    process::exit(1);
+                }
+            }
+        }
+        process::exit(1);
+    }
+
+    finish_export_output(
+        &global_opts,
+        &vault_url,
+        logout_after_operation,
+        export_output,
+    )
+    .await;
+
+    // Done
+
+    if logout_after_operation {
+        let logout_res = do_logout(&global_opts, &vault_url).await;
+
+        match logout_res {
+            Ok(_) => {}
+            Err(_) => {
+                process::exit(1);
+            }
+        }
+    }
+
+    let export_destination = match archive {
+        Some(archive_path) => format!("archive {archive_path}"),
+        None => format!("folder {out_folder}"),
+    };
+
+    if !skipped_media.is_empty() {
+        let n_skipped = skipped_media.len();
+        let skipped_list = skipped_media
+            .iter()
+            .map(|id| identifier_to_string(*id))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        eprintln!("Exported the vault into {export_destination}, but {n_skipped} media assets could not be exported and were skipped: {skipped_list}");
+        process::exit(1);
+    }
+
+    eprintln!("Done. Successfully exported the vault into {export_destination}");
+}
@@ -0,0 +1,601 @@
+// Vault restore command
+
+use std::{
+    process,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    api::{
+        api_call_album_add_media, api_call_album_change_thumbnail, api_call_album_move_media,
+        api_call_create_album, api_call_get_album, api_call_get_tags, api_call_set_config,
+    },
+    commands::{
+        logout::do_logout,
+        media_import::{
+            extract_import_archive, finalize_media_import, read_media_import_metadata,
+            upload_media_import,
+        },
+    },
+    models::{
+        AlbumMediaBody, AlbumMoveMediaBody, AlbumNameBody, VaultMetadataExport, VaultRestoreMapping,
+    },
+    tools::{
+        ask_user, ensure_login, identifier_to_string, is_archive_path, parse_vault_uri,
+        remove_temp_folder, to_csv_string, VaultURI, ARCHIVE_STDIO,
+    },
+};
+
+use super::{
+    get_vault_url, media_upload::UploaderProgressPrinter, print_request_error, CommandGlobalOptions,
+};
+
+pub async fn run_cmd_restore_vault(
+    global_opts: CommandGlobalOptions,
+    path: String,
+    mapping: Option<String>,
+    identity: Option<String>,
+) {
+    let backup_name = path.clone();
+
+    // Archives are extracted into a temporary folder, keeping the mapping file next to the archive
+
+    let mut path = path;
+    let mut mapping = mapping;
+    let mut archive_temp_folder: Option<String> = None;
+
+    if is_archive_path(&path) {
+        if mapping.is_none() {
+            mapping = Some(if path == ARCHIVE_STDIO {
+                "restore_mapping.json".to_string()
+            } else {
+                path.clone() + ".restore_mapping.json"
+            });
+        }
+
+        let (temp_folder, import_folder) = extract_import_archive(&path, identity).await;
+
+        archive_temp_folder = Some(temp_folder);
+        path = import_folder;
+    }
+
+    let restore_ok = restore_vault_folder(global_opts, path, mapping, backup_name).await;
+
+    if let Some(temp_folder) = &archive_temp_folder {
+        remove_temp_folder(temp_folder).await;
+    }
+
+    if !restore_ok {
+        process::exit(1);
+    }
+}
+
+/// Restores a vault from a folder created by the export command
+/// Returns false if the restore failed. Errors are printed instead of exiting,
+/// so the caller can clean up before.
+async fn restore_vault_folder(
+    global_opts: CommandGlobalOptions,
+    path: String,
+    mapping: Option<String>,
+    backup_name: String,
+) -> bool {
+    let url_parse_res = parse_vault_uri(get_vault_url(&global_opts.vault_url));
+
+    if url_parse_res.is_err() {
+        match url_parse_res.err().unwrap() {
+            crate::tools::VaultURIParseError::InvalidProtocol => {
+                eprintln!("Invalid vault URL provided. Must be an HTTP or HTTPS URL.");
+            }
+            crate::tools::VaultURIParseError::URLError(e) => {
+                let err_msg = e.to_string();
+                eprintln!("Invalid vault URL provided: {err_msg}");
+            }
+        }
+
+        return false;
+    }
+
+    let mut vault_url = url_parse_res.unwrap();
+
+    let logout_after_operation = vault_url.is_login();
+    let login_result = ensure_login(&vault_url, &None, global_opts.debug).await;
+
+    if login_result.is_err() {
+        return false;
+    }
+
+    vault_url = login_result.unwrap();
+
+    // Metadata file
+
+    let metadata_file = std::path::Path::new(&path)
+        .join("metadata.json")
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let import_metadata: VaultMetadataExport;
+    let metadata_file_res = tokio::fs::read_to_string(&metadata_file).await;
+
+    match metadata_file_res {
+        Ok(metadata_str) => {
+            let parsed_metadata: Result<VaultMetadataExport, _> =
+                serde_json::from_str(&metadata_str);
+
+            match parsed_metadata {
+                Ok(m) => {
+                    import_metadata = m;
+                }
+                Err(e) => {
+                    let e_str = e.to_string();
+                    eprintln!("Could not read metadata file. Error: {e_str}");
+                    if logout_after_operation {
+                        let _ = do_logout(&global_opts, &vault_url).await;
+                    }
+                    return false;
+                }
+            }
+        }
+        Err(e) => {
+            let e_str = e.to_string();
+            eprintln!("Could not read metadata file. Error: {e_str}");
+            if logout_after_operation {
+                let _ = do_logout(&global_opts, &vault_url).await;
+            }
+            return false;
+        }
+    }
+
+    // Mapping file
+
+    let mapping_file: String = match mapping {
+        Some(m) => m,
+        None => std::path::Path::new(&path)
+            .join("restore_mapping.json")
+            .to_str()
+            .unwrap()
+            .to_string(),
+    };
+
+    let mut restore_mapping = VaultRestoreMapping::default();
+
+    if std::path::Path::new(&mapping_file).exists() {
+        let mapping_file_res = tokio::fs::read_to_string(&mapping_file).await;
+
+        let parsed_mapping: Result<VaultRestoreMapping, String> = match mapping_file_res {
+            Ok(mapping_str) => serde_json::from_str(&mapping_str).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+
+        match parsed_mapping {
+            Ok(m) => {
+                restore_mapping = m;
+
+                let restored_count = restore_mapping
+                    .media
+                    .keys()
+                    .filter(|k| !restore_mapping.pending_media.contains(*k))
+                    .count();
+                eprintln!("Resuming restore. Found {restored_count} media assets already restored in {mapping_file}");
+            }
+            Err(e_str) => {
+                eprintln!("Could not read mapping file: {mapping_file}. Error: {e_str}");
+                if logout_after_operation {
+                    let _ = do_logout(&global_opts, &vault_url).await;
+                }
+                return false;
+            }
+        }
+    }
+
+    // Ask confirmation
+
+    let n_total = import_metadata.media_list.len();
+    let n_albums = import_metadata.albums.len();
+
+    if !global_opts.auto_confirm {
+        eprintln!("Are you sure you want to restore {n_total} media assets and {n_albums} albums into the vault?");
+        let confirmation = ask_user("Continue? y/n: ").await.unwrap_or("".to_string());
+
+        if confirmation.to_lowercase() != "y" {
+            if logout_after_operation {
+                let _ = do_logout(&global_opts, &vault_url).await;
+            }
+            return false;
+        }
+    }
+
+    // Media
+
+    let mut n_failed: usize = 0;
+
+    for (i, media_folder) in import_metadata.media_list.iter().enumerate() {
+        let n_done = i + 1;
+
+        let uploaded_id = restore_mapping.media.get(media_folder).copied();
+
+        if let Some(new_id) = uploaded_id {
+            if !restore_mapping.pending_media.contains(media_folder) {
+                let new_id_str = identifier_to_string(new_id);
+                eprintln!(
+                    "[{n_done}/{n_total}] Skipped {media_folder}: Already restored as {new_id_str}"
+                );
+                continue;
+            }
+        }
+
+        eprintln!("[{n_done}/{n_total}] Restoring {media_folder}...");
+
+        let media_path = std::path::Path::new(&path)
+            .join(media_folder)
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let media_metadata = match read_media_import_metadata(&media_path).await {
+            Ok(m) => m,
+            Err(e_str) => {
+                n_failed += 1;
+                eprintln!("Error: Could not restore {media_folder}: {e_str}");
+                continue;
+            }
+        };
+
+        // The media asset is recorded as soon as it is uploaded,
+        // so resuming finishes its metadata instead of uploading it again
+
+        let new_id: u64 = match uploaded_id {
+            Some(id) => {
+                let id_str = identifier_to_string(id);
+                eprintln!("Resuming {media_folder}: Already uploaded as {id_str}");
+                id
+            }
+            None => {
+                let upload_res = upload_media_import(
+                    &global_opts,
+                    &vault_url,
+                    &media_path,
+                    &media_metadata,
+                    None,
+                )
+                .await;
+
+                match upload_res {
+                    Some(id) => {
+                        restore_mapping.media.insert(media_folder.clone(), id);
+                        restore_mapping.pending_media.insert(media_folder.clone());
+                        let mapping_ok = write_restore_mapping(
+                            &global_opts,
+                            &vault_url,
+                            logout_after_operation,
+                            &mapping_file,
+                            &restore_mapping,
+                        )
+                        .await;
+
+                        if !mapping_ok {
+                            return false;
+                        }
+                        id
+                    }
+                    None => {
+                        n_failed += 1;
+                        eprintln!("Error: Could not restore {media_folder}");
+                        continue;
+                    }
+                }
+            }
+        };
+
+        let finalize_res = finalize_media_import(
+            &global_opts,
+            &vault_url,
+            &media_path,
+            new_id,
+            &media_metadata,
+        )
+        .await;
+
+        if finalize_res {
+            restore_mapping.pending_media.remove(media_folder);
+            let mapping_ok = write_restore_mapping(
+                &global_opts,
+                &vault_url,
+                logout_after_operation,
+                &mapping_file,
+                &restore_mapping,
+            )
+            .await;
+
+            if !mapping_ok {
+                return false;
+            }
+        } else {
+            n_failed += 1;
+            eprintln!("Error: Could not restore {media_folder}");
+        }
+    }
+
+    // Albums
+
+    for album in import_metadata.albums.iter() {
+        let old_album_key = album.id.to_string();
+        let album_name_csv = to_csv_string(&album.name);
+
+        let mut current_list: Option<Vec<u64>> = None;
+
+        if let Some(new_album_id) = restore_mapping.albums.get(&old_album_key) {
+            let api_res = api_call_get_album(&vault_url, *new_album_id, global_opts.debug).await;
+
+            match api_res {
+                Ok(album_data) => {
+                    current_list = Some(album_data.list.iter().map(|m| m.id).collect());
+                }
+                Err(_) => {
+                    eprintln!(
+                        "Album #{new_album_id} could not be found. It will be created again."
+                    );
+                }
+            }
+        }
+
+        let album_id: u64 = match current_list {
+            Some(_) => *restore_mapping.albums.get(&old_album_key).unwrap(),
+            None => {
+                let api_res = api_call_create_album(
+                    &vault_url,
+                    AlbumNameBody {
+                        name: album.name.clone(),
+                    },
+                    global_opts.debug,
+                )
+                .await;
+
+                match api_res {
+                    Ok(added_album) => {
+                        let new_album_id = added_album.album_id;
+
+                        eprintln!("Successfully created album #{new_album_id}: {album_name_csv}");
+
+                        restore_mapping
+                            .albums
+                            .insert(old_album_key.clone(), new_album_id);
+                        restore_mapping.albums_thumbnail_done.remove(&old_album_key);
+                        let mapping_ok = write_restore_mapping(
+                            &global_opts,
+                            &vault_url,
+                            logout_after_operation,
+                            &mapping_file,
+                            &restore_mapping,
+                        )
+                        .await;
+
+                        if !mapping_ok {
+                            return false;
+                        }
+
+                        current_list = Some(Vec::new());
+
+                        new_album_id
+                    }
+                    Err(e) => {
+                        print_request_error(e);
+                        if logout_after_operation {
+                            let _ = do_logout(&global_opts, &vault_url).await;
+                        }
+                        return false;
+                    }
+                }
+            }
+        };
+
+        // Set thumbnail
+
+        if let Some(thumbnail_file) = &album.thumbnail {
+            if !restore_mapping
+                .albums_thumbnail_done
+                .contains(&old_album_key)
+            {
+                let thumbnail_file_path = std::path::Path::new(&path)
+                    .join(thumbnail_file)
+                    .to_str()
+                    .unwrap()
+                    .to_string();
+
+                let progress_printer = Arc::new(Mutex::new(UploaderProgressPrinter::new()));
+
+                let api_res = api_call_album_change_thumbnail(
+                    &vault_url,
+                    album_id,
+                    thumbnail_file_path.clone(),
+                    global_opts.debug,
+                    progress_printer,
+                )
+                .await;
+
+                match api_res {
+                    Ok(_) => {
+                        eprintln!("Upload completed: {thumbnail_file_path}");
+
+                        restore_mapping
+                            .albums_thumbnail_done
+                            .insert(old_album_key.clone());
+                        let mapping_ok = write_restore_mapping(
+                            &global_opts,
+                            &vault_url,
+                            logout_after_operation,
+                            &mapping_file,
+                            &restore_mapping,
+                        )
+                        .await;
+
+                        if !mapping_ok {
+                            return false;
+                        }
+                    }
+                    Err(e) => {
+                        print_request_error(e);
+                    }
+                }
+            }
+        }
+
+        let mut album_list = current_list.unwrap_or_default();
+
+        // Add the missing media, in the original order
+
+        let expected_list: Vec<u64> = album
+            .media_list
+            .iter()
+            .filter_map(|media_folder| restore_mapping.media.get(media_folder).copied())
+            .collect();
+
+        for media_id in expected_list.iter() {
+            if album_list.contains(media_id) {
+                continue;
+            }
+
+            let api_res = api_call_album_add_media(
+                &vault_url,
+                album_id,
+                AlbumMediaBody {
+                    media_id: *media_id,
+                },
+                global_opts.debug,
+            )
+            .await;
+
+            match api_res {
+                Ok(_) => {
+                    album_list.push(*media_id);
+                }
+                Err(e) => {
+                    print_request_error(e);
+                }
+            }
+        }
+
+        // Fix positions (only needed if the restore was interrupted)
+
+        for (position, media_id) in expected_list.iter().enumerate() {
+            if album_list.get(position) == Some(media_id) {
+                continue;
+            }
+
+            let current_position = album_list.iter().position(|m| m == media_id);
+
+            if let Some(current_position) = current_position {
+                let api_res = api_call_album_move_media(
+                    &vault_url,
+                    album_id,
+                    AlbumMoveMediaBody {
+                        media_id: *media_id,
+                        position: position as u32,
+                    },
+                    global_opts.debug,
+                )
+                .await;
+
+                match api_res {
+                    Ok(_) => {
+                        album_list.remove(current_position);
+                        album_list.insert(position.min(album_list.len()), *media_id);
+                    }
+                    Err(e) => {
+                        print_request_error(e);
+                    }
+                }
+            }
+        }
+
+        let album_size = expected_list.len();
+        eprintln!("Restored album #{album_id}: {album_name_csv} ({album_size} media assets)");
+    }
+
+    // Configuration
+
+    if let Some(vault_config) = import_metadata.config {
+        if !restore_mapping.config_applied {
+            let api_res = api_call_set_config(&vault_url, vault_config, global_opts.debug).await;
+
+            match api_res {
+                Ok(_) => {
+                    eprintln!("Successfully restored the vault configuration");
+
+                    restore_mapping.config_applied = true;
+                    let mapping_ok = write_restore_mapping(
+                        &global_opts,
+                        &vault_url,
+                        logout_after_operation,
+                        &mapping_file,
+                        &restore_mapping,
+                    )
+                    .await;
+
+                    if !mapping_ok {
+                        return false;
+                    }
+                }
+                Err(e) => {
+                    print_request_error(e);
+                }
+            }
+        }
+    }
+
+    // Tags are created when added to a media asset, so unused tags cannot be restored
+
+    let tags_res = api_call_get_tags(&vault_url, global_opts.debug).await;
+
+    if let Ok(tags_vec) = tags_res {
+        for tag_name in import_metadata.tags.iter() {
+            if !tags_vec.iter().any(|t| t.name == *tag_name) {
+                eprintln!(
+                    "Warning: Tag {tag_name} was not restored, since no media asset uses it."
+                );
+            }
+        }
+    }
+
+    // Done
+
+    if logout_after_operation {
+        let logout_res = do_logout(&global_opts, &vault_url).await;
+
+        if logout_res.is_err() {
+            return false;
+        }
+    }
+
+    if n_failed > 0 {
+        eprintln!("Restore finished with errors. {n_failed} media assets could not be restored. Run the command again to retry them.");
+        return false;
+    }
+
+    eprintln!("Done. Successfully restored the vault from {backup_name}");
+
+    true
+}
+
+async fn write_restore_mapping(
+    global_opts: &CommandGlobalOptions,
+    vault_url: &VaultURI,
+    logout_after_operation: bool,
+    mapping_file: &str,
+    restore_mapping: &VaultRestoreMapping,
+) -> bool {
+    let mapping_str = serde_json::to_string(restore_mapping).unwrap();
+
+    let write_res = tokio::fs::write(mapping_file, mapping_str).await;
+
+    if let Err(e) = write_res {
+        let e_str = e.to_string();
+        eprintln!("Could not write mapping file: {mapping_file}. Error: {e_str}");
+        if logout_after_operation {
+            let _ = do_logout(global_opts, vault_url).await;
+        }
+        return false;
+    }
+
+    true
+}
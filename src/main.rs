@@ -1,6 +1,6 @@
 // Main
 
-use clap::{Parser, command};
+use clap::Parser;
 use commands::{run_cmd, Commands, CommandGlobalOptions};
 
 mod api;
@@ -24,6 +24,10 @@ struct Cli {
     #[arg(short, long)]
     pub yes: bool,
 
+    /// Record the previous state of the media assets and albums changed by the command in the local undo journal, so the changes can be reverted with the undo command
+    #[arg(long)]
+    pub undo_log: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -36,6 +40,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         debug: cli.debug,
         auto_confirm: cli.yes,
         vault_url: cli.vault_url,
+        undo_log: cli.undo_log,
     };
 
     run_cmd(global_opts, cli.command).await;
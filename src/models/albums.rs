@@ -1,5 +1,7 @@
 // Albums models
 
+use std::collections::{HashMap, HashSet};
+
 use serde::{Deserialize, Serialize};
 
 use super::MediaListItem;
@@ -53,6 +55,9 @@ pub struct Album {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AlbumMetadataExport {
+    #[serde(rename = "id", default)]
+    pub id: Option<u64>,
+
     #[serde(rename = "name")]
     pub name: String,
 
@@ -77,3 +82,24 @@ pub struct AlbumMoveMediaBody {
     #[serde(rename = "position")]
     pub position: u32,
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AlbumImportJournal {
+    /// Base URL of the vault the album is imported into
+    #[serde(rename = "vault", default)]
+    pub vault: String,
+
+    /// ID of the exported album being imported
+    #[serde(rename = "source_album", default)]
+    pub source_album: Option<u64>,
+
+    #[serde(rename = "album", default)]
+    pub album: Option<u64>,
+
+    #[serde(rename = "media", default)]
+    pub media: HashMap<String, u64>,
+
+    /// Media assets already uploaded, but with the rest of the metadata not imported yet
+    #[serde(rename = "pending_media", default)]
+    pub pending_media: HashSet<String>,
+}
@@ -0,0 +1,158 @@
+// Batch operation models
+
+use serde::{Deserialize, Serialize};
+
+use super::SavedSearch;
+
+/// Changes a batch operation would apply, computed by a dry run
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchPlan {
+    #[serde(rename = "operation")]
+    pub operation: String,
+
+    /// Number of media assets matched by the filter
+    #[serde(rename = "matched")]
+    pub matched: usize,
+
+    /// Number of media assets that would change
+    #[serde(rename = "changed")]
+    pub changed: usize,
+
+    #[serde(rename = "media")]
+    pub media: Vec<BatchPlanEntry>,
+}
+
+/// Changes for a single media asset
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BatchPlanEntry {
+    #[serde(rename = "id")]
+    pub id: u64,
+
+    #[serde(rename = "type")]
+    pub media_type: String,
+
+    #[serde(rename = "title")]
+    pub title: String,
+
+    #[serde(rename = "add_tags", default, skip_serializing_if = "Vec::is_empty")]
+    pub add_tags: Vec<String>,
+
+    #[serde(rename = "remove_tags", default, skip_serializing_if = "Vec::is_empty")]
+    pub remove_tags: Vec<String>,
+
+    #[serde(
+        rename = "add_to_album",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub add_to_album: Option<u64>,
+
+    #[serde(
+        rename = "remove_from_album",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub remove_from_album: Option<u64>,
+
+    #[serde(rename = "delete", default)]
+    pub delete: bool,
+}
+
+impl BatchPlanEntry {
+    /// Checks if the media asset would change
+    pub fn has_changes(&self) -> bool {
+        !self.add_tags.is_empty()
+            || !self.remove_tags.is_empty()
+            || self.add_to_album.is_some()
+            || self.remove_from_album.is_some()
+            || self.delete
+    }
+
+    /// Describes the changes, for the dry run table
+    pub fn to_changes_string(&self) -> String {
+        let mut changes: Vec<String> = Vec::new();
+
+        for tag in self.add_tags.iter() {
+            changes.push(format!("+tag:{tag}"));
+        }
+
+        for tag in self.remove_tags.iter() {
+            changes.push(format!("-tag:{tag}"));
+        }
+
+        if let Some(album_id) = self.add_to_album {
+            changes.push(format!("+album:#{album_id}"));
+        }
+
+        if let Some(album_id) = self.remove_from_album {
+            changes.push(format!("-album:#{album_id}"));
+        }
+
+        if self.delete {
+            changes.push("delete".to_string());
+        }
+
+        if changes.is_empty() {
+            "(no changes)".to_string()
+        } else {
+            changes.join(" ")
+        }
+    }
+}
+
+/// First line of a batch journal, identifying the operation
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BatchJournalHeader {
+    #[serde(rename = "operation")]
+    pub operation: String,
+
+    #[serde(rename = "argument")]
+    pub argument: String,
+
+    /// Base URL of the vault
+    #[serde(rename = "vault", default)]
+    pub vault: String,
+
+    /// Criteria used to find the media assets
+    #[serde(rename = "filter", default)]
+    pub filter: SavedSearch,
+
+    /// Media assets listed with --ids-from, if any
+    #[serde(rename = "ids", default, skip_serializing_if = "Option::is_none")]
+    pub ids: Option<Vec<u64>>,
+
+    /// True if the operation was applied to the entire vault
+    #[serde(rename = "everything", default)]
+    pub everything: bool,
+}
+
+impl BatchJournalHeader {
+    /// Renders the criteria used to find the media assets as command line options
+    pub fn to_filter_string(&self) -> String {
+        let mut options: Vec<String> = Vec::new();
+
+        let filter_options = self.filter.to_options_string();
+
+        if !filter_options.is_empty() {
+            options.push(filter_options);
+        }
+
+        if let Some(ids) = &self.ids {
+            let n_ids = ids.len();
+            options.push(format!("--ids-from ({n_ids} media assets)"));
+        }
+
+        if self.everything {
+            options.push("--everything".to_string());
+        }
+
+        options.join(" ")
+    }
+}
+
+/// Line of a batch journal, for each completed media asset
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchJournalEntry {
+    #[serde(rename = "media_id")]
+    pub media_id: u64,
+}
@@ -0,0 +1,56 @@
+// Catalog models
+
+use serde::{Deserialize, Serialize};
+
+use super::MediaAssetSizeStatsItem;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CatalogEntry {
+    #[serde(rename = "id")]
+    pub id: u64,
+
+    #[serde(rename = "type")]
+    pub media_type: String,
+
+    #[serde(rename = "title")]
+    pub title: String,
+
+    #[serde(rename = "description")]
+    pub description: String,
+
+    #[serde(rename = "tags")]
+    pub tags: Vec<String>,
+
+    #[serde(rename = "duration")]
+    pub duration: Option<f64>,
+
+    #[serde(rename = "width")]
+    pub width: Option<i32>,
+
+    #[serde(rename = "height")]
+    pub height: Option<i32>,
+
+    #[serde(rename = "fps")]
+    pub fps: Option<i32>,
+
+    #[serde(rename = "force_start_beginning")]
+    pub force_start_beginning: bool,
+
+    #[serde(rename = "is_anim")]
+    pub is_anim: bool,
+
+    #[serde(rename = "upload_time")]
+    pub upload_time: i64,
+
+    #[serde(rename = "albums")]
+    pub albums: Vec<u64>,
+
+    #[serde(rename = "meta_size")]
+    pub meta_size: u64,
+
+    #[serde(rename = "assets_size")]
+    pub assets_size: u64,
+
+    #[serde(rename = "assets")]
+    pub assets: Vec<MediaAssetSizeStatsItem>,
+}
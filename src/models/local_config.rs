@@ -0,0 +1,95 @@
+// Local CLI configuration models
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Configuration stored in the local machine, for the CLI itself
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LocalConfig {
+    #[serde(rename = "saved_searches", default)]
+    pub saved_searches: BTreeMap<String, SavedSearch>,
+}
+
+/// Saved advanced search criteria
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct SavedSearch {
+    #[serde(rename = "title", default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    #[serde(
+        rename = "description",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub description: Option<String>,
+
+    #[serde(
+        rename = "media_type",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub media_type: Option<String>,
+
+    #[serde(rename = "tags", default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<String>,
+
+    #[serde(rename = "tags_mode", default, skip_serializing_if = "Option::is_none")]
+    pub tags_mode: Option<String>,
+
+    #[serde(rename = "album", default, skip_serializing_if = "Option::is_none")]
+    pub album: Option<String>,
+
+    #[serde(rename = "query", default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+
+    #[serde(rename = "regex", default)]
+    pub regex: bool,
+
+    #[serde(rename = "fuzzy", default)]
+    pub fuzzy: bool,
+}
+
+impl SavedSearch {
+    /// Checks if the saved search has no criteria
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.description.is_none()
+            && self.media_type.is_none()
+            && self.tags.is_none()
+            && self.tags_mode.is_none()
+            && self.album.is_none()
+            && self.query.is_none()
+    }
+
+    /// Renders the criteria as command line options
+    pub fn to_options_string(&self) -> String {
+        let mut options: Vec<String> = Vec::new();
+
+        let string_options = [
+            ("--title", &self.title),
+            ("--description", &self.description),
+            ("--media-type", &self.media_type),
+            ("--tags", &self.tags),
+            ("--tags-mode", &self.tags_mode),
+            ("--album", &self.album),
+            ("--query", &self.query),
+        ];
+
+        for (option, value) in string_options {
+            if let Some(v) = value {
+                options.push(format!("{option} {}", serde_json::to_string(v).unwrap()));
+            }
+        }
+
+        if self.regex {
+            options.push("--regex".to_string());
+        }
+
+        if self.fuzzy {
+            options.push("--fuzzy".to_string());
+        }
+
+        options.join(" ")
+    }
+}
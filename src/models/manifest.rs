@@ -0,0 +1,21 @@
+// Export manifest models
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportManifest {
+    #[serde(rename = "files")]
+    pub files: Vec<ExportManifestFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportManifestFile {
+    #[serde(rename = "name")]
+    pub name: String,
+
+    #[serde(rename = "size")]
+    pub size: u64,
+
+    #[serde(rename = "sha256")]
+    pub sha256: String,
+}
@@ -155,8 +155,30 @@ pub struct MediaMetadata {
     pub ext_desc_url: Option<String>,
 }
 
+impl MediaMetadata {
+    /// Gets the media list item for the media asset, as search results include them
+    pub fn to_list_item(&self) -> MediaListItem {
+        MediaListItem {
+            id: self.id,
+            media_type: self.media_type,
+            title: self.title.clone(),
+            description: self.description.clone(),
+            thumbnail: if self.thumbnail.is_empty() {
+                None
+            } else {
+                Some(self.thumbnail.clone())
+            },
+            tags: self.tags.clone(),
+            duration: self.duration,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MediaMetadataExport {
+    #[serde(rename = "id", default)]
+    pub id: Option<u64>,
+
     #[serde(rename = "original")]
     pub original: Option<String>,
 
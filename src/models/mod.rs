@@ -12,6 +12,12 @@ pub use albums::*;
 mod auth;
 pub use auth::*;
 
+mod batch;
+pub use batch::*;
+
+mod catalog;
+pub use catalog::*;
+
 mod config;
 pub use config::*;
 
@@ -21,14 +27,29 @@ pub use error::*;
 mod invites;
 pub use invites::*;
 
+mod local_config;
+pub use local_config::*;
+
+mod manifest;
+pub use manifest::*;
+
 mod media;
 pub use media::*;
 
 mod search;
 pub use search::*;
 
+mod sidecar;
+pub use sidecar::*;
+
 mod tags;
 pub use tags::*;
 
 mod tasks;
 pub use tasks::*;
+
+mod undo;
+pub use undo::*;
+
+mod vault;
+pub use vault::*;
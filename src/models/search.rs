@@ -49,3 +49,25 @@ pub struct AdvancedSearchMediaResult {
     #[serde(rename = "items")]
     pub page_items: Vec<MediaListItem>,
 }
+
+/// Search result, as written in JSON Lines format
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResultEntry {
+    #[serde(rename = "id")]
+    pub id: u64,
+
+    #[serde(rename = "type")]
+    pub media_type: String,
+
+    #[serde(rename = "title")]
+    pub title: String,
+
+    #[serde(rename = "description")]
+    pub description: String,
+
+    #[serde(rename = "tags")]
+    pub tags: Vec<String>,
+
+    #[serde(rename = "duration")]
+    pub duration: Option<f64>,
+}
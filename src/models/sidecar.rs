@@ -0,0 +1,42 @@
+// Sidecar metadata models (yt-dlp)
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct YtDlpInfo {
+    #[serde(rename = "title", default)]
+    pub title: Option<String>,
+
+    #[serde(rename = "description", default)]
+    pub description: Option<String>,
+
+    #[serde(rename = "tags", default)]
+    pub tags: Option<Vec<String>>,
+
+    #[serde(rename = "categories", default)]
+    pub categories: Option<Vec<String>>,
+
+    #[serde(rename = "chapters", default)]
+    pub chapters: Option<Vec<YtDlpChapter>>,
+
+    #[serde(rename = "uploader", default)]
+    pub uploader: Option<String>,
+
+    #[serde(rename = "channel", default)]
+    pub channel: Option<String>,
+
+    #[serde(rename = "upload_date", default)]
+    pub upload_date: Option<String>,
+
+    #[serde(rename = "webpage_url", default)]
+    pub webpage_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct YtDlpChapter {
+    #[serde(rename = "start_time")]
+    pub start_time: f64,
+
+    #[serde(rename = "title", default)]
+    pub title: Option<String>,
+}
@@ -0,0 +1,93 @@
+// Undo journal models
+
+use serde::{Deserialize, Serialize};
+
+/// Entry of the undo journal, for each operation recorded
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UndoEntry {
+    #[serde(rename = "id")]
+    pub id: u64,
+
+    /// Unix timestamp (milliseconds) of the operation
+    #[serde(rename = "timestamp")]
+    pub timestamp: i64,
+
+    /// Base URL of the vault the operation was applied to
+    #[serde(rename = "vault")]
+    pub vault: String,
+
+    /// Description of the operation
+    #[serde(rename = "operation")]
+    pub operation: String,
+
+    /// Previous state of everything the operation changes
+    #[serde(rename = "changes")]
+    pub changes: Vec<UndoChange>,
+
+    /// True if the operation was already reverted
+    #[serde(rename = "undone", default)]
+    pub undone: bool,
+}
+
+/// Previous state of a single property, before an operation changed it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type")]
+pub enum UndoChange {
+    /// Whether a media asset had a tag
+    #[serde(rename = "media_tag")]
+    MediaTag {
+        #[serde(rename = "media_id")]
+        media_id: u64,
+
+        #[serde(rename = "tag")]
+        tag: String,
+
+        #[serde(rename = "present")]
+        present: bool,
+    },
+
+    /// Title of a media asset
+    #[serde(rename = "media_title")]
+    MediaTitle {
+        #[serde(rename = "media_id")]
+        media_id: u64,
+
+        #[serde(rename = "title")]
+        title: String,
+    },
+
+    /// Description of a media asset
+    #[serde(rename = "media_description")]
+    MediaDescription {
+        #[serde(rename = "media_id")]
+        media_id: u64,
+
+        #[serde(rename = "description")]
+        description: String,
+    },
+
+    /// Position of a media asset in an album, starting at 0. None if it was not in the album.
+    #[serde(rename = "album_media")]
+    AlbumMedia {
+        #[serde(rename = "album_id")]
+        album_id: u64,
+
+        #[serde(rename = "media_id")]
+        media_id: u64,
+
+        #[serde(rename = "position")]
+        position: Option<usize>,
+    },
+}
+
+impl UndoChange {
+    /// Gets the ID of the media asset the change belongs to
+    pub fn media_id(&self) -> u64 {
+        match self {
+            UndoChange::MediaTag { media_id, .. }
+            | UndoChange::MediaTitle { media_id, .. }
+            | UndoChange::MediaDescription { media_id, .. }
+            | UndoChange::AlbumMedia { media_id, .. } => *media_id,
+        }
+    }
+}
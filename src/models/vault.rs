@@ -0,0 +1,73 @@
+// Vault backup models
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::VaultConfig;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VaultMetadataExport {
+    #[serde(rename = "tags")]
+    pub tags: Vec<String>,
+
+    #[serde(rename = "config")]
+    pub config: Option<VaultConfig>,
+
+    #[serde(rename = "media_list")]
+    pub media_list: Vec<String>,
+
+    #[serde(rename = "albums")]
+    pub albums: Vec<VaultAlbumExport>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VaultAlbumExport {
+    #[serde(rename = "id")]
+    pub id: u64,
+
+    #[serde(rename = "name")]
+    pub name: String,
+
+    #[serde(rename = "media_list")]
+    pub media_list: Vec<String>,
+
+    #[serde(rename = "thumbnail")]
+    pub thumbnail: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct VaultRestoreMapping {
+    #[serde(rename = "media", default)]
+    pub media: HashMap<String, u64>,
+
+    #[serde(rename = "albums", default)]
+    pub albums: HashMap<String, u64>,
+
+    /// Albums with the thumbnail already uploaded
+    #[serde(rename = "albums_thumbnail_done", default)]
+    pub albums_thumbnail_done: HashSet<String>,
+
+    /// Media assets already uploaded, but with the rest of the metadata not restored yet
+    #[serde(rename = "pending_media", default)]
+    pub pending_media: HashSet<String>,
+
+    #[serde(rename = "config_applied", default)]
+    pub config_applied: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct VaultSyncMapping {
+    #[serde(rename = "source", default)]
+    pub source: String,
+
+    #[serde(rename = "target", default)]
+    pub target: String,
+
+    #[serde(rename = "media", default)]
+    pub media: HashMap<String, u64>,
+
+    /// Media assets already uploaded, but with the rest of the metadata not copied yet
+    #[serde(rename = "pending_media", default)]
+    pub pending_media: HashSet<String>,
+}
@@ -0,0 +1,629 @@
+// Archive utilities (tar and zip)
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use age::secrecy::SecretString;
+use sha2::{Digest, Sha256};
+
+use crate::models::{ExportManifest, ExportManifestFile};
+
+use super::{ask_user_password, format_checksum, EXPORT_MANIFEST_FILE};
+
+/// Archive path meaning the standard input or output
+pub const ARCHIVE_STDIO: &str = "-";
+
+/// Environment variable to provide the archive passphrase, instead of asking for it
+pub const ARCHIVE_PASSPHRASE_ENV: &str = "PMV_ARCHIVE_PASSPHRASE";
+
+/// Header of the files encrypted with the age format
+const AGE_MAGIC: &[u8] = b"age-encryption.org/v1";
+
+/// Path of the GNU tar entries storing the long name of the next entry
+const TAR_LONG_NAME_PATH: &str = "././@LongLink";
+
+/// Header of the zip files
+const ZIP_MAGIC: &[u8] = &[0x50, 0x4b, 0x03, 0x04];
+
+/// Options to encrypt an archive, using the age format
+pub enum ArchiveEncryption {
+    /// Encrypt with a passphrase
+    Passphrase(SecretString),
+
+    /// Encrypt for a list of age public keys
+    Recipients(Vec<String>),
+}
+
+/// Options to decrypt an archive encrypted with the age format
+pub enum ArchiveDecryption {
+    /// Decrypt with a passphrase
+    Passphrase(SecretString),
+
+    /// Decrypt with the private keys stored in an identity file
+    IdentityFile(String),
+}
+
+/// Checks if a path points to an archive instead of a folder
+pub fn is_archive_path(path: &str) -> bool {
+    path == ARCHIVE_STDIO || Path::new(path).is_file()
+}
+
+/// Checks if the archive path should be written as zip. Otherwise, tar is used.
+fn is_zip_archive_path(path: &str) -> bool {
+    let lower_path = path.to_lowercase();
+    let lower_path = lower_path.strip_suffix(".age").unwrap_or(&lower_path);
+
+    lower_path.ends_with(".zip")
+}
+
+/// Checks if an archive file is encrypted
+fn is_encrypted_archive_file(path: &str) -> bool {
+    let file = match File::open(path) {
+        Ok(f) => f,
+        Err(_) => {
+            return false;
+        }
+    };
+
+    let mut reader = BufReader::new(file);
+
+    match reader.fill_buf() {
+        Ok(buf) => buf.starts_with(AGE_MAGIC),
+        Err(_) => false,
+    }
+}
+
+/// Gets the options to encrypt an archive, from the command line options
+/// The passphrase is taken from the environment, or asked to the user.
+pub async fn get_archive_encryption(
+    passphrase: bool,
+    recipients: Vec<String>,
+) -> Result<Option<ArchiveEncryption>, String> {
+    if !recipients.is_empty() {
+        return Ok(Some(ArchiveEncryption::Recipients(recipients)));
+    }
+
+    if !passphrase {
+        return Ok(None);
+    }
+
+    if let Ok(p) = std::env::var(ARCHIVE_PASSPHRASE_ENV) {
+        if !p.is_empty() {
+            return Ok(Some(ArchiveEncryption::Passphrase(SecretString::from(p))));
+        }
+    }
+
+    let p = ask_user_password("Archive passphrase: ")
+        .await
+        .unwrap_or("".to_string());
+
+    if p.is_empty() {
+        return Err("The passphrase cannot be empty.".to_string());
+    }
+
+    let p_confirm = ask_user_password("Confirm archive passphrase: ")
+        .await
+        .unwrap_or("".to_string());
+
+    if p != p_confirm {
+        return Err("The passphrases do not match.".to_string());
+    }
+
+    Ok(Some(ArchiveEncryption::Passphrase(SecretString::from(p))))
+}
+
+/// Gets the options to decrypt an archive, from the command line options
+/// If the archive is encrypted and no identity file is provided, the passphrase is taken from the environment, or asked to the user.
+pub async fn get_archive_decryption(
+    archive: &str,
+    identity: Option<String>,
+) -> Option<ArchiveDecryption> {
+    if let Some(identity_file) = identity {
+        return Some(ArchiveDecryption::IdentityFile(identity_file));
+    }
+
+    if let Ok(p) = std::env::var(ARCHIVE_PASSPHRASE_ENV) {
+        if !p.is_empty() {
+            return Some(ArchiveDecryption::Passphrase(SecretString::from(p)));
+        }
+    }
+
+    if archive == ARCHIVE_STDIO || !is_encrypted_archive_file(archive) {
+        return None;
+    }
+
+    let p = ask_user_password("Archive passphrase: ")
+        .await
+        .unwrap_or("".to_string());
+
+    Some(ArchiveDecryption::Passphrase(SecretString::from(p)))
+}
+
+/// Output of an archive: a file or the standard output, optionally encrypted
+enum ArchiveOutput {
+    Plain(Box<dyn Write>),
+    Encrypted(age::stream::StreamWriter<Box<dyn Write>>),
+}
+
+impl Write for ArchiveOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            ArchiveOutput::Plain(w) => w.write(buf),
+            ArchiveOutput::Encrypted(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            ArchiveOutput::Plain(w) => w.flush(),
+            ArchiveOutput::Encrypted(w) => w.flush(),
+        }
+    }
+}
+
+impl ArchiveOutput {
+    /// Finishes the encryption, if any, and flushes the output
+    fn finish(self) -> Result<(), String> {
+        let mut writer = match self {
+            ArchiveOutput::Plain(w) => w,
+            ArchiveOutput::Encrypted(w) => w.finish().map_err(|e| e.to_string())?,
+        };
+
+        writer.flush().map_err(|e| e.to_string())
+    }
+}
+
+enum ArchiveBuilder {
+    Tar(tar::Builder<ArchiveOutput>),
+    Zip(Box<zip::ZipWriter<zip::write::StreamWriter<ArchiveOutput>>>),
+}
+
+/// File being written into an archive
+struct ArchiveEntry {
+    folder: String,
+    name: String,
+    size: u64,
+    written: u64,
+    hasher: Sha256,
+}
+
+/// Archive written during an export, adding each file as soon as it is downloaded
+/// Both formats are written sequentially (zip in streaming mode), so they can be encrypted.
+/// The standard output is always written as tar.
+pub struct ArchiveWriter {
+    builder: ArchiveBuilder,
+    entry: Option<ArchiveEntry>,
+    manifests: HashMap<String, Vec<ExportManifestFile>>,
+}
+
+impl ArchiveWriter {
+    /// Creates an archive, optionally encrypting it
+    /// The format is chosen by the extension. The standard output (-) always uses tar.
+    pub fn create(
+        archive: &str,
+        encryption: Option<&ArchiveEncryption>,
+    ) -> Result<ArchiveWriter, String> {
+        let writer: Box<dyn Write> = if archive == ARCHIVE_STDIO {
+            Box::new(std::io::stdout().lock())
+        } else {
+            Box::new(BufWriter::new(
+                File::create(archive).map_err(|e| e.to_string())?,
+            ))
+        };
+
+        let output = match encryption {
+            Some(enc) => ArchiveOutput::Encrypted(
+                get_encryptor(enc)?
+                    .wrap_output(writer)
+                    .map_err(|e| e.to_string())?,
+            ),
+            None => ArchiveOutput::Plain(writer),
+        };
+
+        let builder = if archive != ARCHIVE_STDIO && is_zip_archive_path(archive) {
+            ArchiveBuilder::Zip(Box::new(zip::ZipWriter::new_stream(output)))
+        } else {
+            ArchiveBuilder::Tar(tar::Builder::new(output))
+        };
+
+        Ok(ArchiveWriter {
+            builder,
+            entry: None,
+            manifests: HashMap::new(),
+        })
+    }
+
+    /// Adds a folder
+    pub fn add_folder(&mut self, folder: &str) -> Result<(), String> {
+        match &mut self.builder {
+            ArchiveBuilder::Tar(builder) => {
+                let mut header = new_tar_header(tar::EntryType::Directory, 0o755, 0);
+
+                builder
+                    .append_data(&mut header, folder, std::io::empty())
+                    .map_err(|e| e.to_string())
+            }
+            ArchiveBuilder::Zip(zip) => zip
+                .add_directory(folder, zip_file_options())
+                .map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Starts writing a file, inside a folder of the archive (empty for the root)
+    /// The size must be known, since tar archives store it before the contents.
+    pub fn start_file(&mut self, folder: &str, name: &str, size: u64) -> Result<(), String> {
+        let path = get_archive_entry_path(folder, name);
+
+        match &mut self.builder {
+            ArchiveBuilder::Tar(builder) => {
+                let mut header = new_tar_header(tar::EntryType::Regular, 0o644, size);
+
+                set_tar_header_path(builder, &mut header, &path)?;
+                header.set_cksum();
+
+                builder
+                    .get_mut()
+                    .write_all(header.as_bytes())
+                    .map_err(|e| e.to_string())?;
+            }
+            ArchiveBuilder::Zip(zip) => {
+                zip.start_file(path, zip_file_options())
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+
+        self.entry = Some(ArchiveEntry {
+            folder: folder.to_string(),
+            name: name.to_string(),
+            size,
+            written: 0,
+            hasher: Sha256::new(),
+        });
+
+        Ok(())
+    }
+
+    /// Writes a chunk of the file being written
+    pub fn write_file_chunk(&mut self, chunk: &[u8]) -> Result<(), String> {
+        let entry = match &mut self.entry {
+            Some(e) => e,
+            None => {
+                return Err("No file was started in the archive".to_string());
+            }
+        };
+
+        entry.written += chunk.len() as u64;
+
+        if entry.written > entry.size {
+            let path = get_archive_entry_path(&entry.folder, &entry.name);
+            let size = entry.size;
+            return Err(format!("The file {path} is larger than {size} bytes"));
+        }
+
+        entry.hasher.update(chunk);
+
+        match &mut self.builder {
+            ArchiveBuilder::Tar(builder) => builder.get_mut().write_all(chunk),
+            ArchiveBuilder::Zip(zip) => zip.write_all(chunk),
+        }
+        .map_err(|e| e.to_string())
+    }
+
+    /// Finishes the file being written, adding it to the manifest of its folder
+    pub fn finish_file(&mut self) -> Result<(), String> {
+        let entry = match self.entry.take() {
+            Some(e) => e,
+            None => {
+                return Err("No file was started in the archive".to_string());
+            }
+        };
+
+        if entry.written != entry.size {
+            let path = get_archive_entry_path(&entry.folder, &entry.name);
+            let size = entry.size;
+            let written = entry.written;
+            return Err(format!(
+                "The file {path} is incomplete. Expected {size} bytes, but got {written} bytes"
+            ));
+        }
+
+        if let ArchiveBuilder::Tar(builder) = &mut self.builder {
+            // Tar entries are padded to the block size
+            let padding = (512 - (entry.size % 512)) % 512;
+
+            builder
+                .get_mut()
+                .write_all(&vec![0; padding as usize])
+                .map_err(|e| e.to_string())?;
+        }
+
+        self.manifests
+            .entry(entry.folder)
+            .or_default()
+            .push(ExportManifestFile {
+                name: entry.name,
+                size: entry.size,
+                sha256: format_checksum(&entry.hasher.finalize()),
+            });
+
+        Ok(())
+    }
+
+    /// Writes a file with all its contents
+    pub fn write_file(&mut self, folder: &str, name: &str, contents: &[u8]) -> Result<(), String> {
+        self.start_file(folder, name, contents.len() as u64)?;
+        self.write_file_chunk(contents)?;
+        self.finish_file()
+    }
+
+    /// Writes the manifest of a folder, with the size and checksum of every file written into it
+    pub fn write_manifest(&mut self, folder: &str) -> Result<(), String> {
+        let mut files = self.manifests.remove(folder).unwrap_or_default();
+
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let manifest_str = serde_json::to_string(&ExportManifest { files }).unwrap();
+
+        self.write_file(folder, EXPORT_MANIFEST_FILE, manifest_str.as_bytes())?;
+
+        self.manifests.remove(folder);
+
+        Ok(())
+    }
+
+    /// Finishes the archive
+    pub fn finish(self) -> Result<(), String> {
+        match self.builder {
+            ArchiveBuilder::Tar(builder) => {
+                builder.into_inner().map_err(|e| e.to_string())?.finish()
+            }
+            ArchiveBuilder::Zip(zip) => zip
+                .finish()
+                .map_err(|e| e.to_string())?
+                .into_inner()
+                .finish(),
+        }
+    }
+}
+
+fn get_archive_entry_path(folder: &str, name: &str) -> String {
+    if folder.is_empty() {
+        name.to_string()
+    } else {
+        format!("{folder}/{name}")
+    }
+}
+
+fn new_tar_header(entry_type: tar::EntryType, mode: u32, size: u64) -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+
+    header.set_entry_type(entry_type);
+    header.set_mode(mode);
+    header.set_size(size);
+    header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+
+    header
+}
+
+/// Sets the path of a tar header
+/// Paths too long for the header are written first as a GNU long name entry,
+/// the same way tar::Builder::append_data does.
+fn set_tar_header_path(
+    builder: &mut tar::Builder<ArchiveOutput>,
+    header: &mut tar::Header,
+    path: &str,
+) -> Result<(), String> {
+    let set_path_err = match header.set_path(path) {
+        Ok(_) => return Ok(()),
+        Err(e) => e,
+    };
+
+    let path_bytes = path.as_bytes();
+    let name_field = &mut header.as_old_mut().name;
+
+    if path_bytes.len() < name_field.len() {
+        return Err(set_path_err.to_string());
+    }
+
+    // The truncated path is ignored by the readers, since the long name entry takes precedence
+    let name_field_len = name_field.len();
+    name_field.copy_from_slice(&path_bytes[..name_field_len]);
+
+    let mut long_name_header = new_tar_header(
+        tar::EntryType::GNULongName,
+        0o644,
+        path_bytes.len() as u64 + 1,
+    );
+
+    let long_name_field = &mut long_name_header.as_old_mut().name;
+    long_name_field[..TAR_LONG_NAME_PATH.len()].copy_from_slice(TAR_LONG_NAME_PATH.as_bytes());
+    long_name_header.set_cksum();
+
+    // Null-terminated path
+    let long_name_data = [path_bytes, &[0]].concat();
+
+    builder
+        .append(&long_name_header, long_name_data.as_slice())
+        .map_err(|e| e.to_string())
+}
+
+fn zip_file_options() -> zip::write::SimpleFileOptions {
+    // Media assets are already compressed
+    zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Stored)
+        .large_file(true)
+}
+
+fn get_encryptor(encryption: &ArchiveEncryption) -> Result<age::Encryptor, String> {
+    match encryption {
+        ArchiveEncryption::Passphrase(p) => Ok(age::Encryptor::with_user_passphrase(p.clone())),
+        ArchiveEncryption::Recipients(recipients_list) => {
+            let mut recipients: Vec<age::x25519::Recipient> = Vec::new();
+
+            for r in recipients_list {
+                let recipient = r
+                    .parse::<age::x25519::Recipient>()
+                    .map_err(|e| format!("Invalid recipient {r}: {e}"))?;
+
+                recipients.push(recipient);
+            }
+
+            age::Encryptor::with_recipients(recipients.iter().map(|r| r as &dyn age::Recipient))
+                .map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Extracts an archive (tar or zip) into a folder, decrypting it if necessary
+/// The format is detected from the contents.
+pub fn extract_archive(
+    archive: &str,
+    folder: &str,
+    decryption: Option<&ArchiveDecryption>,
+) -> Result<(), String> {
+    std::fs::create_dir_all(folder).map_err(|e| e.to_string())?;
+
+    let mut reader: Box<dyn BufRead> = if archive == ARCHIVE_STDIO {
+        Box::new(BufReader::new(std::io::stdin().lock()))
+    } else {
+        Box::new(BufReader::new(
+            File::open(archive).map_err(|e| e.to_string())?,
+        ))
+    };
+
+    let is_encrypted = reader
+        .fill_buf()
+        .map_err(|e| e.to_string())?
+        .starts_with(AGE_MAGIC);
+
+    if is_encrypted {
+        let identities: Vec<Box<dyn age::Identity>> = match decryption {
+            Some(ArchiveDecryption::Passphrase(p)) => {
+                vec![Box::new(age::scrypt::Identity::new(p.clone()))]
+            }
+            Some(ArchiveDecryption::IdentityFile(identity_file)) => {
+                age::IdentityFile::from_file(identity_file.clone())
+                    .map_err(|e| format!("Could not read identity file {identity_file}: {e}"))?
+                    .into_identities()
+                    .map_err(|e| e.to_string())?
+                    .into_iter()
+                    .map(|i| i as Box<dyn age::Identity>)
+                    .collect()
+            }
+            None => {
+                return Err(format!("The archive is encrypted. Use the --identity option or set the {ARCHIVE_PASSPHRASE_ENV} environment variable to decrypt it."));
+            }
+        };
+
+        let decryptor = age::Decryptor::new_buffered(reader).map_err(|e| e.to_string())?;
+        let decrypted_reader = decryptor
+            .decrypt(identities.iter().map(|i| i.as_ref()))
+            .map_err(|e| e.to_string())?;
+
+        reader = Box::new(BufReader::new(decrypted_reader));
+    }
+
+    let is_zip = reader
+        .fill_buf()
+        .map_err(|e| e.to_string())?
+        .starts_with(ZIP_MAGIC);
+
+    if !is_zip {
+        return tar::Archive::new(reader)
+            .unpack(folder)
+            .map_err(|e| e.to_string());
+    }
+
+    if !is_encrypted && archive != ARCHIVE_STDIO {
+        let file = File::open(archive).map_err(|e| e.to_string())?;
+        let mut zip = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
+        return zip.extract(folder).map_err(|e| e.to_string());
+    }
+
+    // Zip archives need to seek, so they are written into a temporary file first
+
+    let zip_temp_file = folder.to_string() + ".zip";
+
+    let zip_res = File::create(&zip_temp_file)
+        .and_then(|mut f| std::io::copy(&mut reader, &mut f))
+        .map_err(|e| e.to_string())
+        .and_then(|_| File::open(&zip_temp_file).map_err(|e| e.to_string()))
+        .and_then(|f| zip::ZipArchive::new(f).map_err(|e| e.to_string()))
+        .and_then(|mut zip| zip.extract(folder).map_err(|e| e.to_string()));
+
+    let _ = std::fs::remove_file(&zip_temp_file);
+
+    zip_res
+}
+
+/// Finds the folder containing the metadata file in an extracted archive
+/// Archives created by packing the export folder itself contain a single top level folder.
+pub fn find_extracted_archive_root(folder: &str) -> String {
+    if Path::new(folder).join("metadata.json").exists() {
+        return folder.to_string();
+    }
+
+    let entries: Vec<std::path::PathBuf> = match std::fs::read_dir(folder) {
+        Ok(r) => r.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+        Err(_) => {
+            return folder.to_string();
+        }
+    };
+
+    if entries.len() == 1 && entries[0].is_dir() && entries[0].join("metadata.json").exists() {
+        return entries[0].to_str().unwrap().to_string();
+    }
+
+    folder.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    #[test]
+    fn test_archive_writer_long_tar_path() {
+        let archive = std::env::temp_dir()
+            .join(format!("pmv-cli-test-{}.tar", std::process::id()))
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let folder = "media_1";
+        let long_name = "a".repeat(150) + ".txt";
+
+        let mut writer = ArchiveWriter::create(&archive, None).unwrap();
+        writer.add_folder(folder).unwrap();
+        writer.write_file(folder, &long_name, b"long").unwrap();
+        writer.write_file(folder, "short.txt", b"short").unwrap();
+        writer.finish().unwrap();
+
+        let mut entries: Vec<(String, String)> = Vec::new();
+        let mut tar_archive = tar::Archive::new(File::open(&archive).unwrap());
+
+        for entry in tar_archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().to_str().unwrap().to_string();
+            let mut contents = String::new();
+            entry.read_to_string(&mut contents).unwrap();
+            entries.push((path, contents));
+        }
+
+        std::fs::remove_file(&archive).unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                ("media_1".to_string(), "".to_string()),
+                (format!("media_1/{long_name}"), "long".to_string()),
+                ("media_1/short.txt".to_string(), "short".to_string()),
+            ]
+        );
+    }
+}
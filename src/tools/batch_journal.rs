@@ -0,0 +1,111 @@
+// Journal of batch operations, to resume them after an interruption
+
+use std::{
+    collections::HashSet,
+    fs::{File, OpenOptions},
+    io::Write,
+};
+
+use crate::models::{BatchJournalEntry, BatchJournalHeader};
+
+/// Journal of a batch operation, in JSON Lines format
+/// The first line identifies the operation, and the rest are the completed media assets
+pub struct BatchJournal {
+    path: String,
+    file: File,
+    completed: HashSet<u64>,
+}
+
+impl BatchJournal {
+    /// Opens a journal, creating it if it does not exist
+    /// If it exists, it must belong to the same operation, vault and filter
+    pub fn open(path: &str, header: &BatchJournalHeader) -> Result<BatchJournal, String> {
+        let mut completed: HashSet<u64> = HashSet::new();
+        let mut header_found = false;
+
+        if std::path::Path::new(path).exists() {
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| format!("Could not read journal {path}: {e}"))?;
+
+            let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+
+            if let Some(first_line) = lines.next() {
+                let existing_header: BatchJournalHeader = serde_json::from_str(first_line)
+                    .map_err(|e| format!("Invalid journal {path}: {e}"))?;
+
+                if existing_header.operation != header.operation
+                    || existing_header.argument != header.argument
+                {
+                    let operation = existing_header.operation;
+                    let argument = existing_header.argument;
+                    return Err(format!(
+                        "The journal {path} belongs to a different operation: {operation} {argument}"
+                    ));
+                }
+
+                if existing_header.vault != header.vault {
+                    let vault = existing_header.vault;
+                    return Err(format!(
+                        "The journal {path} belongs to a different vault: {vault}"
+                    ));
+                }
+
+                if existing_header != *header {
+                    let filter = existing_header.to_filter_string();
+                    return Err(format!(
+                        "The journal {path} belongs to a different filter: {filter}"
+                    ));
+                }
+
+                header_found = true;
+            }
+
+            for line in lines {
+                // The last line may be incomplete, if the process was killed while writing it
+                if let Ok(entry) = serde_json::from_str::<BatchJournalEntry>(line) {
+                    completed.insert(entry.media_id);
+                }
+            }
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Could not open journal {path}: {e}"))?;
+
+        if !header_found {
+            writeln!(file, "{}", serde_json::to_string(header).unwrap())
+                .map_err(|e| format!("Could not write journal {path}: {e}"))?;
+        }
+
+        Ok(BatchJournal {
+            path: path.to_string(),
+            file,
+            completed,
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Checks if a media asset was already completed
+    pub fn is_completed(&self, media_id: u64) -> bool {
+        self.completed.contains(&media_id)
+    }
+
+    /// Records a media asset as completed
+    pub fn record_completed(&mut self, media_id: u64) -> Result<(), String> {
+        let path = &self.path;
+        let line = serde_json::to_string(&BatchJournalEntry { media_id }).unwrap();
+
+        writeln!(self.file, "{line}")
+            .and_then(|_| self.file.flush())
+            .map_err(|e| format!("Could not write journal {path}: {e}"))?;
+
+        self.completed.insert(media_id);
+
+        Ok(())
+    }
+}
@@ -85,8 +85,7 @@ pub fn print_table(head: &[String], body: &Vec<Vec<String>>, std_err: bool) {
 
         // Evenly share the spare size
 
-        if overflow_count > 0 {
-            let spare_size_split = spare_size / overflow_count;
+        if let Some(spare_size_split) = spare_size.checked_div(overflow_count) {
             let new_allowed_size = col_allowed_size + spare_size_split;
 
             for (i, max_size) in max_sizes.iter().enumerate() {
@@ -165,7 +164,7 @@ fn sub_string(original_str: &str, skip: usize, limit: usize) -> String {
         "".to_string()
     } else if skip == 0 {
         if limit >= original_str.width() {
-            return original_str.to_string();
+            original_str.to_string()
         } else {
             let mut res = "".to_string();
             let mut res_width: usize = 0;
@@ -181,7 +180,7 @@ fn sub_string(original_str: &str, skip: usize, limit: usize) -> String {
                 res_width += c_width;
             }
 
-            return res;
+            res
         }
     } else {
         let mut skipped_str = "".to_string();
@@ -202,7 +201,7 @@ fn sub_string(original_str: &str, skip: usize, limit: usize) -> String {
         skipped_str = original_str.chars().skip(chars_count_skip).collect();
 
         if limit >= skipped_str.width() {
-            return skipped_str;
+            skipped_str
         } else {
             let mut res = "".to_string();
 
@@ -217,7 +216,7 @@ fn sub_string(original_str: &str, skip: usize, limit: usize) -> String {
                 res = new_res;
             }
 
-            return res;
+            res
         }
     }
 }
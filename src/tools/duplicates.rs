@@ -0,0 +1,319 @@
+// Duplicate media detection
+
+use std::{collections::HashMap, hash::Hash};
+
+use super::normalize_text_fuzzy;
+
+/// Criteria to consider two media assets as duplicates
+/// The media type must always match
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DuplicateCriteria {
+    /// Similar title (compared ignoring case, accents, punctuation and repeated spaces)
+    pub title: bool,
+
+    /// Identical duration, in seconds
+    pub duration: bool,
+
+    /// Identical width and height
+    pub dimensions: bool,
+
+    /// Identical size of the original asset
+    pub size: bool,
+}
+
+/// Way of confirming duplicates, by hashing the downloaded assets
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DuplicateVerification {
+    Thumbnail,
+    Original,
+}
+
+/// Parses the duplicate criteria, as a list separated by commas
+pub fn parse_duplicate_criteria(s: &str) -> Result<DuplicateCriteria, String> {
+    let mut criteria = DuplicateCriteria {
+        title: false,
+        duration: false,
+        dimensions: false,
+        size: false,
+    };
+
+    for c in s.split(',').map(|c| c.trim()).filter(|c| !c.is_empty()) {
+        match c.to_lowercase().as_str() {
+            "title" | "name" => {
+                criteria.title = true;
+            }
+            "duration" | "length" => {
+                criteria.duration = true;
+            }
+            "dimensions" | "resolution" => {
+                criteria.dimensions = true;
+            }
+            "size" => {
+                criteria.size = true;
+            }
+            _ => {
+                return Err(format!(
+                    "Duplicate criteria not recognized: {c} | Valid ones are: title, duration, dimensions or size"
+                ));
+            }
+        }
+    }
+
+    if !criteria.title && !criteria.duration && !criteria.dimensions && !criteria.size {
+        return Err("At least one duplicate criteria must be specified".to_string());
+    }
+
+    Ok(criteria)
+}
+
+pub fn parse_duplicate_verification(s: &str) -> Result<DuplicateVerification, String> {
+    match s.to_lowercase().as_str() {
+        "thumbnail" => Ok(DuplicateVerification::Thumbnail),
+        "original" => Ok(DuplicateVerification::Original),
+        _ => Err(format!(
+            "Verification method not recognized: {s} | Valid ones are: thumbnail or original"
+        )),
+    }
+}
+
+/// Gets the key to compare titles of possible duplicates
+/// Returns None for titles without any letters or digits, since they cannot be compared
+pub fn get_duplicate_title_key(title: &str) -> Option<String> {
+    let normalized: String = normalize_text_fuzzy(title)
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+
+    let key = normalized
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    if key.is_empty() {
+        None
+    } else {
+        Some(key)
+    }
+}
+
+/// Gets the similarity of two title keys, from 0 (different) to 1 (identical)
+/// Based on the Levenshtein distance, relative to the length of the longest title
+pub fn get_duplicate_title_similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let max_len = a.len().max(b.len());
+
+    if max_len == 0 {
+        return 1.0;
+    }
+
+    let mut prev_row: Vec<usize> = (0..=b.len()).collect();
+    let mut row: Vec<usize> = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        row[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let substitution_cost = if ca == cb { 0 } else { 1 };
+
+            row[j + 1] = (prev_row[j] + substitution_cost)
+                .min(prev_row[j + 1] + 1)
+                .min(row[j] + 1);
+        }
+
+        std::mem::swap(&mut prev_row, &mut row);
+    }
+
+    1.0 - (prev_row[b.len()] as f64 / max_len as f64)
+}
+
+/// Splits groups of possible duplicates by title similarity
+/// Two media assets end up in the same group if there is a chain of similar titles joining them
+/// Media assets without a title key are discarded, and so are the groups left with a single element
+pub fn refine_duplicate_groups_by_title<F>(
+    groups: Vec<Vec<u64>>,
+    get_title_key: F,
+    min_similarity: f64,
+) -> Vec<Vec<u64>>
+where
+    F: Fn(u64) -> Option<String>,
+{
+    let mut result: Vec<Vec<u64>> = Vec::new();
+
+    for group in groups {
+        let mut titles: Vec<(u64, String, usize)> = group
+            .into_iter()
+            .filter_map(|id| get_title_key(id).map(|k| (id, k.clone(), k.chars().count())))
+            .collect();
+
+        // Sorted by length, so the comparisons can stop when the lengths are too different
+        titles.sort_by_key(|t| t.2);
+
+        // Union-find of the indexes of the titles
+        let mut parents: Vec<usize> = (0..titles.len()).collect();
+
+        for i in 0..titles.len() {
+            for j in (i + 1)..titles.len() {
+                if (titles[i].2 as f64) < (titles[j].2 as f64) * min_similarity {
+                    break;
+                }
+
+                if get_duplicate_title_similarity(&titles[i].1, &titles[j].1) >= min_similarity {
+                    let root_i = find_union_root(&mut parents, i);
+                    let root_j = find_union_root(&mut parents, j);
+                    parents[root_j] = root_i;
+                }
+            }
+        }
+
+        let mut sub_groups: HashMap<usize, Vec<u64>> = HashMap::new();
+
+        for (i, (media_id, _, _)) in titles.iter().enumerate() {
+            let root = find_union_root(&mut parents, i);
+
+            match sub_groups.get_mut(&root) {
+                Some(sub_group) => {
+                    sub_group.push(*media_id);
+                }
+                None => {
+                    sub_groups.insert(root, vec![*media_id]);
+                }
+            }
+        }
+
+        for mut sub_group in sub_groups.into_values() {
+            if sub_group.len() > 1 {
+                sub_group.sort();
+                result.push(sub_group);
+            }
+        }
+    }
+
+    // Oldest media assets first
+
+    result.sort_by_key(|g| g.iter().min().copied().unwrap_or(0));
+
+    result
+}
+
+/// Finds the root of an element of a union-find structure
+fn find_union_root(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+
+    while parents[root] != root {
+        root = parents[root];
+    }
+
+    parents[i] = root;
+
+    root
+}
+
+/// Splits groups of possible duplicates by a key
+/// Media assets without a key are discarded, and so are the groups left with a single element
+pub fn refine_duplicate_groups<K, F>(groups: Vec<Vec<u64>>, get_key: F) -> Vec<Vec<u64>>
+where
+    K: Hash + Eq,
+    F: Fn(u64) -> Option<K>,
+{
+    let mut result: Vec<Vec<u64>> = Vec::new();
+
+    for group in groups {
+        let mut sub_groups: HashMap<K, Vec<u64>> = HashMap::new();
+
+        for media_id in group {
+            if let Some(key) = get_key(media_id) {
+                match sub_groups.get_mut(&key) {
+                    Some(sub_group) => {
+                        sub_group.push(media_id);
+                    }
+                    None => {
+                        sub_groups.insert(key, vec![media_id]);
+                    }
+                }
+            }
+        }
+
+        for mut sub_group in sub_groups.into_values() {
+            if sub_group.len() > 1 {
+                sub_group.sort();
+                result.push(sub_group);
+            }
+        }
+    }
+
+    // Oldest media assets first
+
+    result.sort_by_key(|g| g.iter().min().copied().unwrap_or(0));
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_duplicate_title_key() {
+        assert_eq!(
+            get_duplicate_title_key("  Café -- Crème!! (1) "),
+            Some("cafe creme 1".to_string())
+        );
+        assert_eq!(get_duplicate_title_key("!!! ..."), None);
+    }
+
+    #[test]
+    fn test_get_duplicate_title_similarity() {
+        assert_eq!(
+            get_duplicate_title_similarity("beach day", "beach day"),
+            1.0
+        );
+        assert_eq!(get_duplicate_title_similarity("", ""), 1.0);
+        assert_eq!(get_duplicate_title_similarity("abc", "xyz"), 0.0);
+        assert_eq!(
+            get_duplicate_title_similarity("beach day 1", "beach day 2"),
+            1.0 - 1.0 / 11.0
+        );
+        assert_eq!(
+            get_duplicate_title_similarity("beach", "beach day"),
+            5.0 / 9.0
+        );
+    }
+
+    #[test]
+    fn test_refine_duplicate_groups_by_title() {
+        let titles: HashMap<u64, &str> = HashMap::from([
+            (1, "summer holidays 2023"),
+            (2, "summer holidays 2024"),
+            (3, "summer holidays"),
+            (4, "winter trip"),
+            (5, "winter trip"),
+            (6, "!!!"),
+            (7, "!!!"),
+            (8, "summer holidays 2025"),
+        ]);
+
+        let get_key = |id: u64| titles.get(&id).and_then(|t| get_duplicate_title_key(t));
+
+        assert_eq!(
+            refine_duplicate_groups_by_title(vec![(1..=7).collect()], get_key, 0.9),
+            vec![vec![1, 2], vec![4, 5]]
+        );
+
+        // Groups are joined through chains of similar titles, but not across different groups
+        assert_eq!(
+            refine_duplicate_groups_by_title(vec![vec![1, 2, 4], vec![5, 8]], get_key, 0.9),
+            vec![vec![1, 2]]
+        );
+        assert_eq!(
+            refine_duplicate_groups_by_title(vec![vec![1, 2, 8]], get_key, 0.9),
+            vec![vec![1, 2, 8]]
+        );
+
+        assert_eq!(
+            refine_duplicate_groups_by_title(vec![(1..=5).collect()], get_key, 0.7),
+            vec![vec![1, 2, 3], vec![4, 5]]
+        );
+    }
+}
@@ -0,0 +1,68 @@
+// Export manifest utilities
+
+use std::{fs::File, io::Read, path::Path};
+
+use sha2::{Digest, Sha256};
+
+use crate::models::{ExportManifest, ExportManifestFile};
+
+/// Name of the manifest file, stored in every export folder
+pub const EXPORT_MANIFEST_FILE: &str = "manifest.json";
+
+/// Computes the size and the SHA-256 checksum (hex) of a file
+pub fn compute_file_checksum(path: &Path) -> Result<(u64, String), String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 64 * 1024];
+    let mut size: u64 = 0;
+
+    loop {
+        let n = file.read(&mut buf).map_err(|e| e.to_string())?;
+
+        if n == 0 {
+            break;
+        }
+
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+
+    Ok((size, format_checksum(&hasher.finalize())))
+}
+
+/// Formats a SHA-256 checksum as hex
+pub fn format_checksum(checksum: &[u8]) -> String {
+    checksum.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Writes the manifest of an export folder, with the size and checksum of every file in it
+/// Sub-folders are not included, since they contain their own manifest.
+pub fn write_export_manifest(folder: &str) -> Result<(), String> {
+    let mut entries: Vec<std::fs::DirEntry> = std::fs::read_dir(folder)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    entries.sort_by_key(|e| e.file_name());
+
+    let mut manifest = ExportManifest { files: Vec::new() };
+
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        if name == EXPORT_MANIFEST_FILE || !entry.path().is_file() {
+            continue;
+        }
+
+        let (size, sha256) = compute_file_checksum(&entry.path())?;
+
+        manifest
+            .files
+            .push(ExportManifestFile { name, size, sha256 });
+    }
+
+    let manifest_str = serde_json::to_string(&manifest).unwrap();
+
+    std::fs::write(Path::new(folder).join(EXPORT_MANIFEST_FILE), manifest_str)
+        .map_err(|e| e.to_string())
+}
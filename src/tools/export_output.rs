@@ -0,0 +1,124 @@
+// Export output (folder or archive)
+
+use std::path::Path;
+
+use super::{
+    do_get_download_request, do_get_download_request_archive, write_export_manifest, ArchiveWriter,
+    ProgressReceiver, RequestError, VaultURI,
+};
+
+/// Output of an export
+/// Files are placed into folders, relative to the root of the output (an empty folder is the root)
+pub enum ExportOutput {
+    /// Files are written into a folder
+    Folder(String),
+
+    /// Files are written into an archive, as soon as they are downloaded
+    Archive(Box<ArchiveWriter>),
+}
+
+impl ExportOutput {
+    /// Gets the path of a file in the output, to display it
+    pub fn get_file_path(&self, folder: &str, name: &str) -> String {
+        match self {
+            ExportOutput::Folder(root) => Path::new(root)
+                .join(folder)
+                .join(name)
+                .to_str()
+                .unwrap()
+                .to_string(),
+            ExportOutput::Archive(_) => {
+                if folder.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{folder}/{name}")
+                }
+            }
+        }
+    }
+
+    /// Creates a folder in the output
+    pub async fn create_folder(&mut self, folder: &str) -> Result<(), String> {
+        match self {
+            ExportOutput::Folder(root) => tokio::fs::create_dir_all(Path::new(root).join(folder))
+                .await
+                .map_err(|e| e.to_string()),
+            ExportOutput::Archive(archive) => {
+                if folder.is_empty() {
+                    return Ok(());
+                }
+
+                archive.add_folder(folder)
+            }
+        }
+    }
+
+    /// Downloads a file into the output
+    pub async fn download(
+        &mut self,
+        uri: &VaultURI,
+        download_path: String,
+        folder: &str,
+        name: &str,
+        debug: bool,
+        progress_receiver: &mut dyn ProgressReceiver,
+    ) -> Result<(), RequestError> {
+        match self {
+            ExportOutput::Folder(_) => {
+                let file_path = self.get_file_path(folder, name);
+
+                do_get_download_request(uri, download_path, file_path, debug, progress_receiver)
+                    .await
+            }
+            ExportOutput::Archive(archive) => {
+                do_get_download_request_archive(
+                    uri,
+                    download_path,
+                    archive,
+                    folder,
+                    name,
+                    debug,
+                    progress_receiver,
+                )
+                .await
+            }
+        }
+    }
+
+    /// Writes a file into the output
+    pub async fn write_file(
+        &mut self,
+        folder: &str,
+        name: &str,
+        contents: &[u8],
+    ) -> Result<(), String> {
+        match self {
+            ExportOutput::Folder(_) => {
+                let file_path = self.get_file_path(folder, name);
+
+                tokio::fs::write(file_path, contents)
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+            ExportOutput::Archive(archive) => archive.write_file(folder, name, contents),
+        }
+    }
+
+    /// Writes the manifest of a folder in the output
+    pub fn write_manifest(&mut self, folder: &str) -> Result<(), String> {
+        match self {
+            ExportOutput::Folder(root) => {
+                write_export_manifest(Path::new(root).join(folder).to_str().unwrap())
+            }
+            ExportOutput::Archive(archive) => archive.write_manifest(folder),
+        }
+    }
+
+    /// Finishes the output, after every file is written
+    pub fn finish(self) -> Result<(), String> {
+        match self {
+            ExportOutput::Folder(_) => Ok(()),
+            ExportOutput::Archive(archive) => archive.finish(),
+        }
+    }
+}
@@ -0,0 +1,391 @@
+// Static HTML gallery, generated from an export folder
+
+use std::path::Path;
+
+use crate::models::{ImageNote, MediaMetadataExport};
+
+use super::{duration_to_string, write_export_manifest, EXPORT_MANIFEST_FILE};
+
+pub const HTML_GALLERY_INDEX_FILE: &str = "index.html";
+
+const GALLERY_IMAGE_EXTENSIONS: &[&str] =
+    &["jpg", "jpeg", "png", "gif", "webp", "bmp", "svg", "avif"];
+
+const GALLERY_AUDIO_EXTENSIONS: &[&str] = &["mp3", "wav", "ogg", "flac", "aac", "m4a", "opus"];
+
+const GALLERY_STYLE: &str = r#"
+body { font-family: sans-serif; margin: 0; padding: 1rem 2rem; background: #1e1e1e; color: #eee; }
+a { color: #8ab4f8; }
+.gallery { display: flex; flex-wrap: wrap; gap: 1rem; }
+.gallery-item { width: 200px; text-decoration: none; color: #eee; }
+.gallery-item .thumb { width: 200px; height: 150px; background: #333; display: flex; align-items: center; justify-content: center; overflow: hidden; }
+.gallery-item .thumb img { max-width: 100%; max-height: 100%; }
+.gallery-item .title { margin-top: 0.25rem; word-break: break-word; }
+.media-container { position: relative; display: inline-block; max-width: 100%; }
+.media-container img, .media-container video { max-width: 100%; max-height: 80vh; display: block; }
+.image-note { position: absolute; border: 1px solid #fff; box-shadow: 0 0 2px #000; }
+.image-note span { display: none; position: absolute; top: 100%; left: 0; background: #000; color: #fff; padding: 0.25rem; min-width: 150px; white-space: pre-wrap; }
+.image-note:hover span { display: block; }
+.tags span { display: inline-block; background: #333; padding: 0.1rem 0.5rem; margin: 0.1rem; border-radius: 0.5rem; }
+.description, .ext-desc { white-space: pre-wrap; }
+"#;
+
+const GALLERY_NOTES_SCRIPT: &str = r#"
+function placeImageNotes() {
+    var img = document.getElementById("media-image");
+    if (!img || !img.naturalWidth) return;
+    var scale = img.clientWidth / img.naturalWidth;
+    var notes = document.getElementsByClassName("image-note");
+    for (var i = 0; i < notes.length; i++) {
+        var n = notes[i];
+        n.style.left = (n.dataset.x * scale) + "px";
+        n.style.top = (n.dataset.y * scale) + "px";
+        n.style.width = (n.dataset.w * scale) + "px";
+        n.style.height = (n.dataset.h * scale) + "px";
+    }
+}
+window.addEventListener("load", placeImageNotes);
+window.addEventListener("resize", placeImageNotes);
+"#;
+
+const GALLERY_CHAPTERS_SCRIPT: &str = r#"
+function seekMedia(time) {
+    var media = document.getElementById("media-player");
+    if (media) { media.currentTime = time; media.play(); }
+    return false;
+}
+"#;
+
+/// Writes a browsable HTML gallery into an export folder
+/// An index page is written into the folder, and a page for each media folder
+/// Returns the number of media pages written
+pub fn write_html_gallery(
+    folder: &str,
+    title: &str,
+    media_folders: &[String],
+) -> Result<usize, String> {
+    let mut items_html = String::new();
+    let mut pages_count: usize = 0;
+
+    for media_folder in media_folders {
+        let media_path = Path::new(folder).join(media_folder);
+
+        let metadata: MediaMetadataExport =
+            match std::fs::read_to_string(media_path.join("metadata.json")) {
+                Ok(s) => serde_json::from_str(&s).map_err(|e| e.to_string())?,
+                Err(_) => {
+                    // The media could not be exported
+                    continue;
+                }
+            };
+
+        write_html_gallery_media_page(media_path.to_str().unwrap(), &metadata)?;
+        pages_count += 1;
+
+        let media_title = metadata.title.clone().unwrap_or_default();
+
+        let thumbnail = match (&metadata.thumbnail, &metadata.original) {
+            (Some(t), _) => Some(t.clone()),
+            (None, Some(o)) if has_gallery_extension(o, GALLERY_IMAGE_EXTENSIONS) => {
+                Some(o.clone())
+            }
+            _ => None,
+        };
+
+        let thumbnail_html = match thumbnail {
+            Some(t) => format!(
+                "<img src=\"{}\" alt=\"\" loading=\"lazy\">",
+                gallery_href(&[media_folder, &t])
+            ),
+            None => "".to_string(),
+        };
+
+        items_html.push_str(&format!(
+            "<a class=\"gallery-item\" href=\"{}\"><div class=\"thumb\">{}</div><div class=\"title\">{}</div></a>\n",
+            gallery_href(&[media_folder, HTML_GALLERY_INDEX_FILE]),
+            thumbnail_html,
+            escape_html(&media_title),
+        ));
+    }
+
+    let body = format!(
+        "<h1>{}</h1>\n<p>{} media assets</p>\n<div class=\"gallery\">\n{}</div>\n",
+        escape_html(title),
+        pages_count,
+        items_html
+    );
+
+    std::fs::write(
+        Path::new(folder).join(HTML_GALLERY_INDEX_FILE),
+        make_html_page(title, &body, ""),
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(pages_count)
+}
+
+/// Writes the page of a media asset, inside its export folder
+fn write_html_gallery_media_page(
+    folder: &str,
+    metadata: &MediaMetadataExport,
+) -> Result<(), String> {
+    let title = metadata.title.clone().unwrap_or_default();
+
+    let mut body = format!(
+        "<p><a href=\"../{HTML_GALLERY_INDEX_FILE}\">&larr; Back</a></p>\n<h1>{}</h1>\n",
+        escape_html(&title)
+    );
+
+    let mut scripts = String::new();
+
+    // Media
+
+    if let Some(original) = &metadata.original {
+        let original_href = gallery_href(&[original]);
+
+        if has_gallery_extension(original, GALLERY_IMAGE_EXTENSIONS) {
+            let mut notes_html = String::new();
+
+            if let Some(notes_file) = &metadata.notes {
+                let notes: Vec<ImageNote> =
+                    match std::fs::read_to_string(Path::new(folder).join(notes_file)) {
+                        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
+                        Err(_) => Vec::new(),
+                    };
+
+                for note in notes.iter() {
+                    notes_html.push_str(&format!(
+                        "<div class=\"image-note\" data-x=\"{}\" data-y=\"{}\" data-w=\"{}\" data-h=\"{}\"><span>{}</span></div>\n",
+                        note.x, note.y, note.w, note.h,
+                        escape_html(&note.text)
+                    ));
+                }
+
+                if !notes.is_empty() {
+                    scripts.push_str(GALLERY_NOTES_SCRIPT);
+                }
+            }
+
+            body.push_str(&format!(
+                "<div class=\"media-container\"><a href=\"{original_href}\"><img id=\"media-image\" src=\"{original_href}\" alt=\"\"></a>\n{notes_html}</div>\n"
+            ));
+        } else {
+            let tag = if has_gallery_extension(original, GALLERY_AUDIO_EXTENSIONS) {
+                "audio"
+            } else {
+                "video"
+            };
+
+            let poster = match &metadata.thumbnail {
+                Some(t) if tag == "video" => format!(" poster=\"{}\"", gallery_href(&[t])),
+                _ => "".to_string(),
+            };
+
+            let mut tracks_html = String::new();
+
+            if let Some(subtitles) = &metadata.subtitles {
+                for subtitle in subtitles {
+                    let vtt_file = match write_subtitle_vtt(folder, &subtitle.file) {
+                        Ok(f) => f,
+                        Err(e_str) => {
+                            let sub_file = &subtitle.file;
+                            eprintln!("Warning: Skipped the subtitles {sub_file} in the HTML gallery. Error: {e_str}");
+                            continue;
+                        }
+                    };
+
+                    tracks_html.push_str(&format!(
+                        "<track kind=\"subtitles\" src=\"{}\" srclang=\"{}\" label=\"{}\">\n",
+                        gallery_href(&[&vtt_file]),
+                        escape_html(&subtitle.id),
+                        escape_html(&subtitle.name)
+                    ));
+                }
+            }
+
+            body.push_str(&format!(
+                "<div class=\"media-container\"><{tag} id=\"media-player\" src=\"{original_href}\"{poster} controls>\n{tracks_html}</{tag}></div>\n"
+            ));
+        }
+
+        body.push_str(&format!(
+            "<p><a href=\"{original_href}\" download>Download original</a></p>\n"
+        ));
+    }
+
+    // Chapters
+
+    if let Some(time_slices) = &metadata.time_slices {
+        if !time_slices.is_empty() {
+            body.push_str("<h2>Chapters</h2>\n<ul>\n");
+
+            for time_slice in time_slices {
+                body.push_str(&format!(
+                    "<li><a href=\"#\" onclick=\"return seekMedia({})\">{}</a> {}</li>\n",
+                    time_slice.time,
+                    duration_to_string(time_slice.time),
+                    escape_html(&time_slice.name)
+                ));
+            }
+
+            body.push_str("</ul>\n");
+            scripts.push_str(GALLERY_CHAPTERS_SCRIPT);
+        }
+    }
+
+    // Tags
+
+    if let Some(tags) = &metadata.tags {
+        if !tags.is_empty() {
+            body.push_str("<div class=\"tags\">");
+
+            for tag in tags {
+                body.push_str(&format!("<span>{}</span>", escape_html(tag)));
+            }
+
+            body.push_str("</div>\n");
+        }
+    }
+
+    // Description
+
+    if let Some(description) = &metadata.description {
+        if !description.is_empty() {
+            body.push_str(&format!(
+                "<h2>Description</h2>\n<div class=\"description\">{}</div>\n",
+                escape_html(description)
+            ));
+        }
+    }
+
+    if let Some(ext_desc_file) = &metadata.ext_desc {
+        if let Ok(ext_desc) = std::fs::read_to_string(Path::new(folder).join(ext_desc_file)) {
+            body.push_str(&format!(
+                "<h2>Extended description</h2>\n<div class=\"ext-desc\">{}</div>\n",
+                escape_html(&ext_desc)
+            ));
+        }
+    }
+
+    // Extra audio tracks and attachments
+
+    if let Some(audios) = &metadata.audios {
+        if !audios.is_empty() {
+            body.push_str("<h2>Audio tracks</h2>\n<ul>\n");
+
+            for audio in audios {
+                body.push_str(&format!(
+                    "<li><a href=\"{}\">{}</a></li>\n",
+                    gallery_href(&[&audio.file]),
+                    escape_html(&audio.name)
+                ));
+            }
+
+            body.push_str("</ul>\n");
+        }
+    }
+
+    if let Some(attachments) = &metadata.attachments {
+        if !attachments.is_empty() {
+            body.push_str("<h2>Attachments</h2>\n<ul>\n");
+
+            for att in attachments {
+                body.push_str(&format!(
+                    "<li><a href=\"{}\" download>{}</a></li>\n",
+                    gallery_href(&[&att.file]),
+                    escape_html(&att.name)
+                ));
+            }
+
+            body.push_str("</ul>\n");
+        }
+    }
+
+    std::fs::write(
+        Path::new(folder).join(HTML_GALLERY_INDEX_FILE),
+        make_html_page(&title, &body, &scripts),
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Keep the manifest in sync with the files added to the folder
+
+    if Path::new(folder).join(EXPORT_MANIFEST_FILE).exists() {
+        write_export_manifest(folder)?;
+    }
+
+    Ok(())
+}
+
+/// Browsers only support WebVTT in <track>, so SRT subtitles are converted
+/// Returns the name of the WebVTT file
+fn write_subtitle_vtt(folder: &str, subtitle_file: &str) -> Result<String, String> {
+    let subtitle_path = Path::new(folder).join(subtitle_file);
+
+    if subtitle_file.to_lowercase().ends_with(".vtt") {
+        return Ok(subtitle_file.to_string());
+    }
+
+    let srt = std::fs::read_to_string(&subtitle_path).map_err(|e| e.to_string())?;
+
+    let mut vtt = "WEBVTT\n\n".to_string();
+
+    for line in srt.replace('\r', "").lines() {
+        if line.contains("-->") {
+            vtt.push_str(&line.replace(',', "."));
+        } else {
+            vtt.push_str(line);
+        }
+        vtt.push('\n');
+    }
+
+    let vtt_file = subtitle_path
+        .with_extension("vtt")
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .to_string();
+
+    std::fs::write(Path::new(folder).join(&vtt_file), vtt).map_err(|e| e.to_string())?;
+
+    Ok(vtt_file)
+}
+
+fn make_html_page(title: &str, body: &str, scripts: &str) -> String {
+    let script_html = if scripts.is_empty() {
+        "".to_string()
+    } else {
+        format!("<script>{scripts}</script>\n")
+    };
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}{}</body>\n</html>\n",
+        escape_html(title),
+        GALLERY_STYLE,
+        body,
+        script_html
+    )
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Makes a relative link from path segments, encoding each segment
+fn gallery_href(segments: &[&str]) -> String {
+    segments
+        .iter()
+        .map(|s| urlencoding::encode(s).to_string())
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+fn has_gallery_extension(file: &str, extensions: &[&str]) -> bool {
+    match Path::new(file).extension() {
+        Some(ext) => extensions.contains(&ext.to_string_lossy().to_lowercase().as_str()),
+        None => false,
+    }
+}
@@ -1,5 +1,7 @@
 // Identifiers tools
 
+use std::{collections::HashSet, io::Read};
+
 pub fn is_identifier(s: &str) -> bool {
     s.chars().count() > 1 && s.chars().next().unwrap_or(' ') == '#'
 }
@@ -27,3 +29,57 @@ pub fn parse_identifier(s: &str) -> Result<u64, ()> {
 pub fn identifier_to_string(id: u64) -> String {
     "#".to_owned() + &id.to_string()
 }
+
+/// Parses a list of identifiers, one per line, like #12 or 12
+/// For lines with multiple fields (CSV or tab separated), the first field is used
+/// The first line is skipped if it is not an identifier, since it may be a header
+/// Repeated identifiers are only included once
+pub fn parse_identifier_list(text: &str) -> Result<Vec<u64>, String> {
+    let mut result: Vec<u64> = Vec::new();
+    let mut found: HashSet<u64> = HashSet::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let field = line
+            .split([',', ';', '\t'])
+            .next()
+            .unwrap_or("")
+            .trim()
+            .trim_matches('"')
+            .trim();
+
+        if field.is_empty() {
+            continue;
+        }
+
+        match parse_identifier(field) {
+            Ok(id) => {
+                if found.insert(id) {
+                    result.push(id);
+                }
+            }
+            Err(_) => {
+                if i > 0 {
+                    let line_number = i + 1;
+                    return Err(format!("Invalid identifier at line {line_number}: {field}"));
+                }
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// Reads a list of identifiers from a file, or from the standard input if the path is -
+pub fn read_identifier_list(path: &str) -> Result<Vec<u64>, String> {
+    let text = if path == "-" {
+        let mut buf = String::new();
+        std::io::stdin()
+            .read_to_string(&mut buf)
+            .map_err(|e| format!("Could not read the standard input: {e}"))?;
+        buf
+    } else {
+        std::fs::read_to_string(path).map_err(|e| format!("Could not read file {path}: {e}"))?
+    };
+
+    parse_identifier_list(&text)
+}
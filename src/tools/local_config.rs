@@ -0,0 +1,89 @@
+// Local CLI configuration
+
+use std::path::PathBuf;
+
+use crate::models::{LocalConfig, SavedSearch};
+
+/// Environment variable to set a custom path for the local configuration file
+pub const LOCAL_CONFIG_ENV: &str = "PMV_CLI_CONFIG";
+
+/// Gets the path of the local configuration file
+/// By default, pmv-cli/config.json inside the user configuration folder
+pub fn get_local_config_path() -> Result<PathBuf, String> {
+    if let Ok(p) = std::env::var(LOCAL_CONFIG_ENV) {
+        if !p.is_empty() {
+            return Ok(PathBuf::from(p));
+        }
+    }
+
+    let config_dir = if cfg!(windows) {
+        std::env::var("APPDATA").ok().map(PathBuf::from)
+    } else {
+        match std::env::var("XDG_CONFIG_HOME") {
+            Ok(p) if !p.is_empty() => Some(PathBuf::from(p)),
+            _ => std::env::var("HOME")
+                .ok()
+                .map(|h| PathBuf::from(h).join(".config")),
+        }
+    };
+
+    match config_dir {
+        Some(d) => Ok(d.join("pmv-cli").join("config.json")),
+        None => Err(format!(
+            "Could not find the user configuration folder. Set the {LOCAL_CONFIG_ENV} environment variable to choose a path for the configuration file."
+        )),
+    }
+}
+
+/// Reads the local configuration
+/// If the file does not exist, the default configuration is returned
+pub fn read_local_config() -> Result<LocalConfig, String> {
+    let path = get_local_config_path()?;
+
+    if !path.exists() {
+        return Ok(LocalConfig::default());
+    }
+
+    let path_str = path.to_string_lossy().to_string();
+
+    let content = match std::fs::read_to_string(&path) {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(format!("Could not read {path_str}: {e}"));
+        }
+    };
+
+    match serde_json::from_str::<LocalConfig>(&content) {
+        Ok(c) => Ok(c),
+        Err(e) => Err(format!("Invalid configuration file {path_str}: {e}")),
+    }
+}
+
+/// Writes the local configuration, creating the folder if needed
+pub fn write_local_config(config: &LocalConfig) -> Result<(), String> {
+    let path = get_local_config_path()?;
+    let path_str = path.to_string_lossy().to_string();
+
+    if let Some(parent) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            return Err(format!("Could not create the folder for {path_str}: {e}"));
+        }
+    }
+
+    let content = serde_json::to_string_pretty(config).unwrap();
+
+    match std::fs::write(&path, content) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("Could not write {path_str}: {e}")),
+    }
+}
+
+/// Gets a saved search from the local configuration, by name
+pub fn get_saved_search(name: &str) -> Result<SavedSearch, String> {
+    let config = read_local_config()?;
+
+    match config.saved_searches.get(name) {
+        Some(s) => Ok(s.clone()),
+        None => Err(format!("Could not find a saved search with name: {name}")),
+    }
+}
@@ -0,0 +1,96 @@
+// Client-side sorting of media lists
+
+use std::{cmp::Ordering, collections::HashMap};
+
+use crate::models::MediaListItem;
+
+/// Field to sort media lists by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaSortField {
+    Title,
+    Duration,
+    Id,
+    Type,
+    Upload,
+}
+
+/// Sorting criteria for media lists
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MediaSort {
+    pub field: MediaSortField,
+    pub descending: bool,
+}
+
+impl MediaSort {
+    /// Checks if sorting requires the upload time of the media assets, which is not included in the lists
+    pub fn requires_upload_time(&self) -> bool {
+        self.field == MediaSortField::Upload
+    }
+}
+
+pub fn parse_media_sort_field(s: &str) -> Result<MediaSortField, ()> {
+    match s.to_lowercase().as_str() {
+        "title" | "name" => Ok(MediaSortField::Title),
+        "duration" | "length" => Ok(MediaSortField::Duration),
+        "id" => Ok(MediaSortField::Id),
+        "type" => Ok(MediaSortField::Type),
+        "upload" | "uploaded" | "date" => Ok(MediaSortField::Upload),
+        _ => Err(()),
+    }
+}
+
+/// Gets the sorting criteria from the command options
+pub fn get_media_sort(sort: Option<String>, descending: bool) -> Result<Option<MediaSort>, String> {
+    match sort {
+        Some(s) => match parse_media_sort_field(&s) {
+            Ok(field) => Ok(Some(MediaSort { field, descending })),
+            Err(_) => Err(format!(
+                "Sort field not recognized: {s} | Valid ones are: title, duration, id, type or upload"
+            )),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Compares two media assets
+/// Ties are resolved by ID, so the order is always the same
+/// upload_times is only used when sorting by upload time
+pub fn compare_media_list_items(
+    a: &MediaListItem,
+    b: &MediaListItem,
+    sort: &MediaSort,
+    upload_times: &HashMap<u64, i64>,
+) -> Ordering {
+    let ordering = match sort.field {
+        MediaSortField::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+        MediaSortField::Duration => a
+            .duration
+            .unwrap_or(0.0)
+            .total_cmp(&b.duration.unwrap_or(0.0)),
+        MediaSortField::Id => Ordering::Equal,
+        MediaSortField::Type => a
+            .media_type
+            .to_type_string()
+            .cmp(&b.media_type.to_type_string()),
+        MediaSortField::Upload => upload_times
+            .get(&a.id)
+            .unwrap_or(&0)
+            .cmp(upload_times.get(&b.id).unwrap_or(&0)),
+    }
+    .then(a.id.cmp(&b.id));
+
+    if sort.descending {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+/// Sorts a list of media assets
+pub fn sort_media_list(
+    list: &mut [MediaListItem],
+    sort: &MediaSort,
+    upload_times: &HashMap<u64, i64>,
+) {
+    list.sort_by(|a, b| compare_media_list_items(a, b, sort, upload_times));
+}
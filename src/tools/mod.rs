@@ -1,5 +1,11 @@
 // Module index
 
+mod archive;
+pub use archive::*;
+
+mod batch_journal;
+pub use batch_journal::*;
+
 mod console_table;
 pub use console_table::*;
 
@@ -9,12 +15,33 @@ pub use date::*;
 mod duration;
 pub use duration::*;
 
+mod duplicates;
+pub use duplicates::*;
+
 mod ensure_login;
 pub use ensure_login::*;
 
+mod export_manifest;
+pub use export_manifest::*;
+
+mod export_output;
+pub use export_output::*;
+
+mod html_gallery;
+pub use html_gallery::*;
+
 mod identifier;
 pub use identifier::*;
 
+mod local_config;
+pub use local_config::*;
+
+mod media_sort;
+pub use media_sort::*;
+
+mod playlist;
+pub use playlist::*;
+
 mod request;
 pub use request::*;
 
@@ -24,9 +51,27 @@ pub use request_download::*;
 mod request_upload;
 pub use request_upload::*;
 
+mod search_query;
+pub use search_query::*;
+
+mod search_results_stream;
+pub use search_results_stream::*;
+
+mod sidecar;
+pub use sidecar::*;
+
 mod size_render;
 pub use size_render::*;
 
+mod temp_folder;
+pub use temp_folder::*;
+
+mod text_match;
+pub use text_match::*;
+
+mod undo_journal;
+pub use undo_journal::*;
+
 mod user_input;
 pub use user_input::*;
 
@@ -0,0 +1,158 @@
+// Media playlists (M3U and XSPF)
+
+use crate::models::MediaMetadata;
+
+use super::{get_media_resolution_urls, parse_query_resolution};
+
+/// Playlist file format
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaylistFormat {
+    M3u,
+    Xspf,
+}
+
+/// Playlist options, from the command options
+#[derive(Debug, Clone)]
+pub struct PlaylistOptions {
+    pub format: PlaylistFormat,
+
+    /// Preferred resolutions, in order, as (width, height)
+    /// If the width is not specified, any width matches
+    pub resolutions: Vec<(Option<i32>, i32)>,
+}
+
+/// Playlist entry
+#[derive(Debug, Clone)]
+pub struct PlaylistEntry {
+    pub title: String,
+    pub duration: Option<f64>,
+    pub url: String,
+}
+
+pub fn parse_playlist_format(s: &str) -> Result<PlaylistFormat, ()> {
+    match s.to_lowercase().as_str() {
+        "m3u" | "m3u8" => Ok(PlaylistFormat::M3u),
+        "xspf" => Ok(PlaylistFormat::Xspf),
+        _ => Err(()),
+    }
+}
+
+/// Gets the playlist options from the command options
+pub fn get_playlist_options(
+    format: Option<String>,
+    resolution: Option<String>,
+) -> Result<Option<PlaylistOptions>, String> {
+    let format = match format {
+        Some(f) => match parse_playlist_format(&f) {
+            Ok(format) => format,
+            Err(_) => {
+                return Err(format!(
+                    "Playlist format not recognized: {f} | Valid ones are: m3u or xspf"
+                ));
+            }
+        },
+        None => {
+            return Ok(None);
+        }
+    };
+
+    let mut resolutions: Vec<(Option<i32>, i32)> = Vec::new();
+
+    if let Some(resolution) = resolution {
+        for r in resolution
+            .split(',')
+            .map(|r| r.trim())
+            .filter(|r| !r.is_empty())
+        {
+            match parse_query_resolution(r) {
+                Some(res) => resolutions.push(res),
+                None => {
+                    return Err(format!(
+                        "Invalid resolution: {r} | Expected a resolution like 720p or 1280x720"
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(Some(PlaylistOptions {
+        format,
+        resolutions,
+    }))
+}
+
+/// Chooses the asset to play for a media, following the resolution preferences
+/// Falls back to the original, if none of the preferred resolutions is ready
+/// Returns None if the media is not ready yet
+pub fn get_playlist_asset_path(
+    metadata: &MediaMetadata,
+    resolutions: &[(Option<i32>, i32)],
+) -> Option<String> {
+    for (width, height) in resolutions {
+        let url = get_media_resolution_urls(metadata, *width, *height)
+            .into_iter()
+            .flatten()
+            .next();
+
+        if let Some(url) = url {
+            return Some(url.clone());
+        }
+    }
+
+    metadata.url.clone()
+}
+
+/// Renders a playlist
+pub fn render_playlist(format: PlaylistFormat, entries: &[PlaylistEntry]) -> String {
+    match format {
+        PlaylistFormat::M3u => {
+            let mut res = "#EXTM3U\n".to_string();
+
+            for entry in entries {
+                let duration = match entry.duration {
+                    Some(d) => d.round() as i64,
+                    None => -1,
+                };
+                let title = entry.title.replace(['\r', '\n'], " ");
+
+                res.push_str(&format!("#EXTINF:{duration},{title}\n{}\n", entry.url));
+            }
+
+            res
+        }
+        PlaylistFormat::Xspf => {
+            let mut res = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n  <trackList>\n".to_string();
+
+            for entry in entries {
+                res.push_str("    <track>\n");
+                res.push_str(&format!(
+                    "      <location>{}</location>\n",
+                    escape_xml(&entry.url)
+                ));
+                res.push_str(&format!(
+                    "      <title>{}</title>\n",
+                    escape_xml(&entry.title)
+                ));
+
+                if let Some(d) = entry.duration {
+                    let duration_ms = (d * 1000.0).round() as i64;
+                    res.push_str(&format!("      <duration>{duration_ms}</duration>\n"));
+                }
+
+                res.push_str("    </track>\n");
+            }
+
+            res.push_str("  </trackList>\n</playlist>\n");
+
+            res
+        }
+    }
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
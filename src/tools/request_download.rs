@@ -4,7 +4,7 @@ use std::time::Instant;
 
 use crate::tools::{get_session_from_uri, resolve_vault_api_uri, SESSION_HEADER_NAME};
 
-use super::{RequestError, VaultURI};
+use super::{ArchiveWriter, RequestError, VaultURI};
 use tokio::{fs::File, io::AsyncWriteExt};
 
 pub trait ProgressReceiver {
@@ -123,6 +123,124 @@ pub async fn do_get_download_request(
     Ok(())
 }
 
+/// Downloads a file into an archive, writing the body as it is received
+/// If the server does not send the size, the body is kept in memory until it is complete.
+pub async fn do_get_download_request_archive(
+    uri: &VaultURI,
+    path: String,
+    archive: &mut ArchiveWriter,
+    folder: &str,
+    name: &str,
+    debug: bool,
+    progress_receiver: &mut dyn ProgressReceiver,
+) -> Result<(), RequestError> {
+    let final_uri = resolve_vault_api_uri(uri.clone(), path);
+
+    if debug {
+        eprintln!("\rDEBUG: DOWNLOAD {final_uri} -> archive:{folder}/{name}");
+    }
+
+    let client = reqwest::Client::new();
+
+    // Build request
+
+    let mut request_builder = client.get(final_uri);
+
+    let session = get_session_from_uri(uri.clone());
+
+    if let Some(s) = session {
+        request_builder = request_builder.header(SESSION_HEADER_NAME, s);
+    }
+
+    // Send request
+
+    let response_result = request_builder.send().await;
+
+    if let Err(err) = response_result {
+        return Err(RequestError::NetworkError(err.to_string()));
+    }
+
+    let mut response = response_result.unwrap();
+
+    let res_status = response.status();
+
+    if res_status != 200 {
+        return Err(RequestError::StatusCode(res_status));
+    }
+
+    // Write body into the archive
+
+    let content_length = response.content_length();
+    let body_length = content_length.unwrap_or(0);
+
+    let mut buffered_body: Vec<u8> = Vec::new();
+
+    if let Some(size) = content_length {
+        archive
+            .start_file(folder, name, size)
+            .map_err(RequestError::FileSystem)?;
+    }
+
+    let mut downloaded_bytes: u64 = 0;
+
+    let mut start = Instant::now();
+    progress_receiver.progress_start();
+
+    let mut finished = false;
+
+    while !finished {
+        // Grab chunk
+
+        let chunk_res = response.chunk().await;
+
+        match chunk_res {
+            Ok(chunk_opt) => match chunk_opt {
+                Some(chunk) => {
+                    downloaded_bytes += chunk.len() as u64;
+
+                    // Write chunk to the archive
+
+                    if content_length.is_some() {
+                        archive
+                            .write_file_chunk(&chunk)
+                            .map_err(RequestError::FileSystem)?;
+                    } else {
+                        buffered_body.extend_from_slice(&chunk);
+                    }
+
+                    // Report progress
+
+                    let elapsed = start.elapsed().as_millis();
+
+                    if elapsed > 100 {
+                        // Report progress
+                        progress_receiver.progress_update(downloaded_bytes, body_length);
+
+                        // Restart counter
+                        start = Instant::now();
+                    }
+                }
+                None => finished = true,
+            },
+            Err(err) => {
+                return Err(RequestError::NetworkError(err.to_string()));
+            }
+        }
+    }
+
+    if content_length.is_some() {
+        archive.finish_file().map_err(RequestError::FileSystem)?;
+    } else {
+        archive
+            .write_file(folder, name, &buffered_body)
+            .map_err(RequestError::FileSystem)?;
+    }
+
+    progress_receiver.progress_update(downloaded_bytes, body_length);
+    progress_receiver.progress_finish();
+    Ok(())
+}
+
 pub async fn do_get_download_request_memory(
     uri: &VaultURI,
     path: String,
@@ -0,0 +1,1082 @@
+// Search query language
+//
+// Examples:
+//   type:video tag:travel -tag:draft title:"beach" duration>10m
+//   (tag:cats OR tag:dogs) NOT is:untagged
+//
+// Terms separated by spaces must all match. OR has lower precedence than the implicit AND.
+// Terms can be negated with - or NOT, and grouped with parentheses.
+//
+// Some terms (uploaded, width, height, fps, resolution, is:ready, is:encoded and has:...)
+// require the media metadata, so it is fetched only for the media assets the rest of the terms cannot discard.
+
+use std::collections::HashMap;
+
+use chrono::{Months, NaiveDate};
+
+use crate::models::{parse_media_type, parse_tag_name, MediaListItem, MediaMetadata, MediaType};
+
+use super::{TextFilter, TextMatchMode};
+
+/// Parsed search query
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    root: SearchQueryNode,
+}
+
+#[derive(Debug, Clone)]
+enum SearchQueryNode {
+    And(Vec<SearchQueryNode>),
+    Or(Vec<SearchQueryNode>),
+    Not(Box<SearchQueryNode>),
+    Term(SearchQueryTerm),
+}
+
+#[derive(Debug, Clone)]
+enum SearchQueryTerm {
+    /// Bare word, matching the title or the description
+    Text(TextFilter),
+    Title(TextFilter),
+    Description(TextFilter),
+    MediaType(MediaType),
+    Tag {
+        name: String,
+        id: u64,
+    },
+    Untagged,
+    Duration(SearchQueryComparison, f64),
+
+    // The following terms require the media metadata
+    /// Upload time range (start inclusive, end exclusive), in Unix milliseconds
+    Uploaded(SearchQueryComparison, i64, i64),
+    Width(SearchQueryComparison, i32),
+    Height(SearchQueryComparison, i32),
+    Fps(SearchQueryComparison, i32),
+    /// Original or extra resolution, by width (optional) and height
+    Resolution(Option<i32>, i32),
+    Ready,
+    Encoded,
+    HasSubtitles,
+    HasAudios,
+    HasAttachments,
+    HasChapters,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SearchQueryComparison {
+    Equal,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum SearchQueryToken {
+    OpenParen,
+    CloseParen,
+    Not,
+    Or,
+    And,
+    Word(String),
+    Quoted(String),
+}
+
+impl SearchQuery {
+    /// Checks if a media asset matches the query
+    /// If the metadata is not provided, the terms requiring it cannot be checked.
+    /// In that case, None is returned if the result depends on them.
+    pub fn evaluate(
+        &self,
+        media: &MediaListItem,
+        metadata: Option<&MediaMetadata>,
+    ) -> Option<bool> {
+        self.root.evaluate(media, metadata)
+    }
+
+    /// Checks if the query has any term requiring the media metadata
+    pub fn requires_metadata(&self) -> bool {
+        self.root.requires_metadata()
+    }
+
+    /// Gets the tags filter to send to the server, in order to reduce the number of media assets to scan
+    /// Only the tags that must be present (or absent) for every result can be used
+    /// Returns the list of tag names and the mode (allof or noneof)
+    pub fn get_server_tags_filter(&self, max_tags: usize) -> Option<(Vec<String>, String)> {
+        let top_level: Vec<&SearchQueryNode> = match &self.root {
+            SearchQueryNode::And(children) => children.iter().collect(),
+            node => vec![node],
+        };
+
+        let required_tags: Vec<String> = top_level
+            .iter()
+            .filter_map(|n| match n {
+                SearchQueryNode::Term(SearchQueryTerm::Tag { name, .. }) => Some(name.clone()),
+                _ => None,
+            })
+            .take(max_tags)
+            .collect();
+
+        if !required_tags.is_empty() {
+            return Some((required_tags, "allof".to_string()));
+        }
+
+        let excluded_tags: Vec<String> = top_level
+            .iter()
+            .filter_map(|n| match n {
+                SearchQueryNode::Not(inner) => match inner.as_ref() {
+                    SearchQueryNode::Term(SearchQueryTerm::Tag { name, .. }) => Some(name.clone()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .take(max_tags)
+            .collect();
+
+        if !excluded_tags.is_empty() {
+            return Some((excluded_tags, "noneof".to_string()));
+        }
+
+        None
+    }
+}
+
+impl SearchQueryNode {
+    fn evaluate(&self, media: &MediaListItem, metadata: Option<&MediaMetadata>) -> Option<bool> {
+        match self {
+            SearchQueryNode::And(children) => {
+                let mut result = Some(true);
+
+                for child in children {
+                    match child.evaluate(media, metadata) {
+                        Some(true) => {}
+                        Some(false) => {
+                            return Some(false);
+                        }
+                        None => {
+                            result = None;
+                        }
+                    }
+                }
+
+                result
+            }
+            SearchQueryNode::Or(children) => {
+                let mut result = Some(false);
+
+                for child in children {
+                    match child.evaluate(media, metadata) {
+                        Some(true) => {
+                            return Some(true);
+                        }
+                        Some(false) => {}
+                        None => {
+                            result = None;
+                        }
+                    }
+                }
+
+                result
+            }
+            SearchQueryNode::Not(inner) => inner.evaluate(media, metadata).map(|r| !r),
+            SearchQueryNode::Term(term) => term.evaluate(media, metadata),
+        }
+    }
+
+    fn requires_metadata(&self) -> bool {
+        match self {
+            SearchQueryNode::And(children) | SearchQueryNode::Or(children) => {
+                children.iter().any(|c| c.requires_metadata())
+            }
+            SearchQueryNode::Not(inner) => inner.requires_metadata(),
+            SearchQueryNode::Term(term) => term.requires_metadata(),
+        }
+    }
+}
+
+impl SearchQueryTerm {
+    fn requires_metadata(&self) -> bool {
+        !matches!(
+            self,
+            SearchQueryTerm::Text(_)
+                | SearchQueryTerm::Title(_)
+                | SearchQueryTerm::Description(_)
+                | SearchQueryTerm::MediaType(_)
+                | SearchQueryTerm::Tag { .. }
+                | SearchQueryTerm::Untagged
+                | SearchQueryTerm::Duration(_, _)
+        )
+    }
+
+    fn evaluate(&self, media: &MediaListItem, metadata: Option<&MediaMetadata>) -> Option<bool> {
+        match self {
+            SearchQueryTerm::Text(filter) => {
+                Some(filter.matches(&media.title) || filter.matches(&media.description))
+            }
+            SearchQueryTerm::Title(filter) => Some(filter.matches(&media.title)),
+            SearchQueryTerm::Description(filter) => Some(filter.matches(&media.description)),
+            SearchQueryTerm::MediaType(media_type) => Some(media.media_type == *media_type),
+            SearchQueryTerm::Tag { id, .. } => Some(media.tags.contains(id)),
+            SearchQueryTerm::Untagged => Some(media.tags.is_empty()),
+            SearchQueryTerm::Duration(comparison, duration) => match media.duration {
+                Some(d) => match comparison {
+                    SearchQueryComparison::Equal => Some(d.floor() == duration.floor()),
+                    _ => Some(compare_query_number(*comparison, d, *duration)),
+                },
+                None => Some(false),
+            },
+            _ => metadata.map(|m| self.matches_metadata(m)),
+        }
+    }
+
+    fn matches_metadata(&self, metadata: &MediaMetadata) -> bool {
+        match self {
+            SearchQueryTerm::Uploaded(comparison, start, end) => match comparison {
+                SearchQueryComparison::Equal => {
+                    metadata.upload_time >= *start && metadata.upload_time < *end
+                }
+                SearchQueryComparison::Greater => metadata.upload_time >= *end,
+                SearchQueryComparison::GreaterOrEqual => metadata.upload_time >= *start,
+                SearchQueryComparison::Less => metadata.upload_time < *start,
+                SearchQueryComparison::LessOrEqual => metadata.upload_time < *end,
+            },
+            SearchQueryTerm::Width(comparison, width) => match metadata.width {
+                Some(w) => compare_query_number(*comparison, w as f64, *width as f64),
+                None => false,
+            },
+            SearchQueryTerm::Height(comparison, height) => match metadata.height {
+                Some(h) => compare_query_number(*comparison, h as f64, *height as f64),
+                None => false,
+            },
+            SearchQueryTerm::Fps(comparison, fps) => match metadata.fps {
+                Some(f) => compare_query_number(*comparison, f as f64, *fps as f64),
+                None => false,
+            },
+            SearchQueryTerm::Resolution(width, height) => {
+                !get_media_resolution_urls(metadata, *width, *height).is_empty()
+            }
+            SearchQueryTerm::Ready => metadata.ready,
+            SearchQueryTerm::Encoded => metadata.encoded,
+            SearchQueryTerm::HasSubtitles => {
+                metadata.subtitles.as_ref().is_some_and(|s| !s.is_empty())
+            }
+            SearchQueryTerm::HasAudios => metadata.audios.as_ref().is_some_and(|a| !a.is_empty()),
+            SearchQueryTerm::HasAttachments => {
+                metadata.attachments.as_ref().is_some_and(|a| !a.is_empty())
+            }
+            SearchQueryTerm::HasChapters => {
+                metadata.time_slices.as_ref().is_some_and(|t| !t.is_empty())
+            }
+            _ => false,
+        }
+    }
+}
+
+fn compare_query_number(comparison: SearchQueryComparison, value: f64, target: f64) -> bool {
+    match comparison {
+        SearchQueryComparison::Equal => value == target,
+        SearchQueryComparison::Greater => value > target,
+        SearchQueryComparison::GreaterOrEqual => value >= target,
+        SearchQueryComparison::Less => value < target,
+        SearchQueryComparison::LessOrEqual => value <= target,
+    }
+}
+
+/// Parses a search query
+/// The tag names are resolved into IDs using the reverse tags map
+/// Text terms are matched using the specified text matching mode
+pub fn parse_search_query(
+    query: &str,
+    tags_reverse_map: &HashMap<String, u64>,
+    text_match_mode: TextMatchMode,
+) -> Result<SearchQuery, String> {
+    let tokens = tokenize_search_query(query)?;
+
+    if tokens.is_empty() {
+        return Err("The query is empty".to_string());
+    }
+
+    let mut parser = SearchQueryParser {
+        tokens,
+        position: 0,
+        tags_reverse_map,
+        text_match_mode,
+    };
+
+    let root = parser.parse_or()?;
+
+    if let Some(token) = parser.peek() {
+        return match token {
+            SearchQueryToken::CloseParen => Err("Unexpected closing parenthesis".to_string()),
+            _ => Err("Unexpected token in query".to_string()),
+        };
+    }
+
+    Ok(SearchQuery { root })
+}
+
+fn tokenize_search_query(query: &str) -> Result<Vec<SearchQueryToken>, String> {
+    let mut tokens: Vec<SearchQueryToken> = Vec::new();
+    let mut chars = query.chars().peekable();
+
+    while let Some(c) = chars.peek().copied() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        match c {
+            '(' => {
+                chars.next();
+                tokens.push(SearchQueryToken::OpenParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(SearchQueryToken::CloseParen);
+            }
+            '-' => {
+                chars.next();
+                tokens.push(SearchQueryToken::Not);
+            }
+            _ => {
+                let mut word = String::new();
+                let mut quoted_word = true;
+
+                while let Some(wc) = chars.peek().copied() {
+                    if wc.is_whitespace() || wc == '(' || wc == ')' {
+                        break;
+                    }
+
+                    chars.next();
+
+                    if wc == '"' {
+                        word.push_str(&read_quoted_string(&mut chars)?);
+                    } else {
+                        word.push(wc);
+                        quoted_word = false;
+                    }
+                }
+
+                if quoted_word {
+                    tokens.push(SearchQueryToken::Quoted(word));
+                } else {
+                    match word.as_str() {
+                        "OR" => tokens.push(SearchQueryToken::Or),
+                        "AND" => tokens.push(SearchQueryToken::And),
+                        "NOT" => tokens.push(SearchQueryToken::Not),
+                        _ => tokens.push(SearchQueryToken::Word(word)),
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn read_quoted_string(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, String> {
+    let mut s = String::new();
+
+    loop {
+        match chars.next() {
+            Some('"') => {
+                return Ok(s);
+            }
+            Some('\\') => match chars.next() {
+                Some(escaped) => s.push(escaped),
+                None => {
+                    return Err("Unterminated quoted string".to_string());
+                }
+            },
+            Some(c) => s.push(c),
+            None => {
+                return Err("Unterminated quoted string".to_string());
+            }
+        }
+    }
+}
+
+struct SearchQueryParser<'a> {
+    tokens: Vec<SearchQueryToken>,
+    position: usize,
+    tags_reverse_map: &'a HashMap<String, u64>,
+    text_match_mode: TextMatchMode,
+}
+
+impl SearchQueryParser<'_> {
+    fn peek(&self) -> Option<&SearchQueryToken> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<SearchQueryToken> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<SearchQueryNode, String> {
+        let mut children = vec![self.parse_and()?];
+
+        while self.peek() == Some(&SearchQueryToken::Or) {
+            self.next();
+            children.push(self.parse_and()?);
+        }
+
+        if children.len() == 1 {
+            Ok(children.pop().unwrap())
+        } else {
+            Ok(SearchQueryNode::Or(children))
+        }
+    }
+
+    fn parse_and(&mut self) -> Result<SearchQueryNode, String> {
+        let mut children = vec![self.parse_unary()?];
+
+        loop {
+            match self.peek() {
+                None | Some(SearchQueryToken::Or) | Some(SearchQueryToken::CloseParen) => {
+                    break;
+                }
+                Some(SearchQueryToken::And) => {
+                    self.next();
+                }
+                _ => {}
+            }
+
+            children.push(self.parse_unary()?);
+        }
+
+        if children.len() == 1 {
+            Ok(children.pop().unwrap())
+        } else {
+            Ok(SearchQueryNode::And(children))
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<SearchQueryNode, String> {
+        match self.next() {
+            Some(SearchQueryToken::Not) => Ok(SearchQueryNode::Not(Box::new(self.parse_unary()?))),
+            Some(SearchQueryToken::OpenParen) => {
+                let node = self.parse_or()?;
+
+                match self.next() {
+                    Some(SearchQueryToken::CloseParen) => Ok(node),
+                    _ => Err("Missing closing parenthesis".to_string()),
+                }
+            }
+            Some(SearchQueryToken::Quoted(text)) => Ok(SearchQueryNode::Term(
+                SearchQueryTerm::Text(TextFilter::new(&text, self.text_match_mode)?),
+            )),
+            Some(SearchQueryToken::Word(word)) => {
+                Ok(SearchQueryNode::Term(self.parse_term(&word)?))
+            }
+            Some(SearchQueryToken::CloseParen) => Err("Unexpected closing parenthesis".to_string()),
+            Some(SearchQueryToken::Or) | Some(SearchQueryToken::And) => {
+                Err("Expected a term before and after OR / AND".to_string())
+            }
+            None => Err("Unexpected end of query".to_string()),
+        }
+    }
+
+    fn parse_term(&self, word: &str) -> Result<SearchQueryTerm, String> {
+        let op_position = word.find([':', '>', '<', '=']);
+
+        let (field, rest) = match op_position {
+            Some(p) => (word[..p].to_lowercase(), &word[p..]),
+            None => {
+                return Ok(SearchQueryTerm::Text(TextFilter::new(
+                    word,
+                    self.text_match_mode,
+                )?));
+            }
+        };
+
+        let (comparison, value) = if let Some(v) = rest.strip_prefix(">=") {
+            (SearchQueryComparison::GreaterOrEqual, v)
+        } else if let Some(v) = rest.strip_prefix("<=") {
+            (SearchQueryComparison::LessOrEqual, v)
+        } else if let Some(v) = rest.strip_prefix('>') {
+            (SearchQueryComparison::Greater, v)
+        } else if let Some(v) = rest.strip_prefix('<') {
+            (SearchQueryComparison::Less, v)
+        } else {
+            (SearchQueryComparison::Equal, &rest[1..])
+        };
+
+        let is_text_field = comparison == SearchQueryComparison::Equal;
+
+        match field.as_str() {
+            "title" if is_text_field => Ok(SearchQueryTerm::Title(TextFilter::new(
+                value,
+                self.text_match_mode,
+            )?)),
+            "desc" | "description" if is_text_field => Ok(SearchQueryTerm::Description(
+                TextFilter::new(value, self.text_match_mode)?,
+            )),
+            "type" if is_text_field => match parse_media_type(value) {
+                Ok(media_type) => Ok(SearchQueryTerm::MediaType(media_type)),
+                Err(_) => Err(format!(
+                    "Media type not recognized: {value} | Valid ones are: video, image or audio"
+                )),
+            },
+            "tag" if is_text_field => {
+                let name = parse_tag_name(value);
+
+                match self.tags_reverse_map.get(&name) {
+                    Some(id) => Ok(SearchQueryTerm::Tag { name, id: *id }),
+                    None => Err(format!("Could not find tag with name: {value}")),
+                }
+            }
+            "is" if is_text_field => match value.to_lowercase().as_str() {
+                "untagged" => Ok(SearchQueryTerm::Untagged),
+                "ready" => Ok(SearchQueryTerm::Ready),
+                "encoded" => Ok(SearchQueryTerm::Encoded),
+                _ => Err(format!(
+                    "Unknown value for is: {value} | Valid ones are: untagged, ready or encoded"
+                )),
+            },
+            "has" if is_text_field => match value.to_lowercase().as_str() {
+                "subtitles" => Ok(SearchQueryTerm::HasSubtitles),
+                "audios" => Ok(SearchQueryTerm::HasAudios),
+                "attachments" => Ok(SearchQueryTerm::HasAttachments),
+                "chapters" => Ok(SearchQueryTerm::HasChapters),
+                _ => Err(format!(
+                    "Unknown value for has: {value} | Valid ones are: subtitles, audios, attachments or chapters"
+                )),
+            },
+            "resolution" if is_text_field => match parse_query_resolution(value) {
+                Some((width, height)) => Ok(SearchQueryTerm::Resolution(width, height)),
+                None => Err(format!(
+                    "Invalid resolution: {value} | Expected a resolution like 1080p or 1920x1080"
+                )),
+            },
+            "uploaded" => match parse_query_date_range(value) {
+                Some((start, end)) => Ok(SearchQueryTerm::Uploaded(comparison, start, end)),
+                None => Err(format!(
+                    "Invalid date: {value} | Expected a date like 2024, 2024-05 or 2024-05-17"
+                )),
+            },
+            "width" | "height" | "fps" => {
+                let n: i32 = match value.parse() {
+                    Ok(n) => n,
+                    Err(_) => {
+                        return Err(format!("Invalid number for {field}: {value}"));
+                    }
+                };
+
+                match field.as_str() {
+                    "width" => Ok(SearchQueryTerm::Width(comparison, n)),
+                    "height" => Ok(SearchQueryTerm::Height(comparison, n)),
+                    _ => Ok(SearchQueryTerm::Fps(comparison, n)),
+                }
+            }
+            "duration" => match parse_query_duration(value) {
+                Some(d) => Ok(SearchQueryTerm::Duration(comparison, d)),
+                None => Err(format!(
+                    "Invalid duration: {value} | Expected a duration like 90, 90s, 10m, 1h30m or 1:30:00"
+                )),
+            },
+            "title" | "desc" | "description" | "type" | "tag" | "is" | "has" | "resolution" => {
+                Err(format!("The field {field} can only be used with ':'"))
+            }
+            _ => Err(format!(
+                "Unknown field: {field} | Valid ones are: title, desc, type, tag, is, has, duration, uploaded, width, height, fps and resolution. Use quotes to search for text containing ':'"
+            )),
+        }
+    }
+}
+
+/// Parses a duration, in seconds
+/// Accepts plain seconds (90), units (90s, 10m, 1h30m) or clock format (1:30, 1:02:03)
+fn parse_query_duration(value: &str) -> Option<f64> {
+    if value.is_empty() {
+        return None;
+    }
+
+    if value.contains(':') {
+        let mut total: f64 = 0.0;
+
+        for part in value.split(':') {
+            let n: f64 = part.parse().ok()?;
+            total = total * 60.0 + n;
+        }
+
+        return Some(total);
+    }
+
+    if let Ok(seconds) = value.parse::<f64>() {
+        return Some(seconds);
+    }
+
+    let mut total: f64 = 0.0;
+    let mut number = String::new();
+
+    for c in value.chars() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+
+        let n: f64 = number.parse().ok()?;
+        number.clear();
+
+        total += match c.to_ascii_lowercase() {
+            'h' => n * 3600.0,
+            'm' => n * 60.0,
+            's' => n,
+            _ => {
+                return None;
+            }
+        };
+    }
+
+    if !number.is_empty() {
+        return None;
+    }
+
+    Some(total)
+}
+
+/// Parses a resolution, like 1080p or 1920x1080
+/// Returns the width (if specified) and the height
+pub fn parse_query_resolution(value: &str) -> Option<(Option<i32>, i32)> {
+    let lower = value.to_lowercase();
+
+    if let Some(height) = lower.strip_suffix('p') {
+        return Some((None, height.parse().ok()?));
+    }
+
+    let (width, height) = lower.split_once('x')?;
+
+    Some((Some(width.parse().ok()?), height.parse().ok()?))
+}
+
+/// Finds the assets of a media matching a resolution, by width (if specified) and height
+/// Returns the URL of each asset (None if not ready), the original first, then the extra resolutions
+pub fn get_media_resolution_urls(
+    metadata: &MediaMetadata,
+    width: Option<i32>,
+    height: i32,
+) -> Vec<Option<&String>> {
+    let matches_resolution = |w: i32, h: i32| -> bool {
+        h == height && width.is_none_or(|expected_width| expected_width == w)
+    };
+
+    let mut urls: Vec<Option<&String>> = Vec::new();
+
+    if let (Some(w), Some(h)) = (metadata.width, metadata.height) {
+        if matches_resolution(w, h) {
+            urls.push(metadata.url.as_ref());
+        }
+    }
+
+    if let Some(resolutions) = &metadata.resolutions {
+        urls.extend(
+            resolutions
+                .iter()
+                .filter(|r| matches_resolution(r.width, r.height))
+                .map(|r| r.url.as_ref().filter(|_| r.ready)),
+        );
+    }
+
+    urls
+}
+
+/// Parses a date (UTC), like 2024, 2024-05 or 2024-05-17
+/// Returns the range of time it covers, in Unix milliseconds (start inclusive, end exclusive)
+fn parse_query_date_range(value: &str) -> Option<(i64, i64)> {
+    let parts: Vec<&str> = value.split('-').collect();
+
+    let year: i32 = parts.first()?.parse().ok()?;
+
+    let (start, end) = match parts.len() {
+        1 => {
+            let start = NaiveDate::from_ymd_opt(year, 1, 1)?;
+            (start, start.checked_add_months(Months::new(12))?)
+        }
+        2 => {
+            let start = NaiveDate::from_ymd_opt(year, parts[1].parse().ok()?, 1)?;
+            (start, start.checked_add_months(Months::new(1))?)
+        }
+        3 => {
+            let start =
+                NaiveDate::from_ymd_opt(year, parts[1].parse().ok()?, parts[2].parse().ok()?)?;
+            (start, start.succ_opt()?)
+        }
+        _ => {
+            return None;
+        }
+    };
+
+    Some((
+        start.and_hms_opt(0, 0, 0)?.and_utc().timestamp_millis(),
+        end.and_hms_opt(0, 0, 0)?.and_utc().timestamp_millis(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_tags() -> HashMap<String, u64> {
+        HashMap::from([
+            ("travel".to_string(), 1),
+            ("draft".to_string(), 2),
+            ("cats".to_string(), 3),
+            ("dogs".to_string(), 4),
+        ])
+    }
+
+    fn parse(query: &str) -> SearchQuery {
+        parse_search_query(query, &test_tags(), TextMatchMode::Insensitive).unwrap()
+    }
+
+    fn test_media(title: &str, tags: &[u64]) -> MediaListItem {
+        MediaListItem {
+            id: 1,
+            media_type: MediaType::Video,
+            title: title.to_string(),
+            description: "".to_string(),
+            thumbnail: None,
+            tags: tags.to_vec(),
+            duration: Some(60.0),
+        }
+    }
+
+    fn matches(query: &str, media: &MediaListItem) -> bool {
+        parse(query).evaluate(media, None).unwrap()
+    }
+
+    #[test]
+    fn test_or_has_lower_precedence_than_and() {
+        let query = parse("tag:cats tag:dogs OR tag:travel");
+
+        match &query.root {
+            SearchQueryNode::Or(children) => {
+                assert_eq!(children.len(), 2);
+                assert!(matches!(&children[0], SearchQueryNode::And(c) if c.len() == 2));
+                assert!(matches!(&children[1], SearchQueryNode::Term(_)));
+            }
+            node => panic!("Expected an OR node, found {node:?}"),
+        }
+
+        assert!(matches(
+            "tag:cats tag:dogs OR tag:travel",
+            &test_media("", &[1])
+        ));
+        assert!(matches(
+            "tag:cats tag:dogs OR tag:travel",
+            &test_media("", &[3, 4])
+        ));
+        assert!(!matches(
+            "tag:cats tag:dogs OR tag:travel",
+            &test_media("", &[3])
+        ));
+    }
+
+    #[test]
+    fn test_explicit_and_and_parentheses() {
+        assert!(matches("tag:cats AND tag:dogs", &test_media("", &[3, 4])));
+        assert!(!matches("tag:cats AND tag:dogs", &test_media("", &[3])));
+
+        let query = "tag:cats (tag:dogs OR tag:travel)";
+
+        assert!(matches(query, &test_media("", &[3, 1])));
+        assert!(matches(query, &test_media("", &[3, 4])));
+        assert!(!matches(query, &test_media("", &[1, 4])));
+    }
+
+    #[test]
+    fn test_quoting() {
+        let media = test_media("Beach day at noon", &[]);
+
+        assert!(matches("\"beach day\"", &media));
+        assert!(!matches("\"beach noon\"", &media));
+        assert!(matches("title:\"day at\"", &media));
+        assert!(matches("\"OR\"", &test_media("Color", &[])));
+        assert!(matches(
+            "\"say \\\"hi\\\"\"",
+            &test_media("They say \"hi\"", &[])
+        ));
+
+        let unterminated = parse_search_query("\"beach", &test_tags(), TextMatchMode::Insensitive);
+        assert!(unterminated.is_err());
+    }
+
+    #[test]
+    fn test_negation() {
+        let draft = test_media("", &[2]);
+        let cats = test_media("", &[3]);
+
+        assert!(!matches("-tag:draft", &draft));
+        assert!(matches("-tag:draft", &cats));
+        assert!(!matches("NOT tag:draft", &draft));
+        assert!(matches("NOT -tag:draft", &draft));
+        assert!(!matches("-(tag:cats OR tag:dogs)", &cats));
+        assert!(matches("-(tag:cats OR tag:dogs)", &draft));
+        assert!(matches("tag:cats -is:untagged", &cats));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let tags = test_tags();
+        let parse_err = |q: &str| parse_search_query(q, &tags, TextMatchMode::Insensitive).is_err();
+
+        assert!(parse_err(""));
+        assert!(parse_err("tag:unknown"));
+        assert!(parse_err("(tag:cats"));
+        assert!(parse_err("tag:cats)"));
+        assert!(parse_err("tag:cats OR"));
+        assert!(parse_err("tag>cats"));
+        assert!(parse_err("foo:bar"));
+    }
+
+    #[test]
+    fn test_parse_query_duration() {
+        assert_eq!(parse_query_duration("90"), Some(90.0));
+        assert_eq!(parse_query_duration("90s"), Some(90.0));
+        assert_eq!(parse_query_duration("10m"), Some(600.0));
+        assert_eq!(parse_query_duration("1h30m"), Some(5400.0));
+        assert_eq!(parse_query_duration("1.5m"), Some(90.0));
+        assert_eq!(parse_query_duration("1:30"), Some(90.0));
+        assert_eq!(parse_query_duration("1:02:03"), Some(3723.0));
+
+        assert_eq!(parse_query_duration(""), None);
+        assert_eq!(parse_query_duration("10x"), None);
+        assert_eq!(parse_query_duration("10m5"), None);
+        assert_eq!(parse_query_duration("1:a"), None);
+    }
+
+    #[test]
+    fn test_server_tags_filter() {
+        assert_eq!(
+            parse("tag:cats tag:dogs type:video").get_server_tags_filter(10),
+            Some((
+                vec!["cats".to_string(), "dogs".to_string()],
+                "allof".to_string()
+            ))
+        );
+        assert_eq!(
+            parse("tag:cats tag:dogs").get_server_tags_filter(1),
+            Some((vec!["cats".to_string()], "allof".to_string()))
+        );
+        assert_eq!(
+            parse("tag:cats -tag:draft").get_server_tags_filter(10),
+            Some((vec!["cats".to_string()], "allof".to_string()))
+        );
+        assert_eq!(
+            parse("-tag:draft title:beach").get_server_tags_filter(10),
+            Some((vec!["draft".to_string()], "noneof".to_string()))
+        );
+
+        assert_eq!(
+            parse("tag:cats OR tag:dogs").get_server_tags_filter(10),
+            None
+        );
+        assert_eq!(
+            parse("(tag:cats tag:dogs) OR beach").get_server_tags_filter(10),
+            None
+        );
+        assert_eq!(
+            parse("-(tag:cats tag:dogs)").get_server_tags_filter(10),
+            None
+        );
+        assert_eq!(parse("beach").get_server_tags_filter(10), None);
+    }
+
+    fn test_metadata(upload_time: i64, width: i32, height: i32, fps: i32) -> MediaMetadata {
+        serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "type": 2,
+            "upload_time": upload_time,
+            "title": "",
+            "description": "",
+            "thumbnail": "",
+            "tags": [],
+            "duration": 60.0,
+            "width": width,
+            "height": height,
+            "fps": fps,
+            "ready": true,
+            "encoded": true,
+            "resolutions": [
+                {"width": 1280, "height": 720, "fps": 30, "ready": true, "task": 0, "url": ""}
+            ],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_evaluate_three_valued_logic() {
+        let media = test_media("beach", &[3]);
+        let metadata = test_metadata(0, 1920, 1080, 30);
+
+        // Metadata terms are unknown without the metadata
+        assert_eq!(parse("is:ready").evaluate(&media, None), None);
+        assert_eq!(parse("NOT is:ready").evaluate(&media, None), None);
+
+        // AND: false wins over unknown, unknown wins over true
+        assert_eq!(
+            parse("tag:dogs is:ready").evaluate(&media, None),
+            Some(false)
+        );
+        assert_eq!(parse("tag:cats is:ready").evaluate(&media, None), None);
+
+        // OR: true wins over unknown, unknown wins over false
+        assert_eq!(
+            parse("tag:cats OR is:ready").evaluate(&media, None),
+            Some(true)
+        );
+        assert_eq!(parse("tag:dogs OR is:ready").evaluate(&media, None), None);
+
+        // With the metadata, every term is known
+        assert_eq!(
+            parse("tag:cats is:ready").evaluate(&media, Some(&metadata)),
+            Some(true)
+        );
+        assert_eq!(
+            parse("tag:dogs OR -is:ready").evaluate(&media, Some(&metadata)),
+            Some(false)
+        );
+
+        assert!(parse("width>1000").requires_metadata());
+        assert!(!parse("beach duration>10").requires_metadata());
+    }
+
+    #[test]
+    fn test_evaluate_numeric_terms() {
+        let media = test_media("", &[]);
+        let metadata = test_metadata(0, 1920, 1080, 30);
+        let eval = |q: &str| parse(q).evaluate(&media, Some(&metadata)).unwrap();
+
+        assert!(eval("width:1920"));
+        assert!(eval("height>=1080"));
+        assert!(!eval("height>1080"));
+        assert!(eval("fps<60"));
+        assert!(eval("duration:1m"));
+        assert!(eval("duration<=60"));
+        assert!(!eval("duration>1m"));
+
+        let tags = test_tags();
+        assert!(parse_search_query("height:1080p", &tags, TextMatchMode::Insensitive).is_err());
+        assert!(parse_search_query("fps>30P", &tags, TextMatchMode::Insensitive).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_uploaded() {
+        let media = test_media("", &[]);
+        let (start, end) = parse_query_date_range("2024-05").unwrap();
+
+        let eval = |q: &str, t: i64| {
+            parse(q)
+                .evaluate(&media, Some(&test_metadata(t, 1920, 1080, 30)))
+                .unwrap()
+        };
+
+        assert!(eval("uploaded:2024-05", start));
+        assert!(eval("uploaded:2024-05", end - 1));
+        assert!(!eval("uploaded:2024-05", end));
+        assert!(!eval("uploaded:2024-05", start - 1));
+
+        assert!(eval("uploaded>2024-05", end));
+        assert!(!eval("uploaded>2024-05", end - 1));
+        assert!(eval("uploaded>=2024-05", start));
+        assert!(eval("uploaded<2024-05", start - 1));
+        assert!(!eval("uploaded<2024-05", start));
+        assert!(eval("uploaded<=2024-05", end - 1));
+        assert!(!eval("uploaded<=2024-05", end));
+    }
+
+    #[test]
+    fn test_parse_query_date_range() {
+        let ms = |y: i32, m: u32, d: u32| {
+            NaiveDate::from_ymd_opt(y, m, d)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+                .and_utc()
+                .timestamp_millis()
+        };
+
+        assert_eq!(
+            parse_query_date_range("2024"),
+            Some((ms(2024, 1, 1), ms(2025, 1, 1)))
+        );
+        assert_eq!(
+            parse_query_date_range("2024-02"),
+            Some((ms(2024, 2, 1), ms(2024, 3, 1)))
+        );
+        assert_eq!(
+            parse_query_date_range("2024-12"),
+            Some((ms(2024, 12, 1), ms(2025, 1, 1)))
+        );
+        assert_eq!(
+            parse_query_date_range("2024-02-29"),
+            Some((ms(2024, 2, 29), ms(2024, 3, 1)))
+        );
+        assert_eq!(
+            parse_query_date_range("2024-12-31"),
+            Some((ms(2024, 12, 31), ms(2025, 1, 1)))
+        );
+
+        assert_eq!(parse_query_date_range("2023-02-29"), None);
+        assert_eq!(parse_query_date_range("2024-13"), None);
+        assert_eq!(parse_query_date_range("2024-05-17-1"), None);
+        assert_eq!(parse_query_date_range("May"), None);
+    }
+
+    #[test]
+    fn test_parse_query_resolution() {
+        assert_eq!(parse_query_resolution("1080p"), Some((None, 1080)));
+        assert_eq!(parse_query_resolution("720P"), Some((None, 720)));
+        assert_eq!(
+            parse_query_resolution("1920x1080"),
+            Some((Some(1920), 1080))
+        );
+        assert_eq!(parse_query_resolution("1280X720"), Some((Some(1280), 720)));
+
+        assert_eq!(parse_query_resolution("1080"), None);
+        assert_eq!(parse_query_resolution("p"), None);
+        assert_eq!(parse_query_resolution("1920x"), None);
+        assert_eq!(parse_query_resolution("ax720"), None);
+    }
+
+    #[test]
+    fn test_evaluate_resolution() {
+        let media = test_media("", &[]);
+        let metadata = test_metadata(0, 1920, 1080, 30);
+        let eval = |q: &str| parse(q).evaluate(&media, Some(&metadata)).unwrap();
+
+        assert!(eval("resolution:1080p"));
+        assert!(eval("resolution:1920x1080"));
+        assert!(eval("resolution:720p"));
+        assert!(eval("resolution:1280x720"));
+        assert!(!eval("resolution:1024x720"));
+        assert!(!eval("resolution:480p"));
+    }
+
+    #[test]
+    fn test_get_media_resolution_urls() {
+        let mut metadata = test_metadata(0, 1920, 1080, 30);
+        metadata.url = Some("/original.mp4".to_string());
+
+        let original_url = "/original.mp4".to_string();
+        let extra_url = "".to_string();
+
+        assert_eq!(
+            get_media_resolution_urls(&metadata, None, 1080),
+            vec![Some(&original_url)]
+        );
+        assert_eq!(
+            get_media_resolution_urls(&metadata, Some(1280), 720),
+            vec![Some(&extra_url)]
+        );
+        assert!(get_media_resolution_urls(&metadata, Some(1024), 720).is_empty());
+
+        // Extra resolutions not ready yet match, but without URL
+
+        metadata.resolutions.as_mut().unwrap()[0].ready = false;
+
+        assert_eq!(get_media_resolution_urls(&metadata, None, 720), vec![None]);
+    }
+}
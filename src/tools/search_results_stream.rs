@@ -0,0 +1,170 @@
+// Streaming output for search results
+
+use std::collections::HashMap;
+
+use crate::models::{tags_names_from_ids, MediaListItem, SearchResultEntry};
+
+use super::{identifier_to_string, print_table, render_media_duration, to_csv_string};
+
+/// Output format for search results
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchResultsFormat {
+    Table,
+    Csv,
+    JsonLines,
+    Ids,
+}
+
+pub fn get_search_results_format(csv: bool, jsonl: bool, ids: bool) -> SearchResultsFormat {
+    if ids {
+        SearchResultsFormat::Ids
+    } else if jsonl {
+        SearchResultsFormat::JsonLines
+    } else if csv {
+        SearchResultsFormat::Csv
+    } else {
+        SearchResultsFormat::Table
+    }
+}
+
+/// Prints search results into the standard output, as they arrive
+/// In table format, a table is printed for each batch of results
+pub struct SearchResultsStream<'a> {
+    format: SearchResultsFormat,
+    extended: bool,
+    tags_map: &'a HashMap<u64, String>,
+    header_printed: bool,
+    count: u64,
+}
+
+impl<'a> SearchResultsStream<'a> {
+    pub fn new(
+        format: SearchResultsFormat,
+        extended: bool,
+        tags_map: &'a HashMap<u64, String>,
+    ) -> SearchResultsStream<'a> {
+        SearchResultsStream {
+            format,
+            extended,
+            tags_map,
+            header_printed: false,
+            count: 0,
+        }
+    }
+
+    /// Gets the number of results printed
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Prints a batch of results
+    pub fn write_items(&mut self, items: &[MediaListItem]) {
+        if items.is_empty() {
+            return;
+        }
+
+        self.count += items.len() as u64;
+
+        match self.format {
+            SearchResultsFormat::Table => self.write_table(items),
+            SearchResultsFormat::Csv => self.write_csv(items),
+            SearchResultsFormat::JsonLines => self.write_json_lines(items),
+            SearchResultsFormat::Ids => {
+                for item in items {
+                    println!("{}", item.id);
+                }
+            }
+        }
+    }
+
+    fn write_table(&self, items: &[MediaListItem]) {
+        let mut table_head: Vec<String> =
+            vec!["Id".to_string(), "Type".to_string(), "Title".to_string()];
+
+        if self.extended {
+            table_head.push("Description".to_string());
+            table_head.push("Tags".to_string());
+            table_head.push("Duration".to_string());
+        }
+
+        let table_body: Vec<Vec<String>> = items
+            .iter()
+            .map(|item| {
+                let mut row = vec![
+                    identifier_to_string(item.id),
+                    item.media_type.to_type_string(),
+                    to_csv_string(&item.title),
+                ];
+
+                if self.extended {
+                    row.push(to_csv_string(&item.description));
+                    row.push(to_csv_string(
+                        &tags_names_from_ids(&item.tags, self.tags_map).join(" "),
+                    ));
+                    row.push(render_media_duration(
+                        item.media_type,
+                        item.duration.unwrap_or(0.0),
+                    ));
+                }
+
+                row
+            })
+            .collect();
+
+        print_table(&table_head, &table_body, false);
+    }
+
+    fn write_csv(&mut self, items: &[MediaListItem]) {
+        if !self.header_printed {
+            if self.extended {
+                println!("\"Id\",\"Type\",\"Title\",\"Description\",\"Tags\",\"Duration\"");
+            } else {
+                println!("\"Id\",\"Type\",\"Title\"");
+            }
+
+            self.header_printed = true;
+        }
+
+        for item in items {
+            let row_id = item.id.to_string();
+            let row_type = to_csv_string(&item.media_type.to_type_string());
+            let row_title = to_csv_string(&item.title);
+
+            if self.extended {
+                let row_description = to_csv_string(&item.description);
+                let row_tags =
+                    to_csv_string(&tags_names_from_ids(&item.tags, self.tags_map).join(" "));
+                let row_duration =
+                    render_media_duration(item.media_type, item.duration.unwrap_or(0.0));
+
+                println!(
+                    "{row_id},{row_type},{row_title},{row_description},{row_tags},{row_duration}"
+                );
+            } else {
+                println!("{row_id},{row_type},{row_title}");
+            }
+        }
+    }
+
+    fn write_json_lines(&self, items: &[MediaListItem]) {
+        for item in items {
+            let entry = SearchResultEntry {
+                id: item.id,
+                media_type: item.media_type.to_type_string(),
+                title: item.title.clone(),
+                description: item.description.clone(),
+                tags: tags_names_from_ids(&item.tags, self.tags_map),
+                duration: item.duration,
+            };
+
+            println!("{}", serde_json::to_string(&entry).unwrap());
+        }
+    }
+}
+
+/// Prints the progress of a search that scans the entire vault, into the standard error
+pub fn print_search_progress(scanned: u64, total: u64, found: u64) {
+    let percent = (scanned.min(total) * 100).checked_div(total).unwrap_or(100);
+
+    eprintln!("Scanned {scanned} / {total} ({percent}%) - Results: {found}");
+}
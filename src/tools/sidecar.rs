@@ -0,0 +1,521 @@
+// Sidecar metadata files (Jellyfin / Kodi NFO and yt-dlp info.json)
+
+use std::path::{Path, PathBuf};
+
+use crate::models::{parse_tag_name, MediaTimeSlice, YtDlpInfo};
+
+const SIDECAR_THUMBNAIL_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp"];
+
+/// Metadata read from a sidecar file
+pub struct SidecarMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    pub time_slices: Vec<MediaTimeSlice>,
+    pub ext_desc: Option<String>,
+    pub thumbnail: Option<PathBuf>,
+}
+
+/// Finds the sidecar file of a media file
+/// Looks for <name>.info.json, <name>.nfo and movie.nfo, in that order
+pub fn find_sidecar_file(media_file: &Path) -> Option<PathBuf> {
+    let stem = media_file.file_stem()?.to_string_lossy().to_string();
+    let dir = media_file.parent().unwrap_or(Path::new(""));
+
+    let candidates = [
+        dir.join(stem.clone() + ".info.json"),
+        dir.join(stem + ".nfo"),
+        dir.join("movie.nfo"),
+    ];
+
+    candidates.into_iter().find(|c| c.is_file())
+}
+
+/// Reads the metadata of a media file from a sidecar file
+/// The format is detected by the extension of the sidecar (.nfo or .json)
+pub fn read_sidecar_metadata(
+    media_file: &Path,
+    sidecar_file: &Path,
+) -> Result<SidecarMetadata, String> {
+    let sidecar_str = std::fs::read_to_string(sidecar_file).map_err(|e| e.to_string())?;
+
+    let sidecar_ext = sidecar_file
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let mut metadata = match sidecar_ext.as_str() {
+        "nfo" | "xml" => parse_nfo(&sidecar_str, sidecar_file)?,
+        "json" => parse_yt_dlp_info(&sidecar_str)?,
+        _ => {
+            return Err("Unknown sidecar format. Expected a .nfo or .info.json file.".to_string());
+        }
+    };
+
+    if metadata.thumbnail.is_none() {
+        metadata.thumbnail = find_sidecar_thumbnail(media_file);
+    }
+
+    Ok(metadata)
+}
+
+/// Finds a thumbnail next to the media file, following the names
+/// used by yt-dlp (<name>.jpg) and Kodi (<name>-thumb.jpg, <name>-poster.jpg, poster.jpg, folder.jpg)
+fn find_sidecar_thumbnail(media_file: &Path) -> Option<PathBuf> {
+    let stem = media_file.file_stem()?.to_string_lossy().to_string();
+    let dir = media_file.parent().unwrap_or(Path::new(""));
+
+    let mut names: Vec<String> = Vec::new();
+
+    for suffix in ["", "-thumb", "-poster"] {
+        names.push(stem.clone() + suffix);
+    }
+
+    names.push("poster".to_string());
+    names.push("folder".to_string());
+
+    for name in names {
+        for ext in SIDECAR_THUMBNAIL_EXTENSIONS {
+            let candidate = dir.join(name.clone() + "." + ext);
+
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
+    }
+
+    None
+}
+
+fn parse_nfo(nfo_str: &str, nfo_file: &Path) -> Result<SidecarMetadata, String> {
+    let doc = roxmltree::Document::parse(nfo_str).map_err(|e| e.to_string())?;
+
+    let root = doc.root_element();
+
+    let get_texts = |name: &str| -> Vec<String> {
+        root.children()
+            .filter(|n| n.has_tag_name(name))
+            .filter_map(|n| n.text())
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect()
+    };
+
+    let get_text = |name: &str| -> Option<String> { get_texts(name).into_iter().next() };
+
+    let plot = get_text("plot");
+    let outline = get_text("outline");
+
+    // The outline is the short description. If both are present, the plot goes to the extended description.
+
+    let (description, long_plot) = match (outline, plot) {
+        (Some(o), Some(p)) if o != p => (Some(o), Some(p)),
+        (Some(o), _) => (Some(o), None),
+        (None, p) => (p, None),
+    };
+
+    let mut tags = get_texts("genre");
+    tags.extend(get_texts("tag"));
+
+    // Details
+
+    let mut details: Vec<(&str, String)> = Vec::new();
+
+    if let Some(tagline) = get_text("tagline") {
+        details.push(("Tagline", tagline));
+    }
+
+    let directors = get_texts("director");
+    if !directors.is_empty() {
+        details.push(("Director", directors.join(", ")));
+    }
+
+    let studios = get_texts("studio");
+    if !studios.is_empty() {
+        details.push(("Studio", studios.join(", ")));
+    }
+
+    if let Some(premiered) = get_text("premiered").or(get_text("aired")) {
+        details.push(("Premiered", premiered));
+    } else if let Some(year) = get_text("year") {
+        details.push(("Year", year));
+    }
+
+    // Thumbnail, only if it is a local file
+
+    let nfo_dir = nfo_file.parent().unwrap_or(Path::new(""));
+
+    let thumbnail = get_texts("thumb")
+        .into_iter()
+        .map(|t| nfo_dir.join(t))
+        .find(|t| t.is_file());
+
+    Ok(SidecarMetadata {
+        title: get_text("title"),
+        description,
+        tags: parse_sidecar_tags(tags),
+        time_slices: parse_nfo_chapters(root),
+        ext_desc: make_sidecar_ext_desc(long_plot, details),
+        thumbnail,
+    })
+}
+
+/// Parses the chapters of a NFO file: <chapter> elements, directly in the root or inside <chapters>
+/// The values can be child elements or attributes. The name is taken from name or title,
+/// and the start from start or time (seconds or HH:MM:SS), or from startpositionticks (100 ns units).
+fn parse_nfo_chapters(root: roxmltree::Node) -> Vec<MediaTimeSlice> {
+    root.descendants()
+        .filter(|n| n.has_tag_name("chapter"))
+        .filter(|n| {
+            n.parent_element().is_some_and(|p| {
+                p == root || (p.has_tag_name("chapters") && p.parent() == Some(root))
+            })
+        })
+        .enumerate()
+        .filter_map(|(i, chapter)| {
+            let time = match get_nfo_chapter_value(chapter, &["start", "time"]) {
+                Some(start) => parse_nfo_chapter_time(&start)?,
+                None => {
+                    get_nfo_chapter_value(chapter, &["startpositionticks"])?
+                        .parse::<f64>()
+                        .ok()?
+                        / 10_000_000.0
+                }
+            };
+
+            if !time.is_finite() || time < 0.0 {
+                return None;
+            }
+
+            Some(MediaTimeSlice {
+                time,
+                name: get_nfo_chapter_value(chapter, &["name", "title"])
+                    .unwrap_or_else(|| format!("Chapter {}", i + 1)),
+            })
+        })
+        .collect()
+}
+
+/// Gets the first non empty value of a chapter, from its attributes or its child elements
+fn get_nfo_chapter_value(chapter: roxmltree::Node, names: &[&str]) -> Option<String> {
+    names.iter().find_map(|name| {
+        chapter
+            .attribute(*name)
+            .or_else(|| {
+                chapter
+                    .children()
+                    .find(|n| n.has_tag_name(*name))
+                    .and_then(|n| n.text())
+            })
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    })
+}
+
+/// Parses a chapter time, in seconds or in the HH:MM:SS(.mmm) format
+fn parse_nfo_chapter_time(time_str: &str) -> Option<f64> {
+    let mut time: f64 = 0.0;
+
+    for part in time_str.split(':') {
+        time = time * 60.0 + part.trim().parse::<f64>().ok()?;
+    }
+
+    Some(time)
+}
+
+fn parse_yt_dlp_info(info_str: &str) -> Result<SidecarMetadata, String> {
+    let info: YtDlpInfo = serde_json::from_str(info_str).map_err(|e| e.to_string())?;
+
+    let mut tags = info.categories.unwrap_or_default();
+    tags.extend(info.tags.unwrap_or_default());
+
+    let time_slices: Vec<MediaTimeSlice> = info
+        .chapters
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+        .filter(|(_, c)| c.start_time.is_finite() && c.start_time >= 0.0)
+        .map(|(i, c)| MediaTimeSlice {
+            time: c.start_time,
+            name: c
+                .title
+                .filter(|t| !t.trim().is_empty())
+                .unwrap_or_else(|| format!("Chapter {}", i + 1)),
+        })
+        .collect();
+
+    // Details
+
+    let mut details: Vec<(&str, String)> = Vec::new();
+
+    if let Some(uploader) = info.channel.or(info.uploader) {
+        details.push(("Uploader", uploader));
+    }
+
+    if let Some(upload_date) = info.upload_date {
+        // yt-dlp uses the YYYYMMDD format
+        if upload_date.len() == 8 && upload_date.chars().all(|c| c.is_ascii_digit()) {
+            let year = &upload_date[0..4];
+            let month = &upload_date[4..6];
+            let day = &upload_date[6..8];
+            details.push(("Upload date", format!("{year}-{month}-{day}")));
+        } else {
+            details.push(("Upload date", upload_date));
+        }
+    }
+
+    if let Some(webpage_url) = info.webpage_url {
+        details.push(("Source", webpage_url));
+    }
+
+    Ok(SidecarMetadata {
+        title: info.title,
+        description: info.description.filter(|d| !d.trim().is_empty()),
+        tags: parse_sidecar_tags(tags),
+        time_slices,
+        ext_desc: make_sidecar_ext_desc(None, details),
+        thumbnail: None,
+    })
+}
+
+fn parse_sidecar_tags(tags: Vec<String>) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+
+    for tag in tags {
+        let tag_name = parse_tag_name(&tag).to_lowercase();
+
+        if !tag_name.is_empty() && !result.contains(&tag_name) {
+            result.push(tag_name);
+        }
+    }
+
+    result
+}
+
+/// Makes the extended description (markdown) from the long text and the details
+fn make_sidecar_ext_desc(text: Option<String>, details: Vec<(&str, String)>) -> Option<String> {
+    let mut parts: Vec<String> = Vec::new();
+
+    if let Some(t) = text {
+        parts.push(t);
+    }
+
+    if !details.is_empty() {
+        let details_lines: Vec<String> = details
+            .iter()
+            .map(|(name, value)| format!("- **{name}**: {value}"))
+            .collect();
+
+        parts.push(details_lines.join("\n"));
+    }
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("\n\n") + "\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_time_slices(metadata: &SidecarMetadata) -> Vec<(f64, String)> {
+        metadata
+            .time_slices
+            .iter()
+            .map(|s| (s.time, s.name.clone()))
+            .collect()
+    }
+
+    fn parse_test_nfo_chapters(nfo_str: &str) -> Vec<(f64, String)> {
+        let doc = roxmltree::Document::parse(nfo_str).unwrap();
+
+        parse_nfo_chapters(doc.root_element())
+            .into_iter()
+            .map(|s| (s.time, s.name))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_nfo() {
+        let nfo_str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<movie>
+    <title> Big Buck Bunny </title>
+    <outline>A short story</outline>
+    <plot>A long story about a rabbit</plot>
+    <tagline>Big rabbit</tagline>
+    <genre>Animation</genre>
+    <genre>Comedy Short</genre>
+    <tag>animation</tag>
+    <director>Sacha Goedegebure</director>
+    <studio>Blender Foundation</studio>
+    <studio>Blender Institute</studio>
+    <year>2008</year>
+    <thumb>missing-poster.jpg</thumb>
+    <chapter name="Intro" start="0" />
+</movie>"#;
+
+        let metadata = parse_nfo(nfo_str, Path::new("/nonexistent/movie.nfo")).unwrap();
+
+        assert_eq!(metadata.title, Some("Big Buck Bunny".to_string()));
+        assert_eq!(metadata.description, Some("A short story".to_string()));
+        assert_eq!(metadata.tags, vec!["animation", "comedy_short"]);
+        assert_eq!(
+            metadata.ext_desc,
+            Some(
+                "A long story about a rabbit\n\n\
+                - **Tagline**: Big rabbit\n\
+                - **Director**: Sacha Goedegebure\n\
+                - **Studio**: Blender Foundation, Blender Institute\n\
+                - **Year**: 2008\n"
+                    .to_string()
+            )
+        );
+        assert_eq!(get_time_slices(&metadata), vec![(0.0, "Intro".to_string())]);
+        assert!(metadata.thumbnail.is_none());
+
+        // Only the plot: it is the description, with no extended description
+
+        let metadata = parse_nfo(
+            "<episodedetails><plot>Only plot</plot><premiered>2020-01-02</premiered><year>2020</year></episodedetails>",
+            Path::new("episode.nfo"),
+        )
+        .unwrap();
+
+        assert_eq!(metadata.title, None);
+        assert_eq!(metadata.description, Some("Only plot".to_string()));
+        assert_eq!(
+            metadata.ext_desc,
+            Some("- **Premiered**: 2020-01-02\n".to_string())
+        );
+
+        assert!(parse_nfo("<movie>", Path::new("movie.nfo")).is_err());
+    }
+
+    #[test]
+    fn test_parse_nfo_chapters() {
+        // Attributes and child elements, inside <chapters>
+
+        assert_eq!(
+            parse_test_nfo_chapters(
+                r#"<movie>
+    <chapters>
+        <chapter name="Intro" start="0" />
+        <chapter><title>Middle</title><time>00:01:30.5</time></chapter>
+        <chapter start="120" />
+    </chapters>
+</movie>"#
+            ),
+            vec![
+                (0.0, "Intro".to_string()),
+                (90.5, "Middle".to_string()),
+                (120.0, "Chapter 3".to_string()),
+            ]
+        );
+
+        // startpositionticks (100 ns units), directly in the root
+
+        assert_eq!(
+            parse_test_nfo_chapters(
+                r#"<movie>
+    <chapter><name>Start</name><startpositionticks>0</startpositionticks></chapter>
+    <chapter><name>End</name><startpositionticks>6000000000</startpositionticks></chapter>
+</movie>"#
+            ),
+            vec![(0.0, "Start".to_string()), (600.0, "End".to_string())]
+        );
+
+        // Invalid times and nested chapters of other elements are ignored
+
+        assert_eq!(
+            parse_test_nfo_chapters(
+                r#"<movie>
+    <chapter name="Bad" start="abc" />
+    <chapter name="Negative" start="-5" />
+    <chapter name="No start" />
+    <actor><chapter name="Nested" start="10" /></actor>
+    <chapter name="Good" start="01:00" />
+</movie>"#
+            ),
+            vec![(60.0, "Good".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_nfo_chapter_time() {
+        assert_eq!(parse_nfo_chapter_time("0"), Some(0.0));
+        assert_eq!(parse_nfo_chapter_time("12.5"), Some(12.5));
+        assert_eq!(parse_nfo_chapter_time("02:03"), Some(123.0));
+        assert_eq!(parse_nfo_chapter_time("01:02:03.250"), Some(3723.25));
+        assert_eq!(parse_nfo_chapter_time(" 1 : 00 "), Some(60.0));
+        assert_eq!(parse_nfo_chapter_time(""), None);
+        assert_eq!(parse_nfo_chapter_time("1:xx"), None);
+    }
+
+    #[test]
+    fn test_parse_yt_dlp_info() {
+        let info_str = r#"{
+    "title": "Some video",
+    "description": "  ",
+    "categories": ["Music"],
+    "tags": ["live concert", "music"],
+    "chapters": [
+        {"start_time": 0.0, "title": "Intro"},
+        {"start_time": 75.5, "title": " "},
+        {"start_time": -1.0, "title": "Invalid"}
+    ],
+    "uploader": "Uploader name",
+    "channel": "Channel name",
+    "upload_date": "20240131",
+    "webpage_url": "https://example.com/watch?v=1"
+}"#;
+
+        let metadata = parse_yt_dlp_info(info_str).unwrap();
+
+        assert_eq!(metadata.title, Some("Some video".to_string()));
+        assert_eq!(metadata.description, None);
+        assert_eq!(metadata.tags, vec!["music", "live_concert"]);
+        assert_eq!(
+            get_time_slices(&metadata),
+            vec![(0.0, "Intro".to_string()), (75.5, "Chapter 2".to_string())]
+        );
+        assert_eq!(
+            metadata.ext_desc,
+            Some(
+                "- **Uploader**: Channel name\n\
+                - **Upload date**: 2024-01-31\n\
+                - **Source**: https://example.com/watch?v=1\n"
+                    .to_string()
+            )
+        );
+
+        // Upload dates not in the YYYYMMDD format are kept as they are
+
+        let metadata =
+            parse_yt_dlp_info(r#"{"uploader": "Someone", "upload_date": "2024-01-31"}"#).unwrap();
+
+        assert_eq!(
+            metadata.ext_desc,
+            Some("- **Uploader**: Someone\n- **Upload date**: 2024-01-31\n".to_string())
+        );
+
+        assert_eq!(parse_yt_dlp_info("{}").unwrap().ext_desc, None);
+        assert!(parse_yt_dlp_info("not json").is_err());
+    }
+
+    #[test]
+    fn test_parse_sidecar_tags() {
+        assert_eq!(
+            parse_sidecar_tags(vec![
+                " Science Fiction ".to_string(),
+                "science fiction".to_string(),
+                "Drama".to_string(),
+                "".to_string(),
+                "  ".to_string(),
+                "multi\nline".to_string(),
+            ]),
+            vec!["science_fiction", "drama", "multi_line"]
+        );
+    }
+}
@@ -0,0 +1,32 @@
+// Temporary folder utilities
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static TEMP_FOLDER_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Gets the path for a new temporary folder, unique for the current process
+pub fn get_temp_folder_path(prefix: &str) -> String {
+    let pid = std::process::id();
+    let counter = TEMP_FOLDER_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let timestamp = chrono::Utc::now().timestamp_millis();
+
+    std::env::temp_dir()
+        .join(format!("pmv-cli-{prefix}-{pid}-{timestamp}-{counter}"))
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+/// Removes a temporary folder and its contents, printing a warning on failure
+pub async fn remove_temp_folder(path: &str) {
+    if !std::path::Path::new(path).exists() {
+        return;
+    }
+
+    let remove_res = tokio::fs::remove_dir_all(path).await;
+
+    if let Err(e) = remove_res {
+        let e_str = e.to_string();
+        eprintln!("Warning: Could not remove temporary folder {path}. Error: {e_str}");
+    }
+}
@@ -0,0 +1,165 @@
+// Text matching for search filters
+
+use regex::{Regex, RegexBuilder};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Text matching mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextMatchMode {
+    /// Case-insensitive substring
+    Insensitive,
+    /// Case-insensitive regular expression
+    Regex,
+    /// Case-insensitive substring, ignoring accents and repeated whitespace
+    Fuzzy,
+}
+
+pub fn get_text_match_mode(regex: bool, fuzzy: bool) -> TextMatchMode {
+    if regex {
+        TextMatchMode::Regex
+    } else if fuzzy {
+        TextMatchMode::Fuzzy
+    } else {
+        TextMatchMode::Insensitive
+    }
+}
+
+/// Compiled text filter
+#[derive(Debug, Clone)]
+pub enum TextFilter {
+    Insensitive(String),
+    Regex(Regex),
+    Fuzzy(String),
+}
+
+impl TextFilter {
+    pub fn new(pattern: &str, mode: TextMatchMode) -> Result<TextFilter, String> {
+        match mode {
+            TextMatchMode::Insensitive => Ok(TextFilter::Insensitive(pattern.to_lowercase())),
+            TextMatchMode::Regex => match RegexBuilder::new(pattern).case_insensitive(true).build()
+            {
+                Ok(r) => Ok(TextFilter::Regex(r)),
+                Err(e) => Err(format!("Invalid regular expression: {pattern} | {e}")),
+            },
+            TextMatchMode::Fuzzy => Ok(TextFilter::Fuzzy(normalize_text_fuzzy(pattern))),
+        }
+    }
+
+    /// Checks if a text matches the filter
+    pub fn matches(&self, text: &str) -> bool {
+        match self {
+            TextFilter::Insensitive(p) => text.to_lowercase().contains(p),
+            TextFilter::Regex(r) => r.is_match(text),
+            TextFilter::Fuzzy(p) => normalize_text_fuzzy(text).contains(p),
+        }
+    }
+}
+
+/// Creates a text filter from an optional command argument
+/// Empty strings mean no filter
+pub fn get_text_filter(
+    pattern: Option<String>,
+    mode: TextMatchMode,
+) -> Result<Option<TextFilter>, String> {
+    match pattern {
+        Some(p) => {
+            if p.is_empty() {
+                Ok(None)
+            } else {
+                Ok(Some(TextFilter::new(&p, mode)?))
+            }
+        }
+        None => Ok(None),
+    }
+}
+
+/// Normalizes a text for fuzzy matching:
+/// lowercase, without accents or other combining marks, and with collapsed whitespace
+pub fn normalize_text_fuzzy(text: &str) -> String {
+    let folded: String = text
+        .nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .collect::<String>()
+        .to_lowercase();
+
+    folded.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_text_match_mode() {
+        assert_eq!(
+            get_text_match_mode(false, false),
+            TextMatchMode::Insensitive
+        );
+        assert_eq!(get_text_match_mode(true, false), TextMatchMode::Regex);
+        assert_eq!(get_text_match_mode(false, true), TextMatchMode::Fuzzy);
+        assert_eq!(get_text_match_mode(true, true), TextMatchMode::Regex);
+    }
+
+    #[test]
+    fn test_insensitive_filter() {
+        let filter = TextFilter::new("Beach", TextMatchMode::Insensitive).unwrap();
+
+        assert!(filter.matches("Summer BEACH day"));
+        assert!(filter.matches("beach"));
+        assert!(!filter.matches("Béach"));
+        assert!(!filter.matches("bea ch"));
+
+        // Special characters are not interpreted
+        let filter = TextFilter::new("a.b", TextMatchMode::Insensitive).unwrap();
+
+        assert!(filter.matches("A.B"));
+        assert!(!filter.matches("axb"));
+    }
+
+    #[test]
+    fn test_regex_filter() {
+        let filter = TextFilter::new("^day [0-9]+$", TextMatchMode::Regex).unwrap();
+
+        assert!(filter.matches("Day 12"));
+        assert!(!filter.matches("Day 12 at the beach"));
+        assert!(!filter.matches("Day x"));
+
+        assert!(TextFilter::new("(unclosed", TextMatchMode::Regex).is_err());
+    }
+
+    #[test]
+    fn test_fuzzy_filter() {
+        let filter = TextFilter::new("cafe  creme", TextMatchMode::Fuzzy).unwrap();
+
+        assert!(filter.matches("Le Café Crème"));
+        assert!(filter.matches("CAFE\tCREME"));
+        assert!(!filter.matches("cafecreme"));
+
+        let filter = TextFilter::new("Ñandú", TextMatchMode::Fuzzy).unwrap();
+
+        assert!(filter.matches("nandu"));
+    }
+
+    #[test]
+    fn test_get_text_filter() {
+        assert!(get_text_filter(None, TextMatchMode::Regex)
+            .unwrap()
+            .is_none());
+        assert!(get_text_filter(Some("".to_string()), TextMatchMode::Regex)
+            .unwrap()
+            .is_none());
+        assert!(get_text_filter(Some("a".to_string()), TextMatchMode::Regex)
+            .unwrap()
+            .is_some());
+        assert!(get_text_filter(Some("[".to_string()), TextMatchMode::Regex).is_err());
+    }
+
+    #[test]
+    fn test_normalize_text_fuzzy() {
+        assert_eq!(normalize_text_fuzzy("Crème Brûlée"), "creme brulee");
+        assert_eq!(normalize_text_fuzzy("  a \t b\n\nc  "), "a b c");
+        assert_eq!(normalize_text_fuzzy("ÅNGSTRÖM"), "angstrom");
+        assert_eq!(normalize_text_fuzzy("ﬁle"), "file");
+        assert_eq!(normalize_text_fuzzy(""), "");
+    }
+}
@@ -0,0 +1,130 @@
+// Local undo journal
+
+use std::{fs::OpenOptions, io::Write, path::PathBuf};
+
+use chrono::Utc;
+
+use crate::models::{UndoChange, UndoEntry};
+
+use super::get_local_config_path;
+
+/// Gets the path of the undo journal
+/// It is stored as undo.jsonl, in the same folder as the local configuration file
+pub fn get_undo_journal_path() -> Result<PathBuf, String> {
+    let config_path = get_local_config_path()?;
+    Ok(config_path.with_file_name("undo.jsonl"))
+}
+
+/// Reads the entries of the undo journal, from the oldest to the newest
+/// If the file does not exist, the journal is empty
+pub fn read_undo_journal() -> Result<Vec<UndoEntry>, String> {
+    let path = get_undo_journal_path()?;
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let path_str = path.to_string_lossy().to_string();
+
+    let content = match std::fs::read_to_string(&path) {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(format!("Could not read {path_str}: {e}"));
+        }
+    };
+
+    let mut entries: Vec<UndoEntry> = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str::<UndoEntry>(line) {
+            Ok(entry) => {
+                entries.push(entry);
+            }
+            Err(e) => {
+                let line_number = i + 1;
+                return Err(format!(
+                    "Invalid undo journal {path_str} at line {line_number}: {e}"
+                ));
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Writes the full undo journal, replacing the existing one
+/// The content is written into a temporary file first, and then renamed,
+/// so the journal is never left half written
+pub fn write_undo_journal(entries: &[UndoEntry]) -> Result<(), String> {
+    let path = get_undo_journal_path()?;
+    let path_str = path.to_string_lossy().to_string();
+
+    let temp_path = path.with_file_name("undo.jsonl.tmp");
+    let temp_path_str = temp_path.to_string_lossy().to_string();
+
+    let mut content = String::new();
+
+    for entry in entries {
+        content.push_str(&serde_json::to_string(entry).unwrap());
+        content.push('\n');
+    }
+
+    if let Err(e) = std::fs::write(&temp_path, content) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(format!("Could not write {temp_path_str}: {e}"));
+    }
+
+    match std::fs::rename(&temp_path, &path) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp_path);
+            Err(format!("Could not write {path_str}: {e}"))
+        }
+    }
+}
+
+/// Appends an entry to the undo journal, creating the folder if needed
+/// Returns the appended entry
+pub fn append_undo_entry(
+    vault: &str,
+    operation: &str,
+    changes: Vec<UndoChange>,
+) -> Result<UndoEntry, String> {
+    let entries = read_undo_journal()?;
+
+    let entry = UndoEntry {
+        id: entries.iter().map(|e| e.id).max().unwrap_or(0) + 1,
+        timestamp: Utc::now().timestamp_millis(),
+        vault: vault.to_string(),
+        operation: operation.to_string(),
+        changes,
+        undone: false,
+    };
+
+    let path = get_undo_journal_path()?;
+    let path_str = path.to_string_lossy().to_string();
+
+    if let Some(parent) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            return Err(format!("Could not create the folder for {path_str}: {e}"));
+        }
+    }
+
+    let mut file = match OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(f) => f,
+        Err(e) => {
+            return Err(format!("Could not open {path_str}: {e}"));
+        }
+    };
+
+    let line = serde_json::to_string(&entry).unwrap();
+
+    match writeln!(file, "{line}") {
+        Ok(_) => Ok(entry),
+        Err(e) => Err(format!("Could not write {path_str}: {e}")),
+    }
+}